@@ -0,0 +1,13 @@
+-- Every launch from the UI is recorded here so search can
+-- rank items by how often and how recently they were
+-- opened. Rows reference items by item_id + kind
+-- rather than by items.id since rescans delete
+-- and re-insert rows in the items table.
+create table if not exists launches (
+    id integer primary key,
+    item_id text not null,
+    kind text not null,
+    launched_at integer not null
+);
+
+create index if not exists idx_launches_item_id_kind on launches(item_id,kind);
@@ -0,0 +1,12 @@
+-- Calculator results that were copied from the launcher,
+-- most recent first. An expression is stored once and
+-- bumped to the top when it's calculated again.
+create table if not exists calculations (
+    id integer primary key,
+    expression text not null unique,
+    result text not null,
+    display text not null,
+    calculated_at integer not null
+);
+
+create index if not exists idx_calculations_calculated_at on calculations(calculated_at);
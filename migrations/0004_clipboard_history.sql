@@ -0,0 +1,13 @@
+-- Everything copied while clipboard history is enabled.
+-- Copying the same contents again moves the entry to
+-- the top instead of adding a new one. preview is a
+-- single line shown and searched in the launcher.
+create table if not exists clipboard_entries (
+    id integer primary key,
+    mime_type text not null,
+    data blob not null,
+    preview text not null,
+    copied_at integer not null
+);
+
+create index if not exists idx_clipboard_entries_copied_at on clipboard_entries(copied_at);
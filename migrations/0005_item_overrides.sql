@@ -0,0 +1,98 @@
+-- Pins, hides and aliases set by the user. Like launches,
+-- rows reference items by item_id + kind so they outlive
+-- the items rows a rescan deletes and re-inserts.
+create table if not exists item_overrides (
+    item_id text not null,
+    kind text not null,
+    -- When the item was pinned, null when it isn't
+    pinned_at integer,
+    hidden integer not null default 0,
+    -- One alias per line
+    aliases text not null default '',
+
+    primary key(item_id, kind)
+);
+
+-- Aliases are searched along with titles and descriptions,
+-- so the search index is rebuilt on top of a view that
+-- joins them in.
+drop trigger if exists items_ai;
+drop trigger if exists items_ad;
+drop trigger if exists items_au;
+drop table if exists items_fts;
+
+create view if not exists items_search as
+select
+    items.id,
+    items.title,
+    items.description,
+    coalesce(o.aliases, '') as aliases
+from items
+left join item_overrides o on o.item_id = items.item_id and o.kind = items.kind;
+
+CREATE VIRTUAL TABLE IF NOT EXISTS items_fts USING fts5(
+  title,
+  description,
+  aliases,
+  content='items_search',
+  content_rowid='id'
+);
+
+-- Rows are removed from the index with the 'delete' command,
+-- which needs the exact values that were indexed. The
+-- content row is already gone by the time these run.
+CREATE TRIGGER IF NOT EXISTS items_ai AFTER INSERT ON items BEGIN
+  INSERT INTO items_fts(rowid, title, description, aliases)
+  SELECT id, title, description, aliases FROM items_search WHERE id = new.id;
+END;
+
+CREATE TRIGGER IF NOT EXISTS items_ad AFTER DELETE ON items BEGIN
+  INSERT INTO items_fts(items_fts, rowid, title, description, aliases)
+  VALUES ('delete', old.id, old.title, old.description, coalesce(
+    (SELECT aliases FROM item_overrides WHERE item_id = old.item_id AND kind = old.kind),
+    ''
+  ));
+END;
+
+CREATE TRIGGER IF NOT EXISTS items_au AFTER UPDATE ON items BEGIN
+  INSERT INTO items_fts(items_fts, rowid, title, description, aliases)
+  VALUES ('delete', old.id, old.title, old.description, coalesce(
+    (SELECT aliases FROM item_overrides WHERE item_id = old.item_id AND kind = old.kind),
+    ''
+  ));
+
+  INSERT INTO items_fts(rowid, title, description, aliases)
+  SELECT id, title, description, aliases FROM items_search WHERE id = new.id;
+END;
+
+CREATE TRIGGER IF NOT EXISTS item_overrides_ai AFTER INSERT ON item_overrides BEGIN
+  INSERT INTO items_fts(items_fts, rowid, title, description, aliases)
+  SELECT 'delete', id, title, description, '' FROM items
+  WHERE item_id = new.item_id AND kind = new.kind;
+
+  INSERT INTO items_fts(rowid, title, description, aliases)
+  SELECT id, title, description, new.aliases FROM items
+  WHERE item_id = new.item_id AND kind = new.kind;
+END;
+
+CREATE TRIGGER IF NOT EXISTS item_overrides_au AFTER UPDATE OF aliases ON item_overrides BEGIN
+  INSERT INTO items_fts(items_fts, rowid, title, description, aliases)
+  SELECT 'delete', id, title, description, old.aliases FROM items
+  WHERE item_id = old.item_id AND kind = old.kind;
+
+  INSERT INTO items_fts(rowid, title, description, aliases)
+  SELECT id, title, description, new.aliases FROM items
+  WHERE item_id = new.item_id AND kind = new.kind;
+END;
+
+CREATE TRIGGER IF NOT EXISTS item_overrides_ad AFTER DELETE ON item_overrides BEGIN
+  INSERT INTO items_fts(items_fts, rowid, title, description, aliases)
+  SELECT 'delete', id, title, description, old.aliases FROM items
+  WHERE item_id = old.item_id AND kind = old.kind;
+
+  INSERT INTO items_fts(rowid, title, description, aliases)
+  SELECT id, title, description, '' FROM items
+  WHERE item_id = old.item_id AND kind = old.kind;
+END;
+
+INSERT INTO items_fts(items_fts) VALUES ('rebuild');
@@ -0,0 +1,11 @@
+-- What desktop entries say about apps besides the name and
+-- comment kept in items. Replaced whole on every app scan,
+-- and looked up by item_id when searching apps. Lists are
+-- stored one value per line.
+create table if not exists app_details (
+    item_id text primary key not null,
+    localized_name text,
+    generic_name text,
+    keywords text not null default '',
+    categories text not null default ''
+);
@@ -0,0 +1,2 @@
+-- How each app was installed: native, flatpak, snap or appimage
+alter table app_details add column source text not null default 'native';
@@ -8,11 +8,14 @@ use thiserror::Error;
 use tracing::info;
 
 use crate::{
-    core::config::AppConfig,
+    core::{
+        SearchMode,
+        config::{AppConfig, OutputTarget},
+    },
     daemon::WaycastDaemon,
     facade::{self, WaycastFacade},
     socket::WaycastSocketListener,
-    ui::WaycastUi,
+    ui::UiHandle,
 };
 
 #[derive(Debug, Error)]
@@ -52,7 +55,11 @@ pub struct WaycastApplication {
 
 #[derive(Debug)]
 pub enum AppMessage {
-    Show,
+    /// Show the UI, on the given output or else the configured one, and
+    /// optionally searching in a mode
+    Show(Option<OutputTarget>, Option<SearchMode>),
+    Hide,
+    Toggle(Option<OutputTarget>, Option<SearchMode>),
     /// Ping the daemon and check if it's running
     Ping,
     Rescan,
@@ -103,12 +110,15 @@ impl WaycastApplication {
         // Central place to listen and act on app messages
         let _message_listener_thread = std::thread::spawn(move || {
             info!("Starting app message central listener");
+            let mut ui = UiHandle::new(self.waycast.clone());
+            ui.start();
+
             for cmd in &self.message_channel {
                 info!("Received app message {:#?}", cmd);
                 match cmd {
-                    AppMessage::Show => {
-                        Self::show_ui(self.waycast.clone());
-                    }
+                    AppMessage::Show(output, mode) => ui.show(output, mode),
+                    AppMessage::Hide => ui.hide(),
+                    AppMessage::Toggle(output, mode) => ui.toggle(output, mode),
                     AppMessage::Ping => {
                         info!("Received ping");
                     }
@@ -124,18 +134,6 @@ impl WaycastApplication {
         }
     }
 
-    // NOTE: It is assumed that this will be called from within
-    // the long running waycast process. If this were to
-    // be called normally, the thread would just be
-    // immediately dropped and not do anything.
-    fn show_ui(waycast: Arc<WaycastFacade>) {
-        std::thread::spawn(move || {
-            info!("Launching UI");
-            let _ = WaycastUi::run(waycast);
-            info!("App exited");
-        });
-    }
-
     /// Get the single instance lock to make sure we don't start
     /// two daemon processes.
     /// An error creating the file does not mean another instance is
@@ -9,9 +9,11 @@ use tracing::{error, info};
 use crate::{
     app::{AppError, WaycastApplication},
     core::{
-        config::AppConfig,
+        SearchMode,
+        config::{AppConfig, OutputTarget},
         data::{DataError, WaycastData},
     },
+    facade::{WaycastError, WaycastFacade},
     socket::{SocketError, WaycastSocketClient},
 };
 
@@ -27,6 +29,17 @@ pub enum StartupError {
     DataError(#[from] DataError),
     #[error("Could not render the configuration: {0}")]
     ConfigRender(#[from] toml::ser::Error),
+    #[error(transparent)]
+    Waycast(#[from] WaycastError),
+    #[error("No item with id {0}, see `waycast search --explain` for ids")]
+    UnknownItem(String),
+}
+
+/// What `item_command` changes about an item.
+pub enum ItemChange {
+    Pinned(bool),
+    Hidden(bool),
+    Aliases(Vec<String>),
 }
 
 pub fn config_command(cfg: &AppConfig) -> Result<(), StartupError> {
@@ -82,9 +95,33 @@ fn highlight(rendered: &str) -> Option<()> {
     None
 }
 
-pub fn show_ui_command(socket_file: PathBuf) -> Result<(), StartupError> {
+pub fn show_ui_command(
+    socket_file: PathBuf,
+    output: Option<OutputTarget>,
+    mode: Option<SearchMode>,
+) -> Result<(), StartupError> {
+    let mut client = WaycastSocketClient::new(socket_file)?;
+    client.send_show(output, mode)?;
+    client.close();
+
+    Ok(())
+}
+
+pub fn hide_ui_command(socket_file: PathBuf) -> Result<(), StartupError> {
+    let mut client = WaycastSocketClient::new(socket_file)?;
+    client.send_hide()?;
+    client.close();
+
+    Ok(())
+}
+
+pub fn toggle_ui_command(
+    socket_file: PathBuf,
+    output: Option<OutputTarget>,
+    mode: Option<SearchMode>,
+) -> Result<(), StartupError> {
     let mut client = WaycastSocketClient::new(socket_file)?;
-    client.send_show()?;
+    client.send_toggle(output, mode)?;
     client.close();
 
     Ok(())
@@ -142,3 +179,115 @@ pub fn cache_clear_command(database_file: PathBuf) -> Result<(), StartupError> {
         Ok(())
     })
 }
+
+/// Pin, hide or alias every item with `id`. A file can also be a recent
+/// file, for one, and both should change.
+pub fn item_command(
+    database_file: PathBuf,
+    id: String,
+    change: ItemChange,
+) -> Result<(), StartupError> {
+    let rt = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .map_err(|_| StartupError::TokioRuntimeFailed)?;
+
+    rt.block_on(async {
+        let overrides = WaycastData::writeable_connection(database_file)
+            .await?
+            .overrides();
+        let kinds = overrides.kinds_of(&id).await?;
+        if kinds.is_empty() {
+            return Err(StartupError::UnknownItem(id));
+        }
+
+        for kind in kinds {
+            match &change {
+                ItemChange::Pinned(pinned) => overrides.set_pinned(&id, kind, *pinned).await?,
+                ItemChange::Hidden(hidden) => overrides.set_hidden(&id, kind, *hidden).await?,
+                ItemChange::Aliases(aliases) => overrides.set_aliases(&id, kind, aliases).await?,
+            }
+        }
+
+        Ok(())
+    })
+}
+
+/// Print every item with an override and what it is.
+pub fn item_list_command(database_file: PathBuf) -> Result<(), StartupError> {
+    let rt = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .map_err(|_| StartupError::TokioRuntimeFailed)?;
+
+    let mut overrides: Vec<_> = rt
+        .block_on(async {
+            WaycastData::writeable_connection(database_file)
+                .await?
+                .overrides()
+                .all()
+                .await
+        })?
+        .into_iter()
+        .collect();
+    overrides.sort_by(|(a, _), (b, _)| a.0.cmp(&b.0));
+
+    if overrides.is_empty() {
+        println!("No pinned, hidden or aliased items");
+        return Ok(());
+    }
+
+    for ((id, kind), item_override) in overrides {
+        let mut notes = Vec::new();
+        if item_override.pinned_at.is_some() {
+            notes.push(String::from("pinned"));
+        }
+        if item_override.hidden {
+            notes.push(String::from("hidden"));
+        }
+        if !item_override.aliases.is_empty() {
+            notes.push(format!("aliases {}", item_override.aliases.join(", ")));
+        }
+        println!("{id} [{kind:?}] {}", notes.join(", "));
+    }
+
+    Ok(())
+}
+
+/// Run a search the same way the launcher does and print the results. With
+/// `explain`, each result also shows how its score was put together.
+pub fn search_command(cfg: AppConfig, query: String, explain: bool) -> Result<(), StartupError> {
+    let rt = tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()
+        .map_err(|_| StartupError::TokioRuntimeFailed)?;
+
+    let waycast = WaycastFacade::new(cfg, rt.handle().clone())?;
+    let results = rt.block_on(waycast.search(query))?;
+
+    if results.is_empty() {
+        println!("No results");
+        return Ok(());
+    }
+
+    for (rank, result) in results.iter().enumerate() {
+        let score = &result.score;
+        println!(
+            "{:>2}. {} [{:?}] {:.2}",
+            rank + 1,
+            result.item.title,
+            result.item.kind,
+            score.total()
+        );
+
+        if explain {
+            println!(
+                "    fuzzy {:.2} x kind weight {:.2} + frecency {:.2} + recency {:.2}",
+                score.fuzzy, score.kind_weight, score.frecency, score.recency
+            );
+            println!("    {}", result.item.id);
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,221 @@
+//! Everything that can be done with a result, for the action panel. Each
+//! kind of item supplies its own actions, so the UI only lists them and
+//! hands the picked one back to the facade.
+
+use std::path::Path;
+
+use super::config::ProjectCommand;
+use super::{ItemKind, LauncherItem, launcher};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Action {
+    pub title: String,
+    pub kind: ActionKind,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ActionKind {
+    /// Whatever Enter does for the item
+    Default,
+    /// Start another instance of an app, even when one is running
+    NewInstance,
+    /// Open the item with the app with this desktop entry id
+    OpenWith(String),
+    /// Show the item in the file manager
+    Reveal,
+    /// Copy the item's path or URL
+    CopyPath,
+    MoveToTrash,
+    /// Run a command, with `{path}` replaced by the item's path
+    Command(String),
+    /// Show the item first when nothing has been typed yet
+    Pin,
+    Unpin,
+    /// Leave the item out of every result
+    Hide,
+}
+
+impl Action {
+    fn new(title: impl Into<String>, kind: ActionKind) -> Self {
+        Self {
+            title: title.into(),
+            kind,
+        }
+    }
+}
+
+/// The actions for `item`, starting with the default one. `pinned` says
+/// whether to offer unpinning it rather than pinning it.
+pub fn for_item(
+    item: &LauncherItem,
+    project_commands: &[ProjectCommand],
+    pinned: bool,
+) -> Vec<Action> {
+    let mut actions = match item.kind {
+        ItemKind::DesktopEntry => app_actions(),
+        ItemKind::File | ItemKind::RecentFile => file_actions(Path::new(&item.id)),
+        ItemKind::Project => project_actions(project_commands),
+        ItemKind::Bookmark => vec![
+            Action::new("Open", ActionKind::Default),
+            Action::new("Copy link", ActionKind::CopyPath),
+        ],
+        kind => vec![Action::new(default_title(kind), ActionKind::Default)],
+    };
+
+    if is_indexed(item.kind) {
+        actions.push(if pinned {
+            Action::new("Unpin", ActionKind::Unpin)
+        } else {
+            Action::new("Pin to top", ActionKind::Pin)
+        });
+        actions.push(Action::new("Hide", ActionKind::Hide));
+    }
+
+    actions
+}
+
+/// Whether items of `kind` are kept in the database, which is where pinned
+/// items are shown from.
+fn is_indexed(kind: ItemKind) -> bool {
+    matches!(
+        kind,
+        ItemKind::DesktopEntry
+            | ItemKind::File
+            | ItemKind::RecentFile
+            | ItemKind::Project
+            | ItemKind::Bookmark
+            | ItemKind::SshHost
+            | ItemKind::Custom
+            | ItemKind::Executable
+    )
+}
+
+fn app_actions() -> Vec<Action> {
+    vec![
+        Action::new("Launch", ActionKind::Default),
+        Action::new("Launch new instance", ActionKind::NewInstance),
+    ]
+}
+
+fn file_actions(path: &Path) -> Vec<Action> {
+    let open_with = launcher::apps_for_path(path)
+        .into_iter()
+        .map(|(id, name)| Action::new(format!("Open with {name}"), ActionKind::OpenWith(id)));
+
+    [Action::new("Open", ActionKind::Default)]
+        .into_iter()
+        .chain(open_with)
+        .chain([
+            Action::new("Show in file manager", ActionKind::Reveal),
+            Action::new("Copy path", ActionKind::CopyPath),
+            Action::new("Move to trash", ActionKind::MoveToTrash),
+        ])
+        .collect()
+}
+
+fn project_actions(commands: &[ProjectCommand]) -> Vec<Action> {
+    let commands = commands
+        .iter()
+        .map(|command| Action::new(&command.title, ActionKind::Command(command.command.clone())));
+
+    [Action::new("Open", ActionKind::Default)]
+        .into_iter()
+        .chain(commands)
+        .chain([
+            Action::new("Show in file manager", ActionKind::Reveal),
+            Action::new("Copy path", ActionKind::CopyPath),
+        ])
+        .collect()
+}
+
+fn default_title(kind: ItemKind) -> &'static str {
+    match kind {
+        ItemKind::DesktopEntry => "Launch",
+        ItemKind::Calculator | ItemKind::Clipboard => "Copy",
+        ItemKind::Emoji => "Pick",
+        ItemKind::Window => "Switch to",
+        ItemKind::SshHost => "Connect",
+        ItemKind::Command | ItemKind::TerminalCommand | ItemKind::Executable | ItemKind::Custom => {
+            "Run"
+        }
+        ItemKind::Plugin => "Activate",
+        ItemKind::File
+        | ItemKind::Project
+        | ItemKind::Bookmark
+        | ItemKind::RecentFile
+        | ItemKind::Unknown => "Open",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn offers_launching_pinning_and_hiding_apps() {
+        let app = LauncherItem {
+            id: String::from("org.mozilla.firefox"),
+            kind: ItemKind::DesktopEntry,
+            title: String::from("Firefox"),
+            description: None,
+            icon: String::from("firefox"),
+        };
+        let kinds = |pinned| -> Vec<ActionKind> {
+            for_item(&app, &[], pinned)
+                .into_iter()
+                .map(|action| action.kind)
+                .collect()
+        };
+
+        assert_eq!(
+            kinds(false),
+            [
+                ActionKind::Default,
+                ActionKind::NewInstance,
+                ActionKind::Pin,
+                ActionKind::Hide
+            ]
+        );
+        assert_eq!(
+            kinds(true),
+            [
+                ActionKind::Default,
+                ActionKind::NewInstance,
+                ActionKind::Unpin,
+                ActionKind::Hide
+            ]
+        );
+    }
+
+    #[test]
+    fn offers_project_commands_after_opening() {
+        let project = LauncherItem {
+            id: String::from("/home/me/code/waycast"),
+            kind: ItemKind::Project,
+            title: String::from("waycast"),
+            description: None,
+            icon: String::from("folder"),
+        };
+        let commands = [ProjectCommand {
+            title: String::from("Open in terminal"),
+            command: String::from("foot -D {path}"),
+        }];
+
+        let titles: Vec<String> = for_item(&project, &commands, false)
+            .into_iter()
+            .map(|action| action.title)
+            .collect();
+
+        assert_eq!(
+            titles,
+            [
+                "Open",
+                "Open in terminal",
+                "Show in file manager",
+                "Copy path",
+                "Pin to top",
+                "Hide"
+            ]
+        );
+    }
+}
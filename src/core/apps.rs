@@ -0,0 +1,338 @@
+//! What desktop entries say about apps besides their name and comment, so
+//! "browser" finds Firefox and `cat:Graphics` lists the image editors, and
+//! where the apps were installed from.
+
+use std::collections::{BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+
+use crate::core::LauncherItem;
+
+/// Starts a search of the apps in one category, like `cat:Graphics`.
+pub const CATEGORY_PREFIX: &str = "cat:";
+
+/// How an app was installed, going by the directory its desktop entry is in.
+/// Sources are ordered by preference for when the same app comes from more
+/// than one.
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, sqlx::Type,
+)]
+#[sqlx(type_name = "TEXT", rename_all = "lowercase")]
+pub enum AppSource {
+    /// The distribution's packages, or anything installed by hand
+    #[default]
+    Native,
+    Flatpak,
+    Snap,
+    AppImage,
+}
+
+impl AppSource {
+    /// Where the entries in `dir` come from. AppImages have no directory of
+    /// their own to go by, so their extracted entries are tagged by whoever
+    /// extracted them.
+    pub fn of_directory(dir: &Path) -> Self {
+        if dir.ends_with("flatpak/exports/share/applications") {
+            Self::Flatpak
+        } else if dir.starts_with("/var/lib/snapd") {
+            Self::Snap
+        } else {
+            Self::Native
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Native => "Native",
+            Self::Flatpak => "Flatpak",
+            Self::Snap => "Snap",
+            Self::AppImage => "AppImage",
+        }
+    }
+}
+
+/// A directory of desktop entries, and how the apps in it were installed.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct AppDirectory {
+    pub path: PathBuf,
+    pub source: AppSource,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AppDetails {
+    /// Desktop entry id, same as the app's item id
+    pub id: String,
+    /// `Name` in the user's language, when it has a translation
+    pub localized_name: Option<String>,
+    /// What kind of app it is, like "Web Browser"
+    pub generic_name: Option<String>,
+    pub keywords: Vec<String>,
+    pub categories: Vec<String>,
+    pub source: AppSource,
+}
+
+impl AppDetails {
+    /// Read the entry at `path`. Translated values follow the locale the
+    /// way GLib does, from `LANGUAGE`, `LC_ALL`, `LC_MESSAGES` and then
+    /// `LANG`.
+    pub fn from_desktop_file(id: &str, name: &str, path: &Path) -> Option<Self> {
+        let info = gio::DesktopAppInfo::from_filename(path)?;
+
+        Some(Self {
+            id: id.to_string(),
+            localized_name: info
+                .locale_string("Name")
+                .map(String::from)
+                .filter(|localized| localized != name),
+            generic_name: info.generic_name().map(String::from),
+            keywords: info.keywords().into_iter().map(String::from).collect(),
+            categories: info
+                .categories()
+                .map(|categories| split_list(&categories))
+                .unwrap_or_default(),
+            source: AppSource::default(),
+        })
+    }
+
+    /// Everything the app can be searched by besides its name and comment.
+    /// Apps that aren't native can be found by how they were installed too,
+    /// like "flatpak".
+    pub fn search_keys(&self) -> Vec<String> {
+        let source = (self.source != AppSource::Native).then(|| self.source.label().to_string());
+
+        self.localized_name
+            .iter()
+            .chain(&self.generic_name)
+            .chain(&self.keywords)
+            .chain(&self.categories)
+            .cloned()
+            .chain(source)
+            .collect()
+    }
+
+    /// Whether one of the app's categories starts with `category`, ignoring
+    /// case, so a category can be picked before it's typed out.
+    pub fn in_category(&self, category: &str) -> bool {
+        let category = category.to_lowercase();
+        self.categories
+            .iter()
+            .any(|own| own.to_lowercase().starts_with(&category))
+    }
+}
+
+/// A `cat:` query, without its prefix, split into the category and what's
+/// searched for in it.
+pub fn split_category_query(query: &str) -> (&str, &str) {
+    let query = query.trim_start();
+    match query.split_once(char::is_whitespace) {
+        Some((category, rest)) => (category, rest.trim()),
+        None => (query, ""),
+    }
+}
+
+/// Deal with apps installed more than one way, like Firefox from the
+/// distribution and from Flathub, going by their names. With `merge` only
+/// the ones from the preferred source are kept. Otherwise all of them are,
+/// and their descriptions start with where they're from so they can be
+/// told apart.
+pub fn resolve_duplicates(
+    apps: Vec<(LauncherItem, AppDetails)>,
+    merge: bool,
+) -> Vec<(LauncherItem, AppDetails)> {
+    let mut sources: HashMap<String, BTreeSet<AppSource>> = HashMap::new();
+    for (item, details) in &apps {
+        sources
+            .entry(item.title.to_lowercase())
+            .or_default()
+            .insert(details.source);
+    }
+
+    apps.into_iter()
+        .filter_map(|(mut item, details)| {
+            let found = &sources[&item.title.to_lowercase()];
+            if found.len() < 2 {
+                return Some((item, details));
+            }
+
+            if merge {
+                return (found.first() == Some(&details.source)).then_some((item, details));
+            }
+
+            let label = details.source.label();
+            item.description = Some(match item.description {
+                Some(description) => format!("{label} · {description}"),
+                None => label.to_string(),
+            });
+            Some((item, details))
+        })
+        .collect()
+}
+
+/// The desktop entry extracted from an AppImage, changed to run the
+/// AppImage itself and to show the icon extracted next to it.
+pub fn appimage_entry(contents: &str, appimage: &Path, icon: Option<&Path>) -> String {
+    let program = quote_exec_arg(&appimage.to_string_lossy());
+    let mut entry = String::with_capacity(contents.len());
+
+    for line in contents.lines() {
+        let trimmed = line.trim_start();
+
+        if let Some(exec) = trimmed.strip_prefix("Exec=") {
+            let args = skip_program(exec.trim_start());
+            entry.push_str(format!("Exec={program} {args}").trim_end());
+        } else if trimmed.starts_with("TryExec=") {
+            continue;
+        } else if let (Some(icon), true) = (icon, trimmed.starts_with("Icon=")) {
+            entry.push_str(&format!("Icon={}", icon.display()));
+        } else {
+            entry.push_str(line);
+        }
+        entry.push('\n');
+    }
+
+    entry
+}
+
+/// What comes after the program in an `Exec` value, which may be quoted.
+fn skip_program(exec: &str) -> &str {
+    let end = match exec.strip_prefix('"') {
+        Some(quoted) => {
+            let mut escaped = false;
+            quoted
+                .char_indices()
+                .find(|&(_, c)| {
+                    let closes = c == '"' && !escaped;
+                    escaped = c == '\\' && !escaped;
+                    closes
+                })
+                .map_or(exec.len(), |(i, _)| i + 2)
+        }
+        None => exec.find(char::is_whitespace).unwrap_or(exec.len()),
+    };
+
+    exec[end..].trim_start()
+}
+
+/// Quote an `Exec` argument. Desktop entry values get their own round of
+/// backslash escapes on top of the ones inside the quotes.
+fn quote_exec_arg(arg: &str) -> String {
+    let mut quoted = String::from("\"");
+    for c in arg.chars() {
+        if matches!(c, '"' | '`' | '$' | '\\') {
+            quoted.push('\\');
+        }
+        quoted.push(c);
+    }
+    quoted.push('"');
+
+    quoted.replace('\\', "\\\\")
+}
+
+/// A `;` separated desktop entry list, which usually ends in a `;` too.
+fn split_list(list: &str) -> Vec<String> {
+    list.split(';')
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_categories_and_splits_queries() {
+        let gimp = AppDetails {
+            id: String::from("gimp"),
+            generic_name: Some(String::from("Image Editor")),
+            categories: split_list("Graphics;2DGraphics;RasterGraphics;GTK;"),
+            ..AppDetails::default()
+        };
+
+        assert!(gimp.in_category("graph"));
+        assert!(gimp.in_category("GTK"));
+        assert!(!gimp.in_category("Office"));
+        assert_eq!(gimp.search_keys().len(), 5);
+
+        assert_eq!(
+            split_category_query("Graphics  image ed"),
+            ("Graphics", "image ed")
+        );
+        assert_eq!(split_category_query("Graph"), ("Graph", ""));
+        assert_eq!(split_category_query(""), ("", ""));
+    }
+
+    fn app(title: &str, source: AppSource) -> (LauncherItem, AppDetails) {
+        let item = LauncherItem {
+            id: format!("{title}-{}", source.label()),
+            kind: crate::core::ItemKind::DesktopEntry,
+            title: title.to_string(),
+            description: Some(String::from("Browse the web")),
+            icon: String::new(),
+        };
+        let details = AppDetails {
+            id: item.id.clone(),
+            source,
+            ..AppDetails::default()
+        };
+
+        (item, details)
+    }
+
+    #[test]
+    fn duplicates_are_merged_or_labelled() {
+        let apps = vec![
+            app("Firefox", AppSource::Flatpak),
+            app("firefox", AppSource::Native),
+            app("Krita", AppSource::AppImage),
+        ];
+
+        let merged = resolve_duplicates(apps.clone(), true);
+        let ids: Vec<&str> = merged.iter().map(|(item, _)| item.id.as_str()).collect();
+        assert_eq!(ids, ["firefox-Native", "Krita-AppImage"]);
+
+        let side_by_side = resolve_duplicates(apps, false);
+        let descriptions: Vec<_> = side_by_side
+            .iter()
+            .map(|(item, _)| item.description.as_deref().unwrap())
+            .collect();
+        assert_eq!(
+            descriptions,
+            [
+                "Flatpak · Browse the web",
+                "Native · Browse the web",
+                "Browse the web"
+            ]
+        );
+        let (_, krita) = &side_by_side[2];
+        assert!(krita.search_keys().contains(&String::from("AppImage")));
+    }
+
+    #[test]
+    fn appimage_entries_run_the_appimage() {
+        let extracted = "[Desktop Entry]\n\
+            Name=Krita\n\
+            Exec=krita %F\n\
+            TryExec=krita\n\
+            Icon=krita\n\
+            \n\
+            [Desktop Action new]\n\
+            Exec=\"AppRun\" --new-window\n";
+        let appimage = Path::new("/home/me/My Apps/krita$1.AppImage");
+        let icon = Path::new("/data/appimages/icons/krita.png");
+
+        assert_eq!(
+            appimage_entry(extracted, appimage, Some(icon)),
+            "[Desktop Entry]\n\
+            Name=Krita\n\
+            Exec=\"/home/me/My Apps/krita\\\\$1.AppImage\" %F\n\
+            Icon=/data/appimages/icons/krita.png\n\
+            \n\
+            [Desktop Action new]\n\
+            Exec=\"/home/me/My Apps/krita\\\\$1.AppImage\" --new-window\n"
+        );
+        assert_eq!(skip_program("AppRun"), "");
+    }
+}
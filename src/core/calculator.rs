@@ -0,0 +1,159 @@
+//! Evaluating search queries as math, so a calculation shows up as a result
+//! like any other and can be copied with Enter.
+
+use mathengine::Value;
+
+use super::config::CalculatorConfig;
+use super::{ItemKind, LauncherItem};
+
+/// Queries starting with this are only evaluated as math, and on their own
+/// list the calculation history.
+pub const PREFIX: char = '=';
+pub const ICON: &str = "accessories-calculator";
+
+#[derive(Debug, Clone, PartialEq, Eq, sqlx::FromRow)]
+pub struct Calculation {
+    pub expression: String,
+    /// The result at full precision. This is what gets copied.
+    pub result: String,
+    /// The result formatted for display
+    pub display: String,
+}
+
+impl Calculation {
+    /// Evaluate a search query, if it looks like something worth
+    /// calculating.
+    ///
+    /// A bare number evaluates to itself, so it isn't shown as a
+    /// calculation. Neither is anything without a digit in it, otherwise
+    /// typing `e` to find an app would turn up Euler's number.
+    pub fn from_query(query: &str, cfg: &CalculatorConfig) -> Option<Self> {
+        let query = query.trim();
+        if query.parse::<f64>().is_ok() || !query.chars().any(|c| c.is_ascii_digit()) {
+            return None;
+        }
+
+        Self::evaluate(query, cfg)
+    }
+
+    pub fn evaluate(expression: &str, cfg: &CalculatorConfig) -> Option<Self> {
+        let expression = expression.trim();
+        let (result, display) = match mathengine::evaluate_expression(expression).ok()? {
+            Value::Number(n) if n.0.is_finite() => (n.0.to_string(), format_number(n.0, cfg)),
+            Value::UnitValue(uv) if uv.value().is_finite() => (
+                format!("{} {}", uv.value(), uv.unit()),
+                format!("{} {}", format_number(uv.value(), cfg), uv.unit()),
+            ),
+            _ => return None,
+        };
+
+        Some(Self {
+            expression: expression.to_string(),
+            result,
+            display,
+        })
+    }
+
+    /// The item the UI shows. Its id is the value that gets copied.
+    pub fn to_item(&self) -> LauncherItem {
+        LauncherItem {
+            id: self.result.clone(),
+            kind: ItemKind::Calculator,
+            title: format!("{PREFIX} {}", self.display),
+            description: Some(self.expression.clone()),
+            icon: ICON.into(),
+        }
+    }
+
+    /// Recover the calculation an item was made from with `to_item`.
+    pub fn from_item(item: &LauncherItem) -> Self {
+        let display = item
+            .title
+            .strip_prefix(PREFIX)
+            .unwrap_or(&item.title)
+            .trim_start();
+
+        Self {
+            expression: item.description.clone().unwrap_or_default(),
+            result: item.id.clone(),
+            display: display.to_string(),
+        }
+    }
+}
+
+/// Format a number for display: at most `precision` decimals with trailing
+/// zeros dropped, and optionally with thousands separators.
+pub fn format_number(n: f64, cfg: &CalculatorConfig) -> String {
+    let mut formatted = match cfg.precision {
+        Some(precision) => format!("{n:.precision$}"),
+        None => n.to_string(),
+    };
+
+    if formatted.contains('.') {
+        let trimmed = formatted.trim_end_matches('0').trim_end_matches('.');
+        formatted.truncate(trimmed.len());
+    }
+    if formatted == "-0" {
+        formatted.remove(0);
+    }
+
+    if !cfg.group_digits {
+        return formatted;
+    }
+
+    let (sign, unsigned) = match formatted.strip_prefix('-') {
+        Some(rest) => ("-", rest),
+        None => ("", formatted.as_str()),
+    };
+    let (integer, fraction) = match unsigned.split_once('.') {
+        Some((integer, fraction)) => (integer, Some(fraction)),
+        None => (unsigned, None),
+    };
+
+    let mut grouped = String::from(sign);
+    for (i, digit) in integer.chars().enumerate() {
+        if i > 0 && (integer.len() - i) % 3 == 0 {
+            grouped.push(',');
+        }
+        grouped.push(digit);
+    }
+    if let Some(fraction) = fraction {
+        grouped.push('.');
+        grouped.push_str(fraction);
+    }
+
+    grouped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bare_numbers_and_words_are_not_calculations() {
+        let cfg = CalculatorConfig::default();
+
+        assert_eq!(Calculation::from_query("2048", &cfg), None);
+        assert_eq!(Calculation::from_query("firefox", &cfg), None);
+
+        let calculation = Calculation::from_query("2048 * 2", &cfg).expect("calculation");
+        assert_eq!(calculation.result, "4096");
+        assert_eq!(Calculation::from_item(&calculation.to_item()), calculation);
+    }
+
+    #[test]
+    fn formatting_follows_the_config() {
+        let full = CalculatorConfig::default();
+        let rounded = CalculatorConfig {
+            precision: Some(2),
+            group_digits: true,
+            ..CalculatorConfig::default()
+        };
+
+        assert_eq!(format_number(1.0 / 3.0, &full), "0.3333333333333333");
+        assert_eq!(format_number(1234567.126, &rounded), "1,234,567.13");
+        assert_eq!(format_number(-1000.0, &rounded), "-1,000");
+        assert_eq!(format_number(-0.001, &rounded), "0");
+        assert_eq!(format_number(2.5, &rounded), "2.5");
+    }
+}
@@ -0,0 +1,639 @@
+//! Copying and watching the clipboard over ext-data-control, or
+//! wlr-data-control on compositors that don't have the ext protocol yet. The
+//! two are the same protocol under different names, so each piece below
+//! exists once per protocol.
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, PipeReader, Read, Write};
+use std::os::fd::{AsFd, AsRawFd, BorrowedFd, OwnedFd, RawFd};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use tokio::sync::mpsc::Sender;
+use tracing::{debug, info, warn};
+use wayland_client::backend::ObjectId;
+use wayland_client::globals::{GlobalList, GlobalListContents, registry_queue_init};
+use wayland_client::protocol::wl_registry::WlRegistry;
+use wayland_client::protocol::wl_seat::WlSeat;
+use wayland_client::{Connection, Dispatch, EventQueue, Proxy, QueueHandle, event_created_child};
+use wayland_protocols::ext::data_control::v1::client::{
+    ext_data_control_device_v1::{self, ExtDataControlDeviceV1},
+    ext_data_control_manager_v1::ExtDataControlManagerV1,
+    ext_data_control_offer_v1::{self, ExtDataControlOfferV1},
+    ext_data_control_source_v1::{self, ExtDataControlSourceV1},
+};
+use wayland_protocols_wlr::data_control::v1::client::{
+    zwlr_data_control_device_v1::{self, ZwlrDataControlDeviceV1},
+    zwlr_data_control_manager_v1::ZwlrDataControlManagerV1,
+    zwlr_data_control_offer_v1::{self, ZwlrDataControlOfferV1},
+    zwlr_data_control_source_v1::{self, ZwlrDataControlSourceV1},
+};
+
+use super::{ClipboardError, Contents, Serve, TEXT_MIME_TYPES};
+use crate::core::config::ClipboardHistoryConfig;
+
+/// How long a source gets to send what was copied. One that never closes
+/// its end of the pipe would otherwise hang the watcher, and with it the
+/// history, for good.
+const RECEIVE_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Put `contents` on the clipboard of every seat. Returns once the
+/// compositor has the selection; a background thread serves pastes until
+/// something else is copied.
+pub fn copy(contents: Contents, serve: Serve) -> Result<(), ClipboardError> {
+    let Session {
+        conn,
+        mut queue,
+        manager,
+        seats,
+    } = Session::connect()?;
+    let qh = queue.handle();
+
+    for seat in &seats {
+        manager.set_selection(seat, &contents, &qh);
+    }
+
+    let mut state = State {
+        serving: Some(contents),
+        serve,
+        sources: seats.len(),
+        ..State::default()
+    };
+    queue.roundtrip(&mut state)?;
+
+    thread::Builder::new()
+        .name("clipboard".into())
+        .spawn(move || {
+            // Dropping the connection would take the selection with it.
+            let _conn = conn;
+
+            while state.sources > 0 {
+                if let Err(e) = queue.blocking_dispatch(&mut state) {
+                    warn!("Stopped serving clipboard contents: {e}");
+                    return;
+                }
+            }
+
+            debug!("Clipboard contents were replaced");
+        })?;
+
+    Ok(())
+}
+
+/// BLOCKS and sends whatever gets copied to `tx`, until the receiving end
+/// goes away. Copies offering one of the ignored mime types, or larger than
+/// the size cap, are skipped.
+///
+/// Only the first seat is watched; on the usual single-seat desktop that's
+/// the clipboard.
+pub fn watch(cfg: &ClipboardHistoryConfig, tx: Sender<Contents>) -> Result<(), ClipboardError> {
+    let Session {
+        conn,
+        mut queue,
+        manager,
+        seats,
+    } = Session::connect()?;
+    let qh = queue.handle();
+
+    manager.watch(&seats[0], &qh);
+    let mut state = State {
+        watching: true,
+        ..State::default()
+    };
+    info!("Watching the clipboard");
+
+    loop {
+        queue.blocking_dispatch(&mut state)?;
+
+        let Some(offer) = state.selection.take() else {
+            continue;
+        };
+        let mime_types = state.offers.remove(&offer.id()).unwrap_or_default();
+        let contents = receive(&conn, &offer, &mime_types, cfg);
+        offer.destroy();
+
+        if let Some(contents) = contents
+            && tx.blocking_send(contents).is_err()
+        {
+            info!("Clipboard history receiver closed; stopping clipboard watcher");
+            return Ok(());
+        }
+    }
+}
+
+/// Read the contents of an offer, if it's worth keeping.
+fn receive(
+    conn: &Connection,
+    offer: &Offer,
+    mime_types: &[String],
+    cfg: &ClipboardHistoryConfig,
+) -> Option<Contents> {
+    if let Some(ignored) = mime_types
+        .iter()
+        .find(|mime_type| cfg.ignored_mime_types.contains(mime_type))
+    {
+        debug!("Not recording a copy offering {ignored}");
+        return None;
+    }
+
+    let mime_type = preferred_mime_type(mime_types)?;
+    let (reader, writer) = std::io::pipe()
+        .inspect_err(|e| warn!("Could not create a pipe for clipboard contents: {e}"))
+        .ok()?;
+    offer.receive(mime_type.to_string(), writer.as_fd());
+    // Our copy of the write end has to be closed, or reading never ends.
+    drop(writer);
+    conn.flush()
+        .inspect_err(|e| warn!("Could not request clipboard contents: {e}"))
+        .ok()?;
+
+    // Read one byte past the cap to tell a copy that fits from one that
+    // doesn't.
+    let data = read_until_closed(reader, cfg.max_entry_size + 1, RECEIVE_TIMEOUT)
+        .inspect_err(|e| warn!("Could not read clipboard contents: {e}"))
+        .ok()?;
+
+    if data.is_empty() {
+        return None;
+    }
+    if data.len() > cfg.max_entry_size {
+        debug!("Not recording a copy over {} bytes", cfg.max_entry_size);
+        return None;
+    }
+
+    Some(Contents {
+        mime_type: mime_type.to_string(),
+        data,
+    })
+}
+
+/// Read up to `limit` bytes from `reader`, until the other end is closed.
+/// Gives up with `TimedOut` once `timeout` has passed.
+fn read_until_closed(
+    mut reader: PipeReader,
+    limit: usize,
+    timeout: Duration,
+) -> io::Result<Vec<u8>> {
+    let fd = reader.as_raw_fd();
+    set_nonblocking(fd)?;
+
+    let deadline = Instant::now() + timeout;
+    let mut data = Vec::new();
+    let mut buf = [0; 8192];
+    while data.len() < limit {
+        let len = buf.len().min(limit - data.len());
+        match reader.read(&mut buf[..len]) {
+            Ok(0) => break,
+            Ok(read) => data.extend_from_slice(&buf[..read]),
+            Err(e) if e.kind() == io::ErrorKind::WouldBlock => {
+                let remaining = deadline.saturating_duration_since(Instant::now());
+                if remaining.is_zero() || !wait_readable(fd, remaining)? {
+                    return Err(io::Error::new(
+                        io::ErrorKind::TimedOut,
+                        "the source did not finish sending it",
+                    ));
+                }
+            }
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+
+    Ok(data)
+}
+
+fn set_nonblocking(fd: RawFd) -> io::Result<()> {
+    // SAFETY: fcntl on a descriptor we own, with no pointers involved.
+    let flags = unsafe { libc::fcntl(fd, libc::F_GETFL) };
+    if flags == -1 || unsafe { libc::fcntl(fd, libc::F_SETFL, flags | libc::O_NONBLOCK) } == -1 {
+        return Err(io::Error::last_os_error());
+    }
+
+    Ok(())
+}
+
+/// Wait up to `timeout` for `fd` to have something to read, or for its
+/// other end to be closed. Returns whether it does.
+fn wait_readable(fd: RawFd, timeout: Duration) -> io::Result<bool> {
+    let mut pollfd = libc::pollfd {
+        fd,
+        events: libc::POLLIN,
+        revents: 0,
+    };
+    let millis = timeout.as_millis().clamp(1, i32::MAX as u128) as i32;
+
+    // SAFETY: `pollfd` outlives the call, which gets a count of one.
+    match unsafe { libc::poll(&mut pollfd, 1, millis) } {
+        -1 => {
+            let e = io::Error::last_os_error();
+            if e.kind() == io::ErrorKind::Interrupted {
+                Ok(true)
+            } else {
+                Err(e)
+            }
+        }
+        0 => Ok(false),
+        _ => Ok(true),
+    }
+}
+
+/// Text in its best encoding first, then images. Anything else isn't
+/// something the history can show.
+fn preferred_mime_type(mime_types: &[String]) -> Option<&str> {
+    let offered = |wanted: &str| mime_types.iter().any(|mime_type| mime_type == wanted);
+
+    TEXT_MIME_TYPES
+        .into_iter()
+        .find(|mime_type| offered(mime_type))
+        .or_else(|| offered("image/png").then_some("image/png"))
+        .or_else(|| {
+            mime_types
+                .iter()
+                .map(String::as_str)
+                .find(|mime_type| mime_type.starts_with("image/"))
+        })
+}
+
+/// A connection with everything bound that copying or watching needs.
+struct Session {
+    conn: Connection,
+    queue: EventQueue<State>,
+    manager: Manager,
+    seats: Vec<WlSeat>,
+}
+
+impl Session {
+    fn connect() -> Result<Self, ClipboardError> {
+        let conn = Connection::connect_to_env()?;
+        let (globals, queue) = registry_queue_init::<State>(&conn)?;
+        let qh = queue.handle();
+
+        let manager = Manager::bind(&globals, &qh)?;
+        let seats: Vec<WlSeat> = globals
+            .contents()
+            .clone_list()
+            .into_iter()
+            .filter(|global| global.interface == WlSeat::interface().name)
+            .map(|global| globals.registry().bind(global.name, 1, &qh, ()))
+            .collect();
+
+        if seats.is_empty() {
+            return Err(ClipboardError::NoSeats);
+        }
+
+        Ok(Self {
+            conn,
+            queue,
+            manager,
+            seats,
+        })
+    }
+}
+
+enum Manager {
+    Ext(ExtDataControlManagerV1),
+    Wlr(ZwlrDataControlManagerV1),
+}
+
+impl Manager {
+    fn bind(globals: &GlobalList, qh: &QueueHandle<State>) -> Result<Self, ClipboardError> {
+        if let Ok(manager) = globals.bind::<ExtDataControlManagerV1, _, _>(qh, 1..=1, ()) {
+            return Ok(Self::Ext(manager));
+        }
+
+        globals
+            .bind::<ZwlrDataControlManagerV1, _, _>(qh, 1..=2, ())
+            .map(Self::Wlr)
+            .map_err(|_| ClipboardError::Unsupported)
+    }
+
+    /// Get a data device for `seat`, which reports every new selection.
+    fn watch(&self, seat: &WlSeat, qh: &QueueHandle<State>) {
+        match self {
+            Self::Ext(manager) => {
+                manager.get_data_device(seat, qh, ());
+            }
+            Self::Wlr(manager) => {
+                manager.get_data_device(seat, qh, ());
+            }
+        }
+    }
+
+    fn set_selection(&self, seat: &WlSeat, contents: &Contents, qh: &QueueHandle<State>) {
+        match self {
+            Self::Ext(manager) => {
+                let source = manager.create_data_source(qh, ());
+                for mime_type in contents.offered_mime_types() {
+                    source.offer(mime_type.to_string());
+                }
+                manager
+                    .get_data_device(seat, qh, ())
+                    .set_selection(Some(&source));
+            }
+            Self::Wlr(manager) => {
+                let source = manager.create_data_source(qh, ());
+                for mime_type in contents.offered_mime_types() {
+                    source.offer(mime_type.to_string());
+                }
+                manager
+                    .get_data_device(seat, qh, ())
+                    .set_selection(Some(&source));
+            }
+        }
+    }
+}
+
+enum Offer {
+    Ext(ExtDataControlOfferV1),
+    Wlr(ZwlrDataControlOfferV1),
+}
+
+impl Offer {
+    fn id(&self) -> ObjectId {
+        match self {
+            Self::Ext(offer) => offer.id(),
+            Self::Wlr(offer) => offer.id(),
+        }
+    }
+
+    fn receive(&self, mime_type: String, fd: BorrowedFd<'_>) {
+        match self {
+            Self::Ext(offer) => offer.receive(mime_type, fd),
+            Self::Wlr(offer) => offer.receive(mime_type, fd),
+        }
+    }
+
+    fn destroy(&self) {
+        match self {
+            Self::Ext(offer) => offer.destroy(),
+            Self::Wlr(offer) => offer.destroy(),
+        }
+    }
+}
+
+#[derive(Default)]
+struct State {
+    /// Contents being copied
+    serving: Option<Contents>,
+    serve: Serve,
+    /// Sources still holding a selection
+    sources: usize,
+    /// Whether new selections are of interest, rather than just noise
+    watching: bool,
+    /// Mime types of the offers that haven't been dealt with yet
+    offers: HashMap<ObjectId, Vec<String>>,
+    /// The most recent selection, once all of its mime types are known
+    selection: Option<Offer>,
+}
+
+impl State {
+    /// Answer a paste. Returns whether the source should keep serving.
+    fn send(&mut self, fd: OwnedFd) -> bool {
+        let data = self
+            .serving
+            .as_ref()
+            .map_or(&[][..], |contents| &contents.data);
+        if let Err(e) = File::from(fd).write_all(data) {
+            warn!("Failed to send clipboard contents: {e}");
+        }
+
+        self.serve == Serve::UntilReplaced
+    }
+
+    fn data_offer(&mut self, offer: Offer) {
+        if self.watching {
+            self.offers.insert(offer.id(), Vec::new());
+        } else {
+            offer.destroy();
+        }
+    }
+
+    fn offer_mime_type(&mut self, offer: ObjectId, mime_type: String) {
+        if let Some(mime_types) = self.offers.get_mut(&offer) {
+            mime_types.push(mime_type);
+        }
+    }
+
+    /// The clipboard changed. An empty clipboard is `None`.
+    fn new_selection(&mut self, offer: Option<Offer>) {
+        if let Some(previous) = self.selection.take() {
+            self.offers.remove(&previous.id());
+            previous.destroy();
+        }
+        self.selection = offer;
+    }
+
+    /// Primary selections (middle click paste) aren't recorded.
+    fn new_primary_selection(&mut self, offer: Option<Offer>) {
+        if let Some(offer) = offer {
+            self.offers.remove(&offer.id());
+            offer.destroy();
+        }
+    }
+
+    fn source_done(&mut self) {
+        self.sources = self.sources.saturating_sub(1);
+    }
+}
+
+impl Dispatch<WlRegistry, GlobalListContents> for State {
+    fn event(
+        _state: &mut Self,
+        _registry: &WlRegistry,
+        _event: <WlRegistry as Proxy>::Event,
+        _data: &GlobalListContents,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+    }
+}
+
+/// For objects that are needed but whose events are not.
+macro_rules! ignore_events {
+    ($($proxy:ty),* $(,)?) => {
+        $(
+            impl Dispatch<$proxy, ()> for State {
+                fn event(
+                    _state: &mut Self,
+                    _proxy: &$proxy,
+                    _event: <$proxy as Proxy>::Event,
+                    _data: &(),
+                    _conn: &Connection,
+                    _qh: &QueueHandle<Self>,
+                ) {
+                }
+            }
+        )*
+    };
+}
+
+ignore_events!(WlSeat, ExtDataControlManagerV1, ZwlrDataControlManagerV1);
+
+impl Dispatch<ExtDataControlDeviceV1, ()> for State {
+    fn event(
+        state: &mut Self,
+        _device: &ExtDataControlDeviceV1,
+        event: ext_data_control_device_v1::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+        match event {
+            ext_data_control_device_v1::Event::DataOffer { id } => {
+                state.data_offer(Offer::Ext(id));
+            }
+            ext_data_control_device_v1::Event::Selection { id } => {
+                state.new_selection(id.map(Offer::Ext));
+            }
+            ext_data_control_device_v1::Event::PrimarySelection { id } => {
+                state.new_primary_selection(id.map(Offer::Ext));
+            }
+            _ => {}
+        }
+    }
+
+    event_created_child!(State, ExtDataControlDeviceV1, [
+        ext_data_control_device_v1::EVT_DATA_OFFER_OPCODE => (ExtDataControlOfferV1, ()),
+    ]);
+}
+
+impl Dispatch<ZwlrDataControlDeviceV1, ()> for State {
+    fn event(
+        state: &mut Self,
+        _device: &ZwlrDataControlDeviceV1,
+        event: zwlr_data_control_device_v1::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+        match event {
+            zwlr_data_control_device_v1::Event::DataOffer { id } => {
+                state.data_offer(Offer::Wlr(id));
+            }
+            zwlr_data_control_device_v1::Event::Selection { id } => {
+                state.new_selection(id.map(Offer::Wlr));
+            }
+            zwlr_data_control_device_v1::Event::PrimarySelection { id } => {
+                state.new_primary_selection(id.map(Offer::Wlr));
+            }
+            _ => {}
+        }
+    }
+
+    event_created_child!(State, ZwlrDataControlDeviceV1, [
+        zwlr_data_control_device_v1::EVT_DATA_OFFER_OPCODE => (ZwlrDataControlOfferV1, ()),
+    ]);
+}
+
+impl Dispatch<ExtDataControlSourceV1, ()> for State {
+    fn event(
+        state: &mut Self,
+        source: &ExtDataControlSourceV1,
+        event: ext_data_control_source_v1::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+        let done = match event {
+            ext_data_control_source_v1::Event::Send { fd, .. } => !state.send(fd),
+            ext_data_control_source_v1::Event::Cancelled => true,
+            _ => false,
+        };
+
+        if done {
+            source.destroy();
+            state.source_done();
+        }
+    }
+}
+
+impl Dispatch<ZwlrDataControlSourceV1, ()> for State {
+    fn event(
+        state: &mut Self,
+        source: &ZwlrDataControlSourceV1,
+        event: zwlr_data_control_source_v1::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+        let done = match event {
+            zwlr_data_control_source_v1::Event::Send { fd, .. } => !state.send(fd),
+            zwlr_data_control_source_v1::Event::Cancelled => true,
+            _ => false,
+        };
+
+        if done {
+            source.destroy();
+            state.source_done();
+        }
+    }
+}
+
+impl Dispatch<ExtDataControlOfferV1, ()> for State {
+    fn event(
+        state: &mut Self,
+        offer: &ExtDataControlOfferV1,
+        event: ext_data_control_offer_v1::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+        if let ext_data_control_offer_v1::Event::Offer { mime_type } = event {
+            state.offer_mime_type(offer.id(), mime_type);
+        }
+    }
+}
+
+impl Dispatch<ZwlrDataControlOfferV1, ()> for State {
+    fn event(
+        state: &mut Self,
+        offer: &ZwlrDataControlOfferV1,
+        event: zwlr_data_control_offer_v1::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+        if let zwlr_data_control_offer_v1::Event::Offer { mime_type } = event {
+            state.offer_mime_type(offer.id(), mime_type);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_until_the_source_closes_its_end() {
+        let (reader, mut writer) = io::pipe().unwrap();
+        writer.write_all(b"copied").unwrap();
+        drop(writer);
+
+        let data = read_until_closed(reader, 100, Duration::from_secs(1)).unwrap();
+        assert_eq!(data, b"copied");
+    }
+
+    #[test]
+    fn stops_one_byte_past_the_cap() {
+        let (reader, mut writer) = io::pipe().unwrap();
+        writer.write_all(&[b'x'; 64]).unwrap();
+
+        // Reading stops at the limit, so a writer that's still open
+        // doesn't matter.
+        let data = read_until_closed(reader, 11, Duration::from_secs(1)).unwrap();
+        assert_eq!(data.len(), 11);
+    }
+
+    #[test]
+    fn gives_up_on_a_source_that_never_closes() {
+        let (reader, mut writer) = io::pipe().unwrap();
+        writer.write_all(b"partial").unwrap();
+
+        let started = Instant::now();
+        let e = read_until_closed(reader, 100, Duration::from_millis(50)).unwrap_err();
+        assert_eq!(e.kind(), io::ErrorKind::TimedOut);
+        assert!(started.elapsed() < Duration::from_secs(1));
+        drop(writer);
+    }
+}
@@ -0,0 +1,168 @@
+//! Putting things on the Wayland clipboard.
+//!
+//! Clipboard data on Wayland lives in the client that copied it, and is gone
+//! as soon as that client lets go of it. Copies are served from a background
+//! thread of this process until something else takes the clipboard, so they
+//! outlive the launcher UI being hidden. Without a data-control protocol
+//! (ext or wlr), `wl-copy` does the same from a process of its own.
+//!
+//! Watching the clipboard for the history needs data-control; there is no
+//! fallback for it.
+
+mod data_control;
+mod wl_copy;
+
+use thiserror::Error;
+use tokio::sync::mpsc::Sender;
+use tracing::warn;
+
+use crate::core::config::{ClipboardBackend, ClipboardConfig, ClipboardHistoryConfig};
+
+/// Queries starting with this search the clipboard history.
+pub const SEARCH_PREFIX: &str = "clip:";
+pub const HISTORY_ICON: &str = "edit-paste";
+
+/// Longest preview shown for a text entry in the clipboard history
+const PREVIEW_CHARS: usize = 120;
+
+/// Mime types plain text is offered as, so it pastes into anything from
+/// GTK apps to X11 clients running under XWayland.
+const TEXT_MIME_TYPES: [&str; 5] = [
+    "text/plain;charset=utf-8",
+    "text/plain",
+    "UTF8_STRING",
+    "STRING",
+    "TEXT",
+];
+
+#[derive(Debug, Error)]
+pub enum ClipboardError {
+    #[error("Could not connect to the Wayland compositor: {0}")]
+    Connect(#[from] wayland_client::ConnectError),
+    #[error("Could not list Wayland globals: {0}")]
+    Globals(#[from] wayland_client::globals::GlobalError),
+    #[error("Wayland connection error: {0}")]
+    Dispatch(#[from] wayland_client::DispatchError),
+    #[error("The compositor supports neither ext-data-control nor wlr-data-control")]
+    Unsupported,
+    #[error("The compositor has no seats")]
+    NoSeats,
+    #[error("{program} failed: {reason}")]
+    Command { program: String, reason: String },
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+}
+
+/// Something to put on the clipboard.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Contents {
+    pub mime_type: String,
+    pub data: Vec<u8>,
+}
+
+impl Contents {
+    pub fn text(text: &str) -> Self {
+        Self {
+            mime_type: TEXT_MIME_TYPES[0].to_string(),
+            data: text.as_bytes().to_vec(),
+        }
+    }
+
+    pub fn is_text(&self) -> bool {
+        TEXT_MIME_TYPES.contains(&self.mime_type.as_str())
+    }
+
+    /// One line describing the contents: the text itself with whitespace
+    /// collapsed, or the kind and size of anything else.
+    pub fn preview(&self) -> String {
+        if !self.is_text() {
+            return format!("{} ({} bytes)", self.mime_type, self.data.len());
+        }
+
+        let text = String::from_utf8_lossy(&self.data);
+        let mut preview = text.split_whitespace().collect::<Vec<_>>().join(" ");
+        if let Some((cut, _)) = preview.char_indices().nth(PREVIEW_CHARS) {
+            preview.truncate(cut);
+            preview.push('…');
+        }
+
+        preview
+    }
+
+    /// Every mime type the contents are offered as.
+    fn offered_mime_types(&self) -> Vec<&str> {
+        if self.mime_type.starts_with("text/plain") {
+            TEXT_MIME_TYPES.to_vec()
+        } else {
+            vec![self.mime_type.as_str()]
+        }
+    }
+}
+
+/// How long copied contents stay on the clipboard.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Serve {
+    /// Until something else is copied
+    #[default]
+    UntilReplaced,
+    /// For a single paste, then the clipboard is cleared
+    Once,
+}
+
+#[derive(Clone)]
+pub struct Clipboard {
+    backend: ClipboardBackend,
+    wl_copy: String,
+}
+
+impl Clipboard {
+    pub fn new(cfg: &ClipboardConfig) -> Self {
+        Self {
+            backend: cfg.backend,
+            wl_copy: cfg.wl_copy.clone(),
+        }
+    }
+
+    pub fn copy_text(&self, text: &str) -> Result<(), ClipboardError> {
+        self.copy(Contents::text(text), Serve::UntilReplaced)
+    }
+
+    pub fn copy(&self, contents: Contents, serve: Serve) -> Result<(), ClipboardError> {
+        match self.backend {
+            ClipboardBackend::DataControl => data_control::copy(contents, serve),
+            ClipboardBackend::WlCopy => wl_copy::copy(&self.wl_copy, &contents, serve),
+            ClipboardBackend::Auto => match data_control::copy(contents.clone(), serve) {
+                Ok(()) => Ok(()),
+                Err(e) => {
+                    warn!("Copying over data-control failed, falling back to wl-copy: {e}");
+                    wl_copy::copy(&self.wl_copy, &contents, serve)
+                }
+            },
+        }
+    }
+}
+
+/// BLOCKS and sends everything that gets copied to `tx`, minus what the
+/// history config excludes.
+pub fn watch(cfg: &ClipboardHistoryConfig, tx: Sender<Contents>) -> Result<(), ClipboardError> {
+    data_control::watch(cfg, tx)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn previews_collapse_text_and_describe_everything_else() {
+        let text = Contents::text("  fn main() {\n    println!();\n}\n");
+        let long = Contents::text(&"a".repeat(PREVIEW_CHARS + 10));
+        let image = Contents {
+            mime_type: "image/png".into(),
+            data: vec![0; 2048],
+        };
+
+        assert_eq!(text.preview(), "fn main() { println!(); }");
+        assert_eq!(long.preview().chars().count(), PREVIEW_CHARS + 1);
+        assert_eq!(image.preview(), "image/png (2048 bytes)");
+    }
+}
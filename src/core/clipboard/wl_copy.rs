@@ -0,0 +1,84 @@
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+use super::{ClipboardError, Contents, Serve};
+
+/// Copy by piping the contents to `wl-copy`, which forks and keeps serving
+/// them on its own.
+pub fn copy(program: &str, contents: &Contents, serve: Serve) -> Result<(), ClipboardError> {
+    let failed = |reason: String| ClipboardError::Command {
+        program: program.to_string(),
+        reason,
+    };
+
+    let mut command = Command::new(program);
+    command.arg("--type").arg(&contents.mime_type);
+    if serve == Serve::Once {
+        command.arg("--paste-once");
+    }
+
+    // The forked wl-copy inherits stdout and stderr, so capturing either
+    // would block until the clipboard gets replaced.
+    let mut child = command
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|e| failed(e.to_string()))?;
+
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(&contents.data)?;
+    }
+
+    let status = child.wait()?;
+    if !status.success() {
+        return Err(failed(format!("exited with {status}")));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::os::unix::fs::PermissionsExt;
+    use std::path::Path;
+
+    use super::*;
+
+    /// A `wl-copy` that writes its arguments and input next to itself.
+    fn stub(dir: &Path, exit_code: i32) -> String {
+        let path = dir.join("wl-copy");
+        let script = format!(
+            "#!/bin/sh\necho \"$@\" > '{dir}/args'\ncat > '{dir}/input'\nexit {exit_code}\n",
+            dir = dir.display()
+        );
+        fs::write(&path, script).expect("write stub");
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o755))
+            .expect("make stub executable");
+        path.to_string_lossy().into_owned()
+    }
+
+    #[test]
+    fn pipes_contents_and_options_to_wl_copy() {
+        let dir = tempfile::tempdir().expect("temporary directory");
+        let program = stub(dir.path(), 0);
+
+        copy(&program, &Contents::text("/home/me/notes.md"), Serve::Once).expect("copy");
+
+        let input = fs::read_to_string(dir.path().join("input")).unwrap();
+        let args = fs::read_to_string(dir.path().join("args")).unwrap();
+        assert_eq!(input, "/home/me/notes.md");
+        assert_eq!(args.trim(), "--type text/plain;charset=utf-8 --paste-once");
+    }
+
+    #[test]
+    fn reports_a_failing_wl_copy() {
+        let dir = tempfile::tempdir().expect("temporary directory");
+        let program = stub(dir.path(), 1);
+
+        let result = copy(&program, &Contents::text("42"), Serve::UntilReplaced);
+
+        assert!(matches!(result, Err(ClipboardError::Command { .. })));
+    }
+}
@@ -1,11 +1,15 @@
-use directories::{ProjectDirs, UserDirs};
+use directories::{BaseDirs, ProjectDirs, UserDirs};
 use serde::{Deserialize, Serialize};
-use std::collections::HashSet;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt;
 use std::path::Path;
 use std::{env, path::PathBuf};
 use std::{fs, io};
 use tracing::error;
 
+use crate::core::ItemKind;
+use crate::core::apps::{AppDirectory, AppSource};
+use crate::core::keymap::{Keymap, KeymapSettings};
 use crate::daemon::scanners;
 
 /// Utility struct for waycast configuration. The idea
@@ -26,10 +30,40 @@ pub struct AppConfig {
     pub database_file: PathBuf,
     /// Command used to open a project, with `{path}` substituted at launch.
     pub project_open_command: String,
+    /// More ways to open a project, offered in its action panel
+    pub project_commands: Vec<ProjectCommand>,
     /// Directories for app data. XDG dirs from the freedesktop spec
     pub app_dir: AppDirectories,
     /// Directories to scan for the different item types
     pub scan_paths: ScanDirectories,
+    /// Launching apps, and apps installed more than one way
+    pub apps: AppsConfig,
+    /// How launched programs are started, for everything and per app
+    pub launch: LaunchConfig,
+    /// Weights and limits used to rank search results
+    pub ranking: RankingConfig,
+    /// Launcher UI behavior
+    pub ui: UiConfig,
+    /// Calculator result formatting and history
+    pub calculator: CalculatorConfig,
+    /// How things get put on the clipboard
+    pub clipboard: ClipboardConfig,
+    /// What picking an emoji does
+    pub emoji: EmojiConfig,
+    /// Switching between open windows
+    pub windows: WindowsConfig,
+    /// Running typed commands
+    pub run: RunConfig,
+    /// Indexing browser bookmarks and history
+    pub browsers: BrowsersConfig,
+    /// Connecting to SSH hosts
+    pub ssh: SshConfig,
+    /// Files recently opened in any app
+    pub recent_files: RecentFilesConfig,
+    /// Entries declared in `[[commands]]`
+    pub commands: Vec<CustomCommand>,
+    /// Out-of-process item sources declared in `[[plugins]]`
+    pub plugins: Vec<PluginConfig>,
 }
 
 impl AppConfig {
@@ -45,8 +79,23 @@ impl AppConfig {
             lock_file: app_dir.runtime.join("waycast.lock"),
             socket_file: app_dir.runtime.join("waycast.sock"),
             database_file: app_dir.data.join("waycast.db"),
-            scan_paths: ScanDirectories::from_file(&file),
+            scan_paths: ScanDirectories::from_file(&file, &app_dir.data),
+            apps: AppsConfig::from_file(&file),
+            launch: file.launch.clone().validated(),
+            ranking: RankingConfig::from_file(&file),
+            ui: UiConfig::from_file(&file),
+            calculator: file.calculator,
+            clipboard: file.clipboard.clone(),
+            emoji: file.emoji.clone(),
+            windows: file.windows,
+            run: file.run.clone(),
+            browsers: file.browsers,
+            ssh: file.ssh,
+            recent_files: file.recent_files,
+            commands: file.commands.clone(),
+            plugins: file.plugins.clone(),
             project_open_command: file.projects.open_command,
+            project_commands: file.projects.commands,
             config_file,
             app_dir,
         }
@@ -110,7 +159,14 @@ impl Default for AppDirectories {
 /// Directories to scan for the different item types
 #[derive(Debug, Serialize)]
 pub struct ScanDirectories {
-    pub apps: HashSet<PathBuf>,
+    /// Directories of desktop entries, the ones whose entries win when two
+    /// have the same id first
+    pub apps: Vec<AppDirectory>,
+    /// Directories holding AppImages
+    pub appimages: HashSet<PathBuf>,
+    /// Where the desktop entries and icons extracted from AppImages are
+    /// kept, in `applications` and `icons`
+    pub appimage_data: PathBuf,
     pub projects: HashSet<PathBuf>,
     pub files: HashSet<PathBuf>,
     /// Directory names the file scanner should skip
@@ -118,15 +174,19 @@ pub struct ScanDirectories {
 }
 
 impl ScanDirectories {
-    fn from_file(file: &WaycastConfig) -> Self {
+    fn from_file(file: &WaycastConfig, data_dir: &Path) -> Self {
         let files = if file.files.search_paths.is_empty() {
             scanners::default_search_list()
         } else {
             expand_all(&file.files.search_paths)
         };
+        let appimages = expand_all(&file.apps.appimage_dirs);
+        let appimage_data = data_dir.join("appimages");
 
         Self {
-            apps: freedesktop::application_entry_paths().into_iter().collect(),
+            apps: app_directories(&file.apps, &appimage_data, !appimages.is_empty()),
+            appimages,
+            appimage_data,
             projects: expand_all(&file.projects.search_paths),
             files,
             ignore_dirs: file.files.ignore_dirs.clone(),
@@ -134,6 +194,698 @@ impl ScanDirectories {
     }
 }
 
+#[derive(Debug, Clone, Serialize)]
+pub struct AppsConfig {
+    /// What to do with an app installed more than one way
+    pub duplicates: DuplicateApps,
+    /// Terminal that apps with `Terminal=true` run in, followed by whatever
+    /// it needs before the command to run
+    pub terminal: Vec<String>,
+}
+
+impl AppsConfig {
+    /// Apps share the `run` terminal unless they're given their own.
+    fn from_file(file: &WaycastConfig) -> Self {
+        let terminal = if file.apps.terminal.is_empty() {
+            file.run.terminal.clone()
+        } else {
+            file.apps.terminal.clone()
+        };
+
+        Self {
+            duplicates: file.apps.duplicates,
+            terminal,
+        }
+    }
+}
+
+/// How launched programs are started. The top level applies to every
+/// launch, and `apps` adds to it for the desktop entries it names.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LaunchConfig {
+    pub mode: LaunchMode,
+    /// Run in front of every launched command, like `["uwsm", "app", "--"]`
+    pub prefix: Vec<String>,
+    /// Environment variables set for every launch
+    pub env: BTreeMap<String, String>,
+    /// systemd slice the scopes or services go in
+    pub slice: Option<String>,
+    /// systemd properties for the scopes or services, like `MemoryMax`
+    pub properties: BTreeMap<String, String>,
+    /// Settings for single apps, by desktop entry id
+    pub apps: BTreeMap<String, AppLaunchConfig>,
+}
+
+impl LaunchConfig {
+    /// How to start `app_id`, or anything that isn't an app when it's
+    /// `None`. An app's prefix goes after the global one, and its mode,
+    /// slice, variables and properties win over the global ones.
+    pub fn profile(&self, app_id: Option<&str>) -> LaunchProfile {
+        let mut profile = LaunchProfile {
+            mode: self.mode,
+            prefix: self.prefix.clone(),
+            env: self.env.clone(),
+            slice: self.slice.clone(),
+            properties: self.properties.clone(),
+        };
+
+        if let Some(app) = app_id.and_then(|id| self.apps.get(id)) {
+            profile.mode = app.mode.unwrap_or(profile.mode);
+            profile.prefix.extend(app.prefix.iter().cloned());
+            profile.env.extend(app.env.clone());
+            profile.slice = app.slice.clone().or(profile.slice);
+            profile.properties.extend(app.properties.clone());
+        }
+
+        profile
+    }
+
+    /// The settings with every malformed slice and property name left out,
+    /// and reported. Launches are double-forked, so systemd-run refusing
+    /// one would otherwise go unnoticed. Property values aren't checked,
+    /// since what's valid depends on the property.
+    fn validated(mut self) -> Self {
+        drop_invalid_unit_settings("[launch]", &mut self.slice, &mut self.properties);
+        for (id, app) in &mut self.apps {
+            let section = format!("[launch.apps.\"{id}\"]");
+            drop_invalid_unit_settings(&section, &mut app.slice, &mut app.properties);
+        }
+        self
+    }
+}
+
+fn drop_invalid_unit_settings(
+    section: &str,
+    slice: &mut Option<String>,
+    properties: &mut BTreeMap<String, String>,
+) {
+    if let Some(name) = slice.take_if(|name| !is_slice_name(name)) {
+        error!("Ignoring slice \"{name}\" in {section}: slices are named like \"app.slice\"");
+    }
+    properties.retain(|key, value| {
+        let valid = is_property_name(key) && !value.contains('\n');
+        if !valid {
+            error!("Ignoring property \"{key}\" in {section}: not a systemd property setting");
+        }
+        valid
+    });
+}
+
+/// Whether `name` is a slice unit systemd would accept, like
+/// `app-graphical.slice`.
+fn is_slice_name(name: &str) -> bool {
+    name.strip_suffix(".slice").is_some_and(|prefix| {
+        !prefix.is_empty()
+            && prefix
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | ':' | '\\'))
+    })
+}
+
+/// Whether `key` could be a systemd property, which are all alphanumeric,
+/// like `MemoryMax` or `CPUWeight`.
+fn is_property_name(key: &str) -> bool {
+    !key.is_empty() && key.chars().all(|c| c.is_ascii_alphanumeric())
+}
+
+/// A `[launch.apps."<id>"]` section. Anything left out is the global
+/// setting.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AppLaunchConfig {
+    pub mode: Option<LaunchMode>,
+    pub prefix: Vec<String>,
+    pub env: BTreeMap<String, String>,
+    pub slice: Option<String>,
+    pub properties: BTreeMap<String, String>,
+}
+
+/// Everything one launch is started with, from [`LaunchConfig::profile`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LaunchProfile {
+    pub mode: LaunchMode,
+    pub prefix: Vec<String>,
+    pub env: BTreeMap<String, String>,
+    pub slice: Option<String>,
+    pub properties: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum LaunchMode {
+    /// A transient systemd scope, run by waycast with its environment
+    #[default]
+    Scope,
+    /// A transient systemd user service, which gets the user manager's
+    /// environment and logs to the journal. GUI apps only start if the
+    /// session imported `WAYLAND_DISPLAY` into the user manager
+    Service,
+    /// Forked with no unit of its own, in waycast's cgroup
+    Fork,
+}
+
+/// The XDG application directories, plus wherever flatpak and snap export
+/// their apps when they're on, since those aren't always in
+/// `XDG_DATA_DIRS`. The user's own directory goes first so their entries
+/// override the system's, as the spec has it.
+fn app_directories(
+    settings: &AppSettings,
+    appimage_data: &Path,
+    appimages: bool,
+) -> Vec<AppDirectory> {
+    let data_home = BaseDirs::new().map(|dirs| dirs.data_dir().to_path_buf());
+    let mut paths: Vec<PathBuf> = data_home
+        .iter()
+        .map(|dir| dir.join("applications"))
+        .chain(freedesktop::application_entry_paths())
+        .collect();
+
+    if settings.flatpak {
+        paths.extend(
+            data_home
+                .iter()
+                .map(|dir| dir.join("flatpak/exports/share/applications")),
+        );
+        paths.push(PathBuf::from("/var/lib/flatpak/exports/share/applications"));
+    }
+    if settings.snap {
+        paths.push(PathBuf::from("/var/lib/snapd/desktop/applications"));
+    }
+    if let Some(data_home) = &data_home {
+        paths.sort_by_key(|path| !path.starts_with(data_home));
+    }
+
+    let mut dirs: Vec<AppDirectory> = Vec::with_capacity(paths.len() + 1);
+    for path in paths {
+        let source = AppSource::of_directory(&path);
+        let enabled = match source {
+            AppSource::Flatpak => settings.flatpak,
+            AppSource::Snap => settings.snap,
+            _ => true,
+        };
+
+        if enabled && !dirs.iter().any(|dir| dir.path == path) {
+            dirs.push(AppDirectory { path, source });
+        }
+    }
+
+    if appimages {
+        dirs.push(AppDirectory {
+            path: appimage_data.join("applications"),
+            source: AppSource::AppImage,
+        });
+    }
+
+    dirs
+}
+
+/// How search results get ranked against each other.
+///
+/// A result's total is `fuzzy * kind weight + frecency + recency`, where
+/// frecency and recency are derived from the launch history and scaled by
+/// their weights here.
+#[derive(Debug, Clone, Serialize)]
+pub struct RankingConfig {
+    /// How many results a search returns across all kinds
+    pub max_results: usize,
+    /// Multiplier applied to fuzzy scores from secondary keys (descriptions)
+    pub secondary_key_weight: f32,
+    /// Multiplier applied to fuzzy scores from an app's generic name,
+    /// keywords, categories and translated name
+    pub keyword_weight: f32,
+    /// Points added for how often an item has been launched
+    pub frecency_weight: f32,
+    /// Points added for how recently an item was launched
+    pub recency_weight: f32,
+    pub apps: KindRanking,
+    pub projects: KindRanking,
+    pub files: KindRanking,
+    pub windows: KindRanking,
+    pub bookmarks: KindRanking,
+    pub ssh: KindRanking,
+    pub recent_files: KindRanking,
+}
+
+/// Ranking settings for a single item kind.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct KindRanking {
+    /// Multiplier applied to the fuzzy score of items of this kind
+    pub weight: f32,
+    /// Most results of this kind a single search returns
+    pub limit: usize,
+}
+
+impl Default for KindRanking {
+    fn default() -> Self {
+        Self {
+            weight: 1.0,
+            limit: 5,
+        }
+    }
+}
+
+impl RankingConfig {
+    fn from_file(file: &WaycastConfig) -> Self {
+        let search = &file.search;
+
+        Self {
+            max_results: search.max_results,
+            secondary_key_weight: search.secondary_key_weight,
+            keyword_weight: search.keyword_weight,
+            frecency_weight: search.frecency_weight,
+            recency_weight: search.recency_weight,
+            apps: search.apps,
+            projects: search.projects,
+            files: search.files,
+            windows: search.windows,
+            bookmarks: search.bookmarks,
+            ssh: search.ssh,
+            recent_files: search.recent_files,
+        }
+    }
+
+    pub fn kind(&self, kind: ItemKind) -> KindRanking {
+        match kind {
+            ItemKind::DesktopEntry => self.apps,
+            ItemKind::Project => self.projects,
+            ItemKind::File => self.files,
+            ItemKind::Window => self.windows,
+            ItemKind::Bookmark => self.bookmarks,
+            ItemKind::SshHost => self.ssh,
+            ItemKind::RecentFile => self.recent_files,
+            ItemKind::Calculator
+            | ItemKind::Clipboard
+            | ItemKind::Emoji
+            | ItemKind::Command
+            | ItemKind::TerminalCommand
+            | ItemKind::Executable
+            | ItemKind::Custom
+            | ItemKind::Plugin
+            | ItemKind::Unknown => KindRanking::default(),
+        }
+    }
+}
+
+impl Default for RankingConfig {
+    fn default() -> Self {
+        Self::from_file(&WaycastConfig::default())
+    }
+}
+
+/// Launcher UI behavior.
+#[derive(Debug, Clone, Serialize)]
+pub struct UiConfig {
+    /// How long typing has to pause before a search is sent. 0 searches on
+    /// every keystroke.
+    pub search_debounce_ms: u64,
+    /// Which output (monitor) the launcher shows up on
+    pub output: OutputTarget,
+    /// Size and position overrides, keyed by output name
+    pub outputs: HashMap<String, OutputOverride>,
+    /// Show a preview of the selected file next to the results
+    pub preview: bool,
+    /// What the keys do, from `[keymap]`
+    pub keymap: Keymap,
+}
+
+impl UiConfig {
+    fn from_file(file: &WaycastConfig) -> Self {
+        Self {
+            search_debounce_ms: file.ui.search_debounce_ms,
+            output: file.ui.output.clone(),
+            outputs: file.ui.outputs.clone(),
+            preview: file.ui.preview,
+            keymap: Keymap::new(&file.keymap),
+        }
+    }
+}
+
+/// Which output the launcher shows up on.
+///
+/// Read from config as a plain string: `active`, `focused`, `cursor`, or
+/// anything else is taken as an output name like `DP-1`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(from = "String", into = "String")]
+pub enum OutputTarget {
+    /// Let the compositor pick
+    #[default]
+    Active,
+    /// The output with keyboard focus
+    Focused,
+    /// The output under the mouse cursor
+    Cursor,
+    /// A specific output by name
+    Named(String),
+}
+
+impl From<String> for OutputTarget {
+    fn from(value: String) -> Self {
+        match value.as_str() {
+            "active" => Self::Active,
+            "focused" => Self::Focused,
+            "cursor" => Self::Cursor,
+            _ => Self::Named(value),
+        }
+    }
+}
+
+impl From<OutputTarget> for String {
+    fn from(value: OutputTarget) -> Self {
+        value.to_string()
+    }
+}
+
+impl fmt::Display for OutputTarget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Active => f.write_str("active"),
+            Self::Focused => f.write_str("focused"),
+            Self::Cursor => f.write_str("cursor"),
+            Self::Named(name) => f.write_str(name),
+        }
+    }
+}
+
+/// Size and position of the launcher on one output. Anything left unset
+/// falls back to the default size, centered.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct OutputOverride {
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    /// Offset from the left edge of the output. Unset centers horizontally.
+    pub x: Option<i32>,
+    /// Offset from the top edge of the output. Unset centers vertically.
+    pub y: Option<i32>,
+}
+
+/// How calculator results are shown and how many are remembered.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CalculatorConfig {
+    /// Maximum number of decimals shown. Unset shows full precision.
+    pub precision: Option<usize>,
+    /// Separate thousands with commas
+    pub group_digits: bool,
+    /// How many copied calculations are kept in the history
+    pub history_size: usize,
+}
+
+impl Default for CalculatorConfig {
+    fn default() -> Self {
+        Self {
+            precision: None,
+            group_digits: false,
+            history_size: 50,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ClipboardConfig {
+    pub backend: ClipboardBackend,
+    /// The `wl-copy` program used when data-control isn't available
+    pub wl_copy: String,
+    pub history: ClipboardHistoryConfig,
+}
+
+impl Default for ClipboardConfig {
+    fn default() -> Self {
+        Self {
+            backend: ClipboardBackend::Auto,
+            wl_copy: String::from("wl-copy"),
+            history: ClipboardHistoryConfig::default(),
+        }
+    }
+}
+
+/// Recording what gets copied, for the `clip:` search.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ClipboardHistoryConfig {
+    /// Off unless asked for, since it keeps copies of everything on disk
+    pub enabled: bool,
+    /// Oldest entries are dropped past this many
+    pub max_entries: usize,
+    /// Copies larger than this many bytes are not recorded
+    pub max_entry_size: usize,
+    /// Entries older than this many days are dropped
+    pub retention_days: u32,
+    /// Copies offering any of these mime types are never recorded. Password
+    /// managers mark secrets with `x-kde-passwordManagerHint`.
+    pub ignored_mime_types: Vec<String>,
+}
+
+impl Default for ClipboardHistoryConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_entries: 500,
+            max_entry_size: 1024 * 1024,
+            retention_days: 30,
+            ignored_mime_types: vec![String::from("x-kde-passwordManagerHint")],
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ClipboardBackend {
+    /// Data-control when the compositor supports it, `wl-copy` otherwise
+    #[default]
+    Auto,
+    /// Only the ext or wlr data-control protocol
+    DataControl,
+    /// Only `wl-copy`
+    WlCopy,
+}
+
+/// Switching to open windows through the compositor's IPC. Only Sway, i3,
+/// Hyprland and niri are supported.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct WindowsConfig {
+    /// List open windows in search results
+    pub enabled: bool,
+    /// Launching an app that already has a window focuses that window
+    /// instead of starting another instance
+    pub focus_existing: bool,
+}
+
+impl Default for WindowsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            focus_existing: true,
+        }
+    }
+}
+
+/// Running what's typed after `prefix` as a shell command.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RunConfig {
+    pub prefix: String,
+    /// Terminal program, followed by whatever it needs before the command
+    /// to run, e.g. `["alacritty", "-e"]`
+    pub terminal: Vec<String>,
+    /// How many past commands are recalled
+    pub history_size: usize,
+    /// Index the programs on `$PATH` so they can be run by name
+    pub index_path: bool,
+}
+
+impl Default for RunConfig {
+    fn default() -> Self {
+        Self {
+            prefix: String::from(">"),
+            terminal: vec![String::from("xdg-terminal-exec")],
+            history_size: 100,
+            index_path: true,
+        }
+    }
+}
+
+/// Bookmarks and history read out of Firefox and Chromium-based browser
+/// profiles.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BrowsersConfig {
+    /// Index bookmarks, and the history if that's on too
+    pub enabled: bool,
+    /// Index the most visited pages in the history as well
+    pub history: bool,
+    /// How many history entries are indexed per profile
+    pub history_limit: u32,
+}
+
+impl Default for BrowsersConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            history: true,
+            history_limit: 500,
+        }
+    }
+}
+
+/// Hosts from the SSH client config, opened in the `run` terminal.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SshConfig {
+    /// Index hosts from `~/.ssh/config` and the files it includes
+    pub enabled: bool,
+    /// Index hosts from `~/.ssh/known_hosts` as well. Hashed entries are
+    /// skipped since their names can't be recovered
+    pub known_hosts: bool,
+}
+
+impl Default for SshConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            known_hosts: true,
+        }
+    }
+}
+
+/// Files opened in any app, from `~/.local/share/recently-used.xbel`.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RecentFilesConfig {
+    pub enabled: bool,
+    /// How many of the most recently opened files are indexed
+    pub max_items: usize,
+}
+
+impl Default for RecentFilesConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            max_items: 200,
+        }
+    }
+}
+
+/// An entry of the user's own from `[[commands]]`, which runs `command` or
+/// opens `url`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CustomCommand {
+    /// Shown in the results, and what identifies the entry in the launch
+    /// history
+    pub title: String,
+    pub description: Option<String>,
+    pub icon: Option<String>,
+    /// More words to find it by
+    #[serde(default)]
+    pub keywords: Vec<String>,
+    /// Shell command to run
+    pub command: Option<String>,
+    /// Run `command` in the `[run]` terminal
+    #[serde(default)]
+    pub terminal: bool,
+    /// URL or path to open with the default app instead of a command
+    pub url: Option<String>,
+}
+
+/// Another way to open a project, from `[[projects.commands]]`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ProjectCommand {
+    /// Shown in the action panel
+    pub title: String,
+    /// Command to run, with `{path}` substituted like `open_command`
+    pub command: String,
+}
+
+/// A plugin from `[[plugins]]`: a program waycast runs and talks JSON-RPC
+/// to over stdio. See `core::plugins` for the protocol.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PluginConfig {
+    /// Unique name, which also goes in the ids of the plugin's items
+    pub name: String,
+    /// Program to run, followed by its arguments
+    pub command: Vec<String>,
+    /// Only search this plugin when the query starts with this. Plugins
+    /// without one are searched along with everything else
+    pub prefix: Option<String>,
+    /// Icon for items that don't bring their own
+    pub icon: Option<String>,
+    /// How long a call can take before it's given up on
+    #[serde(default = "default_plugin_timeout_ms")]
+    pub timeout_ms: u64,
+}
+
+fn default_plugin_timeout_ms() -> u64 {
+    500
+}
+
+/// What picking an emoji does, and which skin tone it comes in.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct EmojiConfig {
+    pub skin_tone: EmojiSkinTone,
+    pub action: EmojiAction,
+    /// Virtual keyboard program used by the `type` action
+    pub type_command: String,
+    /// How long to wait for the launcher to give up keyboard focus before
+    /// typing
+    pub type_delay_ms: u64,
+}
+
+impl Default for EmojiConfig {
+    fn default() -> Self {
+        Self {
+            skin_tone: EmojiSkinTone::Default,
+            action: EmojiAction::Copy,
+            type_command: String::from("wtype"),
+            type_delay_ms: 150,
+        }
+    }
+}
+
+/// Skin tone for emoji that have them. The others are still searchable by
+/// including the tone in the query.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum EmojiSkinTone {
+    /// The yellow, untoned emoji
+    #[default]
+    Default,
+    Light,
+    MediumLight,
+    Medium,
+    MediumDark,
+    Dark,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum EmojiAction {
+    /// Put it on the clipboard
+    #[default]
+    Copy,
+    /// Type it into the focused window with `type_command`
+    Type,
+}
+
+/// What to do with an app installed more than one way, like Firefox from
+/// both the distribution and Flathub. Apps are matched by name.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum DuplicateApps {
+    /// Keep one, preferring native packages, then flatpak, snap and AppImage
+    #[default]
+    Merge,
+    /// Keep them all, each labelled with how it was installed
+    SideBySide,
+}
+
 fn expand_all(paths: &HashSet<PathBuf>) -> HashSet<PathBuf> {
     paths.iter().map(|path| expand_home(path)).collect()
 }
@@ -155,8 +907,45 @@ fn expand_home(path: &Path) -> PathBuf {
 #[derive(Debug, Default, Deserialize)]
 #[serde(default, deny_unknown_fields)]
 struct WaycastConfig {
+    apps: AppSettings,
+    launch: LaunchConfig,
     files: FileSettings,
     projects: ProjectSettings,
+    search: SearchSettings,
+    ui: UiSettings,
+    keymap: KeymapSettings,
+    calculator: CalculatorConfig,
+    clipboard: ClipboardConfig,
+    emoji: EmojiConfig,
+    windows: WindowsConfig,
+    run: RunConfig,
+    browsers: BrowsersConfig,
+    ssh: SshConfig,
+    recent_files: RecentFilesConfig,
+    commands: Vec<CustomCommand>,
+    plugins: Vec<PluginConfig>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct AppSettings {
+    flatpak: bool,
+    snap: bool,
+    appimage_dirs: HashSet<PathBuf>,
+    duplicates: DuplicateApps,
+    terminal: Vec<String>,
+}
+
+impl Default for AppSettings {
+    fn default() -> Self {
+        Self {
+            flatpak: true,
+            snap: true,
+            appimage_dirs: HashSet::from([PathBuf::from("~/Applications")]),
+            duplicates: DuplicateApps::default(),
+            terminal: Vec::new(),
+        }
+    }
 }
 
 #[derive(Debug, Default, Deserialize)]
@@ -171,6 +960,7 @@ struct FileSettings {
 struct ProjectSettings {
     search_paths: HashSet<PathBuf>,
     open_command: String,
+    commands: Vec<ProjectCommand>,
 }
 
 impl Default for ProjectSettings {
@@ -178,10 +968,62 @@ impl Default for ProjectSettings {
         Self {
             search_paths: HashSet::new(),
             open_command: String::from("code -n {path}"),
+            commands: Vec::new(),
         }
     }
 }
 
+#[derive(Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct SearchSettings {
+    max_results: usize,
+    secondary_key_weight: f32,
+    keyword_weight: f32,
+    frecency_weight: f32,
+    recency_weight: f32,
+    apps: KindRanking,
+    projects: KindRanking,
+    files: KindRanking,
+    windows: KindRanking,
+    bookmarks: KindRanking,
+    ssh: KindRanking,
+    recent_files: KindRanking,
+}
+
+impl Default for SearchSettings {
+    fn default() -> Self {
+        Self {
+            max_results: 5,
+            secondary_key_weight: 0.9,
+            keyword_weight: 0.7,
+            frecency_weight: 20.0,
+            recency_weight: 20.0,
+            apps: KindRanking::default(),
+            projects: KindRanking::default(),
+            files: KindRanking {
+                weight: 0.9,
+                limit: 5,
+            },
+            windows: KindRanking::default(),
+            bookmarks: KindRanking {
+                weight: 0.9,
+                limit: 5,
+            },
+            ssh: KindRanking::default(),
+            recent_files: KindRanking::default(),
+        }
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct UiSettings {
+    search_debounce_ms: u64,
+    output: OutputTarget,
+    outputs: HashMap<String, OutputOverride>,
+    preview: bool,
+}
+
 impl WaycastConfig {
     /// A missing config file is normal. Anything else is reported and then
     /// falls back to defaults so the daemon still comes up.
@@ -227,3 +1069,49 @@ pub fn data_dir() -> Option<PathBuf> {
 
     None
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn leaves_out_slices_and_properties_systemd_would_refuse() {
+        let config = LaunchConfig {
+            slice: Some(String::from("graphical")),
+            properties: BTreeMap::from([
+                (String::from("MemoryMax"), String::from("2G")),
+                (String::from("Memory Max"), String::from("2G")),
+                (String::from("CPUWeight"), String::from("200\nNice=-20")),
+                (String::new(), String::from("1")),
+            ]),
+            apps: BTreeMap::from([(
+                String::from("steam"),
+                AppLaunchConfig {
+                    slice: Some(String::from("app-games.slice")),
+                    properties: BTreeMap::from([(String::from("IOWeight="), String::from("10"))]),
+                    ..AppLaunchConfig::default()
+                },
+            )]),
+            ..LaunchConfig::default()
+        }
+        .validated();
+
+        assert_eq!(config.slice, None);
+        assert_eq!(
+            config.properties,
+            BTreeMap::from([(String::from("MemoryMax"), String::from("2G"))])
+        );
+        let steam = &config.apps["steam"];
+        assert_eq!(steam.slice.as_deref(), Some("app-games.slice"));
+        assert!(steam.properties.is_empty());
+    }
+
+    #[test]
+    fn knows_slice_names() {
+        assert!(is_slice_name("app.slice"));
+        assert!(is_slice_name("app-graphical.slice"));
+        assert!(!is_slice_name(".slice"));
+        assert!(!is_slice_name("app"));
+        assert!(!is_slice_name("my app.slice"));
+    }
+}
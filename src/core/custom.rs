@@ -0,0 +1,102 @@
+//! The user's own entries, declared as `[[commands]]` in waycast.toml.
+
+use std::collections::HashSet;
+
+use tracing::warn;
+
+use super::config::CustomCommand;
+use super::{ItemKind, LauncherItem};
+
+pub const ICON: &str = "system-run";
+
+/// Items for every usable entry. An entry needs exactly one of `command`
+/// and `url`, and a title no other entry has, since the title is its id.
+pub fn items(commands: &[CustomCommand]) -> Vec<LauncherItem> {
+    let mut titles = HashSet::new();
+
+    commands
+        .iter()
+        .filter(|custom| {
+            if custom.command.is_some() == custom.url.is_some() {
+                warn!(
+                    "Skipping command \"{}\": it needs either a command or a url",
+                    custom.title
+                );
+                return false;
+            }
+            if !titles.insert(custom.title.as_str()) {
+                warn!("Skipping command \"{}\": the title is taken", custom.title);
+                return false;
+            }
+            true
+        })
+        .map(to_item)
+        .collect()
+}
+
+/// The entry an item came from.
+pub fn find<'a>(commands: &'a [CustomCommand], id: &str) -> Option<&'a CustomCommand> {
+    commands.iter().find(|custom| custom.title == id)
+}
+
+fn to_item(custom: &CustomCommand) -> LauncherItem {
+    // Keywords go in the description, which is searched along with the
+    // title, the same way symbol keywords are.
+    let description = custom
+        .description
+        .iter()
+        .cloned()
+        .chain((!custom.keywords.is_empty()).then(|| custom.keywords.join(" ")))
+        .collect::<Vec<_>>()
+        .join(" · ");
+
+    LauncherItem {
+        id: custom.title.clone(),
+        kind: ItemKind::Custom,
+        title: custom.title.clone(),
+        description: (!description.is_empty()).then_some(description),
+        icon: custom.icon.clone().unwrap_or_else(|| ICON.into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn command(title: &str, command: Option<&str>, url: Option<&str>) -> CustomCommand {
+        CustomCommand {
+            title: title.into(),
+            description: None,
+            icon: None,
+            keywords: Vec::new(),
+            command: command.map(String::from),
+            terminal: false,
+            url: url.map(String::from),
+        }
+    }
+
+    #[test]
+    fn skips_unusable_entries() {
+        let mut deploy = command("Deploy staging", Some("./deploy.sh staging"), None);
+        deploy.description = Some("Ship main to staging".into());
+        deploy.keywords = vec!["ship".into(), "release".into()];
+        let commands = [
+            deploy,
+            command("Status page", None, Some("https://status.example.com")),
+            command("Nothing to do", None, None),
+            command("Both", Some("true"), Some("https://example.com")),
+            command("Status page", Some("true"), None),
+        ];
+
+        let items = items(&commands);
+
+        let titles: Vec<&str> = items.iter().map(|item| item.title.as_str()).collect();
+        assert_eq!(titles, ["Deploy staging", "Status page"]);
+        assert_eq!(
+            items[0].description.as_deref(),
+            Some("Ship main to staging · ship release")
+        );
+        assert_eq!(items[1].description, None);
+        assert_eq!(items[1].icon, ICON);
+    }
+}
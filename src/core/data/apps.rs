@@ -0,0 +1,101 @@
+use std::collections::HashMap;
+
+use sqlx::SqlitePool;
+
+use crate::core::apps::{AppDetails, AppSource};
+
+use super::DataError;
+
+pub struct AppDetailsRepository {
+    pub pool: SqlitePool,
+}
+
+#[derive(sqlx::FromRow, Debug)]
+struct AppDetailsRow {
+    item_id: String,
+    localized_name: Option<String>,
+    generic_name: Option<String>,
+    keywords: String,
+    categories: String,
+    source: AppSource,
+}
+
+impl From<AppDetailsRow> for AppDetails {
+    fn from(row: AppDetailsRow) -> Self {
+        Self {
+            id: row.item_id,
+            localized_name: row.localized_name,
+            generic_name: row.generic_name,
+            keywords: row.keywords.lines().map(String::from).collect(),
+            categories: row.categories.lines().map(String::from).collect(),
+            source: row.source,
+        }
+    }
+}
+
+impl AppDetailsRepository {
+    /// Replace the details of every app with `details`, all at once so a
+    /// search never sees half of them.
+    pub async fn replace(&self, details: Vec<AppDetails>) -> Result<(), DataError> {
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query!("delete from app_details")
+            .execute(&mut *tx)
+            .await?;
+
+        for app in details {
+            let keywords = app.keywords.join("\n");
+            let categories = app.categories.join("\n");
+
+            sqlx::query!(
+                r#"
+                    insert or replace into app_details (
+                        item_id,
+                        localized_name,
+                        generic_name,
+                        keywords,
+                        categories,
+                        source
+                    )
+                    values (?, ?, ?, ?, ?, ?)
+                "#,
+                app.id,
+                app.localized_name,
+                app.generic_name,
+                keywords,
+                categories,
+                app.source
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    /// Details of every app, keyed by desktop entry id.
+    pub async fn all(&self) -> Result<HashMap<String, AppDetails>, DataError> {
+        let rows = sqlx::query_as!(
+            AppDetailsRow,
+            r#"
+            select
+                item_id,
+                localized_name,
+                generic_name,
+                keywords,
+                categories,
+                source as "source: AppSource"
+            from app_details
+        "#
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| (row.item_id.clone(), AppDetails::from(row)))
+            .collect())
+    }
+}
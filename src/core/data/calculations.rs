@@ -0,0 +1,107 @@
+use sqlx::SqlitePool;
+
+use crate::core::calculator::Calculation;
+
+use super::DataError;
+use super::launches::now_epoch_secs;
+
+pub struct CalculationHistoryRepository {
+    pub pool: SqlitePool,
+}
+
+impl CalculationHistoryRepository {
+    /// Record a calculation, keeping at most `limit` entries around.
+    ///
+    /// Replacing rather than updating a repeated expression gives it a new
+    /// id, which keeps the order right when timestamps tie.
+    pub async fn record(&self, calculation: &Calculation, limit: usize) -> Result<(), DataError> {
+        let calculated_at = now_epoch_secs();
+        let limit = limit as i64;
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query!(
+            r#"
+                insert or replace into calculations (expression, result, display, calculated_at)
+                values (?, ?, ?, ?)
+            "#,
+            calculation.expression,
+            calculation.result,
+            calculation.display,
+            calculated_at
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query!(
+            r#"
+                delete from calculations
+                where id not in (
+                    select id from calculations
+                    order by calculated_at desc, id desc
+                    limit ?
+                )
+            "#,
+            limit
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    /// Past calculations, most recent first.
+    pub async fn recent(&self, limit: usize) -> Result<Vec<Calculation>, DataError> {
+        let limit = limit as i64;
+        let calculations = sqlx::query_as!(
+            Calculation,
+            r#"
+            select expression, result, display
+            from calculations
+            order by calculated_at desc, id desc
+            limit ?
+        "#,
+            limit
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(calculations)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::data::WaycastData;
+
+    fn calculation(expression: &str, result: &str) -> Calculation {
+        Calculation {
+            expression: expression.into(),
+            result: result.into(),
+            display: result.into(),
+        }
+    }
+
+    #[tokio::test]
+    async fn repeated_expressions_are_stored_once_and_history_is_capped() {
+        let directory = tempfile::tempdir().expect("temporary database directory");
+        let database = WaycastData::writeable_connection(directory.path().join("waycast.db"))
+            .await
+            .expect("initialize temporary database");
+        let history = database.calculations();
+
+        history.record(&calculation("1+1", "2"), 2).await.unwrap();
+        history.record(&calculation("2*3", "6"), 2).await.unwrap();
+        history.record(&calculation("1+1", "2"), 2).await.unwrap();
+        history
+            .record(&calculation("10/4", "2.5"), 2)
+            .await
+            .unwrap();
+
+        let recent = history.recent(10).await.unwrap();
+        let expressions: Vec<&str> = recent.iter().map(|c| c.expression.as_str()).collect();
+        assert_eq!(expressions, ["10/4", "1+1"]);
+    }
+}
@@ -0,0 +1,160 @@
+use sqlx::SqlitePool;
+
+use crate::core::clipboard::{Contents, HISTORY_ICON};
+use crate::core::config::ClipboardHistoryConfig;
+use crate::core::{ItemKind, LauncherItem};
+
+use super::DataError;
+use super::launches::now_epoch_secs;
+
+const SECS_PER_DAY: i64 = 24 * 60 * 60;
+
+pub struct ClipboardHistoryRepository {
+    pub pool: SqlitePool,
+}
+
+/// A clipboard history entry, without the contents themselves.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClipboardEntry {
+    pub id: i64,
+    pub mime_type: String,
+    pub preview: String,
+    /// Unix timestamp (seconds) of the most recent copy
+    pub copied_at: i64,
+}
+
+impl From<ClipboardEntry> for LauncherItem {
+    fn from(entry: ClipboardEntry) -> Self {
+        Self {
+            id: entry.id.to_string(),
+            kind: ItemKind::Clipboard,
+            title: entry.preview,
+            description: Some(entry.mime_type),
+            icon: HISTORY_ICON.into(),
+        }
+    }
+}
+
+impl ClipboardHistoryRepository {
+    /// Record a copy, then drop whatever the config says is too old or one
+    /// too many.
+    pub async fn record(
+        &self,
+        contents: &Contents,
+        cfg: &ClipboardHistoryConfig,
+    ) -> Result<(), DataError> {
+        let copied_at = now_epoch_secs();
+        let expires_before = copied_at - i64::from(cfg.retention_days) * SECS_PER_DAY;
+        let max_entries = cfg.max_entries as i64;
+        let preview = contents.preview();
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query!(
+            "delete from clipboard_entries where mime_type = ? and data = ?",
+            contents.mime_type,
+            contents.data
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query!(
+            r#"
+                insert into clipboard_entries (mime_type, data, preview, copied_at)
+                values (?, ?, ?, ?)
+            "#,
+            contents.mime_type,
+            contents.data,
+            preview,
+            copied_at
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query!(
+            "delete from clipboard_entries where copied_at < ?",
+            expires_before
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query!(
+            r#"
+                delete from clipboard_entries
+                where id not in (
+                    select id from clipboard_entries
+                    order by copied_at desc, id desc
+                    limit ?
+                )
+            "#,
+            max_entries
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    /// Entries most recently copied first.
+    pub async fn recent(&self, limit: usize) -> Result<Vec<ClipboardEntry>, DataError> {
+        let limit = limit as i64;
+        let entries = sqlx::query_as!(
+            ClipboardEntry,
+            r#"
+            select id, mime_type, preview, copied_at
+            from clipboard_entries
+            order by copied_at desc, id desc
+            limit ?
+        "#,
+            limit
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(entries)
+    }
+
+    pub async fn contents(&self, id: i64) -> Result<Option<Contents>, DataError> {
+        let contents = sqlx::query_as!(
+            Contents,
+            "select mime_type, data from clipboard_entries where id = ?",
+            id
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(contents)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::data::WaycastData;
+
+    #[tokio::test]
+    async fn repeated_copies_are_stored_once_and_history_is_capped() {
+        let directory = tempfile::tempdir().expect("temporary database directory");
+        let database = WaycastData::writeable_connection(directory.path().join("waycast.db"))
+            .await
+            .expect("initialize temporary database");
+        let history = database.clipboard();
+        let cfg = ClipboardHistoryConfig {
+            enabled: true,
+            max_entries: 2,
+            ..ClipboardHistoryConfig::default()
+        };
+
+        for text in ["first", "second", "first", "third"] {
+            history.record(&Contents::text(text), &cfg).await.unwrap();
+        }
+
+        let recent = history.recent(10).await.unwrap();
+        let previews: Vec<&str> = recent.iter().map(|entry| entry.preview.as_str()).collect();
+        assert_eq!(previews, ["third", "first"]);
+
+        let contents = history.contents(recent[1].id).await.unwrap();
+        assert_eq!(contents, Some(Contents::text("first")));
+    }
+}
@@ -259,6 +259,29 @@ impl LauncherItemRepository {
         Ok(items.into_iter().map(LauncherItem::from).collect())
     }
 
+    /// Pinned items that aren't hidden, in the order they were pinned.
+    pub async fn get_pinned(&self) -> Result<Vec<LauncherItem>, DataError> {
+        let items = sqlx::query_as!(
+            ItemRow,
+            r#"
+            select
+                i.item_id as id,
+                i.kind,
+                i.title,
+                i.description,
+                i.icon
+            from items i
+            join item_overrides o on o.item_id = i.item_id and o.kind = i.kind
+            where o.pinned_at is not null and o.hidden = 0
+            order by o.pinned_at, i.title
+        "#
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(items.into_iter().map(LauncherItem::from).collect())
+    }
+
     pub async fn search(
         &self,
         query: String,
@@ -282,7 +305,7 @@ impl LauncherItemRepository {
                 join items i on i.id = items_fts.rowid
                 where items_fts match ?1
                 and (?2 is null or i.kind = ?2)
-                order by bm25(items_fts, 10.0, 3.0) desc
+                order by bm25(items_fts, 10.0, 3.0, 10.0) desc
                 limit ?3
             "#,
             fts_query,
@@ -428,6 +451,58 @@ mod tests {
         assert_eq!(results[0].id, "notes");
     }
 
+    #[tokio::test]
+    async fn aliases_are_searched_and_outlive_rescans() {
+        let (_directory, database) = database().await;
+        let repository = database.items();
+        let firefox = item("firefox.desktop", ItemKind::DesktopEntry, "Firefox");
+        repository
+            .insert(vec![firefox.clone()])
+            .await
+            .expect("insert items");
+
+        database
+            .overrides()
+            .set_aliases("firefox.desktop", ItemKind::DesktopEntry, &["ff".into()])
+            .await
+            .expect("set aliases");
+        let results = repository
+            .search("ff".into(), None, 20)
+            .await
+            .expect("search aliases");
+        assert_eq!(results.len(), 1);
+
+        // Gone from one scan and back in the next gets the item a new row.
+        repository.insert(Vec::new()).await.expect("empty rescan");
+        repository.insert(vec![firefox]).await.expect("rescan");
+        let results = repository
+            .search("ff".into(), None, 20)
+            .await
+            .expect("search aliases after rescan");
+        assert_eq!(results.len(), 1);
+
+        database
+            .overrides()
+            .set_aliases("firefox.desktop", ItemKind::DesktopEntry, &[])
+            .await
+            .expect("clear aliases");
+        assert!(
+            repository
+                .search("ff".into(), None, 20)
+                .await
+                .expect("search cleared aliases")
+                .is_empty()
+        );
+        assert!(
+            database
+                .overrides()
+                .all()
+                .await
+                .expect("load overrides")
+                .is_empty()
+        );
+    }
+
     #[tokio::test]
     async fn legacy_text_values_decode_through_the_canonical_item_kind() {
         let (_directory, database) = database().await;
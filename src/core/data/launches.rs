@@ -0,0 +1,87 @@
+use std::collections::HashMap;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use sqlx::SqlitePool;
+
+use crate::core::{ItemKind, LauncherItem};
+
+use super::DataError;
+
+pub struct LaunchHistoryRepository {
+    pub pool: SqlitePool,
+}
+
+/// How often and how recently an item was launched.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LaunchStats {
+    pub launches: i64,
+    /// Unix timestamp (seconds) of the most recent launch.
+    pub last_launched_at: i64,
+}
+
+#[derive(sqlx::FromRow, Debug)]
+struct LaunchStatsRow {
+    item_id: String,
+    kind: ItemKind,
+    launches: i64,
+    last_launched_at: i64,
+}
+
+pub fn now_epoch_secs() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("system time before unix epoch?")
+        .as_secs() as i64
+}
+
+impl LaunchHistoryRepository {
+    pub async fn record(&self, item: &LauncherItem) -> Result<(), DataError> {
+        let launched_at = now_epoch_secs();
+
+        sqlx::query!(
+            r#"
+                insert into launches (item_id, kind, launched_at)
+                values (?, ?, ?)
+            "#,
+            item.id,
+            item.kind,
+            launched_at
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Launch stats for every item that has been launched at least once,
+    /// keyed by item id and kind.
+    pub async fn stats(&self) -> Result<HashMap<(String, ItemKind), LaunchStats>, DataError> {
+        let rows = sqlx::query_as!(
+            LaunchStatsRow,
+            r#"
+            select
+                item_id,
+                kind,
+                count(*) as "launches!",
+                max(launched_at) as "last_launched_at!"
+            from launches
+            group by item_id, kind
+        "#
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| {
+                (
+                    (row.item_id, row.kind),
+                    LaunchStats {
+                        launches: row.launches,
+                        last_launched_at: row.last_launched_at,
+                    },
+                )
+            })
+            .collect())
+    }
+}
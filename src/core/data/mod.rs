@@ -10,10 +10,20 @@ use sqlx::{
     sqlite::{SqliteConnectOptions, SqlitePoolOptions},
 };
 
+pub mod apps;
 pub mod cache;
+pub mod calculations;
+pub mod clipboard;
 pub mod items;
+pub mod launches;
+pub mod overrides;
 
+pub use apps::AppDetailsRepository;
+pub use calculations::CalculationHistoryRepository;
+pub use clipboard::ClipboardHistoryRepository;
 pub use items::LauncherItemRepository;
+pub use launches::LaunchHistoryRepository;
+pub use overrides::ItemOverrideRepository;
 
 use self::cache::CacheRepository;
 
@@ -111,6 +121,36 @@ impl WaycastData {
             pool: self.pool.clone(),
         }
     }
+
+    pub fn launches(&self) -> LaunchHistoryRepository {
+        LaunchHistoryRepository {
+            pool: self.pool.clone(),
+        }
+    }
+
+    pub fn calculations(&self) -> CalculationHistoryRepository {
+        CalculationHistoryRepository {
+            pool: self.pool.clone(),
+        }
+    }
+
+    pub fn clipboard(&self) -> ClipboardHistoryRepository {
+        ClipboardHistoryRepository {
+            pool: self.pool.clone(),
+        }
+    }
+
+    pub fn apps(&self) -> AppDetailsRepository {
+        AppDetailsRepository {
+            pool: self.pool.clone(),
+        }
+    }
+
+    pub fn overrides(&self) -> ItemOverrideRepository {
+        ItemOverrideRepository {
+            pool: self.pool.clone(),
+        }
+    }
 }
 
 async fn create_database_directory(database_path: &Path) -> Result<(), DataError> {
@@ -175,7 +215,7 @@ mod tests {
             .fetch_one(&database.pool)
             .await
             .expect("query migration history");
-        assert_eq!(applied_migrations, 1);
+        assert_eq!(applied_migrations, sqlx::migrate!().iter().count() as i64);
     }
 
     #[tokio::test]
@@ -0,0 +1,174 @@
+use std::collections::HashMap;
+
+use sqlx::SqlitePool;
+
+use crate::core::ItemKind;
+
+use super::{DataError, launches::now_epoch_secs};
+
+pub struct ItemOverrideRepository {
+    pub pool: SqlitePool,
+}
+
+/// What the user changed about an item.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ItemOverride {
+    /// Unix timestamp (seconds) of when the item was pinned.
+    pub pinned_at: Option<i64>,
+    pub hidden: bool,
+    /// Other names to find the item by, like `ff` for Firefox.
+    pub aliases: Vec<String>,
+}
+
+#[derive(sqlx::FromRow, Debug)]
+struct ItemOverrideRow {
+    item_id: String,
+    kind: ItemKind,
+    pinned_at: Option<i64>,
+    hidden: bool,
+    aliases: String,
+}
+
+impl ItemOverrideRepository {
+    /// Every item with an override, keyed by item id and kind.
+    pub async fn all(&self) -> Result<HashMap<(String, ItemKind), ItemOverride>, DataError> {
+        let rows = sqlx::query_as!(
+            ItemOverrideRow,
+            r#"
+            select
+                item_id,
+                kind,
+                pinned_at,
+                hidden as "hidden: bool",
+                aliases
+            from item_overrides
+        "#
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| {
+                (
+                    (row.item_id, row.kind),
+                    ItemOverride {
+                        pinned_at: row.pinned_at,
+                        hidden: row.hidden,
+                        aliases: row.aliases.lines().map(String::from).collect(),
+                    },
+                )
+            })
+            .collect())
+    }
+
+    /// The kinds of item with this id that are indexed or have an override.
+    pub async fn kinds_of(&self, item_id: &str) -> Result<Vec<ItemKind>, DataError> {
+        let kinds = sqlx::query_scalar!(
+            r#"
+            select kind as "kind: ItemKind" from items where item_id = ?1
+            union
+            select kind from item_overrides where item_id = ?1
+        "#,
+            item_id
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(kinds)
+    }
+
+    pub async fn set_pinned(
+        &self,
+        item_id: &str,
+        kind: ItemKind,
+        pinned: bool,
+    ) -> Result<(), DataError> {
+        let pinned_at = pinned.then(now_epoch_secs);
+
+        sqlx::query!(
+            r#"
+                insert into item_overrides (item_id, kind, pinned_at)
+                values (?, ?, ?)
+                on conflict(item_id, kind) do update set
+                pinned_at = excluded.pinned_at
+            "#,
+            item_id,
+            kind,
+            pinned_at
+        )
+        .execute(&self.pool)
+        .await?;
+
+        self.prune().await
+    }
+
+    pub async fn set_hidden(
+        &self,
+        item_id: &str,
+        kind: ItemKind,
+        hidden: bool,
+    ) -> Result<(), DataError> {
+        sqlx::query!(
+            r#"
+                insert into item_overrides (item_id, kind, hidden)
+                values (?, ?, ?)
+                on conflict(item_id, kind) do update set
+                hidden = excluded.hidden
+            "#,
+            item_id,
+            kind,
+            hidden
+        )
+        .execute(&self.pool)
+        .await?;
+
+        self.prune().await
+    }
+
+    /// Replace the item's aliases. Blank ones are dropped, so an empty
+    /// list removes them all.
+    pub async fn set_aliases(
+        &self,
+        item_id: &str,
+        kind: ItemKind,
+        aliases: &[String],
+    ) -> Result<(), DataError> {
+        let aliases = aliases
+            .iter()
+            .map(|alias| alias.trim())
+            .filter(|alias| !alias.is_empty())
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        sqlx::query!(
+            r#"
+                insert into item_overrides (item_id, kind, aliases)
+                values (?, ?, ?)
+                on conflict(item_id, kind) do update set
+                aliases = excluded.aliases
+            "#,
+            item_id,
+            kind,
+            aliases
+        )
+        .execute(&self.pool)
+        .await?;
+
+        self.prune().await
+    }
+
+    /// Drop rows that no longer change anything.
+    async fn prune(&self) -> Result<(), DataError> {
+        sqlx::query!(
+            r#"
+                delete from item_overrides
+                where pinned_at is null and hidden = 0 and aliases = ''
+            "#
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+}
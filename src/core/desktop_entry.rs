@@ -0,0 +1,323 @@
+//! Running a desktop entry the way the spec has it: the `Exec` line's
+//! quoting and field codes, `Terminal=true`, and `DBusActivatable=true`
+//! apps started over D-Bus.
+
+use std::collections::HashMap;
+
+use freedesktop::ApplicationEntry;
+use glib::{ToVariant, Variant};
+
+use super::launcher::LaunchError;
+
+/// How long activating an app over D-Bus waits for it to answer. The bus
+/// starts the app first when it isn't running, which can take a while.
+const ACTIVATE_TIMEOUT_MS: i32 = 5000;
+
+/// The command line an entry's `Exec` key runs, launched on its own without
+/// any files or URLs. Field codes are expanded the way the spec has it.
+pub fn exec_command(app: &ApplicationEntry) -> Result<Vec<String>, LaunchError> {
+    let exec = app
+        .exec()
+        .filter(|exec| !exec.trim().is_empty())
+        .ok_or_else(|| LaunchError::LaunchError(String::from("No Exec key found")))?;
+
+    let mut command = Vec::new();
+    for arg in split_exec(&exec)? {
+        match arg.as_str() {
+            // Files and URLs to open, of which there are none.
+            "%f" | "%F" | "%u" | "%U" => {}
+            "%i" => {
+                if let Some(icon) = app.icon() {
+                    command.extend([String::from("--icon"), icon]);
+                }
+            }
+            _ => command.push(expand_field_codes(&arg, app)),
+        }
+    }
+
+    if command.is_empty() {
+        return Err(LaunchError::LaunchError(format!(
+            "Nothing to run in {exec}"
+        )));
+    }
+
+    Ok(command)
+}
+
+/// `command` run in `terminal`, which is the terminal program plus whatever
+/// it needs before the command to execute, like `["alacritty", "-e"]`.
+pub fn in_terminal(terminal: &[String], command: Vec<String>) -> Vec<String> {
+    terminal.iter().cloned().chain(command).collect()
+}
+
+/// Ask a `DBusActivatable` app to start, or come to the front if it's
+/// already running, through `org.freedesktop.Application`. The bus starts
+/// the app from its D-Bus service file when it isn't running, and this
+/// waits for it to come up, so it's best kept off the UI thread. The
+/// activation token goes along as both the Wayland and the X11 startup id.
+pub fn activate(
+    connection: &gio::DBusConnection,
+    app_id: &str,
+    activation_token: Option<&str>,
+) -> Result<(), glib::Error> {
+    let platform_data: HashMap<String, Variant> = activation_token
+        .into_iter()
+        .flat_map(|token| {
+            ["activation-token", "desktop-startup-id"]
+                .map(|key| (String::from(key), token.to_variant()))
+        })
+        .collect();
+
+    connection.call_sync(
+        Some(app_id),
+        &object_path(app_id),
+        "org.freedesktop.Application",
+        "Activate",
+        Some(&(platform_data,).to_variant()),
+        None,
+        gio::DBusCallFlags::NONE,
+        ACTIVATE_TIMEOUT_MS,
+        None::<&gio::Cancellable>,
+    )?;
+
+    Ok(())
+}
+
+/// Where a D-Bus activated app exports `org.freedesktop.Application`,
+/// which is its id with dots for slashes, like `/org/gnome/TextEditor`.
+pub fn object_path(app_id: &str) -> String {
+    format!("/{}", app_id.replace('.', "/").replace('-', "_"))
+}
+
+/// Split an `Exec` value into arguments. Arguments with spaces are in
+/// double quotes, inside which `"`, `` ` ``, `$` and `\` are escaped with a
+/// backslash.
+fn split_exec(exec: &str) -> Result<Vec<String>, LaunchError> {
+    let mut args = Vec::new();
+    let mut current = String::new();
+    let mut in_arg = false;
+    let mut chars = exec.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => {
+                in_arg = true;
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => current.extend(chars.next()),
+                        Some(c) => current.push(c),
+                        None => {
+                            return Err(LaunchError::LaunchError(format!(
+                                "Unterminated quote in {exec}"
+                            )));
+                        }
+                    }
+                }
+            }
+            ' ' | '\t' => {
+                if in_arg {
+                    args.push(std::mem::take(&mut current));
+                    in_arg = false;
+                }
+            }
+            c => {
+                in_arg = true;
+                current.push(c);
+            }
+        }
+    }
+
+    if in_arg {
+        args.push(current);
+    }
+
+    Ok(args)
+}
+
+/// Expand the field codes that can be part of a bigger argument. File and
+/// URL codes, and the deprecated ones, expand to nothing.
+fn expand_field_codes(arg: &str, app: &ApplicationEntry) -> String {
+    let mut expanded = String::with_capacity(arg.len());
+    let mut chars = arg.chars();
+
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            expanded.push(c);
+            continue;
+        }
+
+        match chars.next() {
+            Some('%') => expanded.push('%'),
+            Some('c') => expanded.push_str(&app.name().unwrap_or_default()),
+            Some('k') => expanded.push_str(&app.path().to_string_lossy()),
+            Some(_) => {}
+            None => expanded.push('%'),
+        }
+    }
+
+    expanded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc;
+    use std::thread;
+    use std::time::Duration;
+
+    fn entry(dir: &tempfile::TempDir, contents: &str) -> ApplicationEntry {
+        let path = dir.path().join("editor.desktop");
+        std::fs::write(&path, contents).unwrap();
+        ApplicationEntry::from_path(path).unwrap()
+    }
+
+    #[test]
+    fn expands_exec_lines() {
+        let dir = tempfile::tempdir().unwrap();
+        let app = entry(
+            &dir,
+            "[Desktop Entry]\n\
+            Type=Application\n\
+            Name=Editor\n\
+            Icon=editor\n\
+            Exec=\"/opt/My Editor/editor\" --title=%c %i --new-window %U\n",
+        );
+
+        assert_eq!(
+            exec_command(&app).unwrap(),
+            [
+                "/opt/My Editor/editor",
+                "--title=Editor",
+                "--icon",
+                "editor",
+                "--new-window"
+            ]
+        );
+        assert_eq!(
+            split_exec(r#"sh -c "echo \"\$HOME\" 100%%""#).unwrap(),
+            ["sh", "-c", r#"echo "$HOME" 100%%"#]
+        );
+        assert!(split_exec("\"unterminated").is_err());
+    }
+
+    #[test]
+    fn runs_terminal_apps_in_the_terminal() {
+        let dir = tempfile::tempdir().unwrap();
+        let app = entry(
+            &dir,
+            "[Desktop Entry]\n\
+            Type=Application\n\
+            Name=htop\n\
+            Terminal=true\n\
+            Exec=htop -d 5\n",
+        );
+        let terminal = vec![String::from("foot"), String::from("--")];
+
+        assert!(app.terminal());
+        assert_eq!(
+            in_terminal(&terminal, exec_command(&app).unwrap()),
+            ["foot", "--", "htop", "-d", "5"]
+        );
+    }
+
+    const APPLICATION_XML: &str = r#"
+        <node>
+          <interface name="org.freedesktop.Application">
+            <method name="Activate">
+              <arg type="a{sv}" name="platform_data" direction="in"/>
+            </method>
+          </interface>
+        </node>
+    "#;
+
+    fn connect(address: &str) -> gio::DBusConnection {
+        gio::DBusConnection::for_address_sync(
+            address,
+            gio::DBusConnectionFlags::AUTHENTICATION_CLIENT
+                | gio::DBusConnectionFlags::MESSAGE_BUS_CONNECTION,
+            None,
+            None::<&gio::Cancellable>,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn activates_apps_on_a_private_bus() {
+        let bus = gio::TestDBus::new(gio::TestDBusFlags::NONE);
+        bus.up();
+        let address = bus.bus_address().unwrap().to_string();
+
+        // The app side has a main loop of its own to answer calls from.
+        let (activated_tx, activated) = mpsc::channel();
+        let (ready_tx, ready) = mpsc::channel();
+        let app_address = address.clone();
+        let app = thread::spawn(move || {
+            let context = glib::MainContext::new();
+            let main_loop = glib::MainLoop::new(Some(&context), false);
+            context
+                .with_thread_default(|| {
+                    let connection = connect(&app_address);
+                    let node = gio::DBusNodeInfo::for_xml(APPLICATION_XML).unwrap();
+                    let interface = node
+                        .lookup_interface("org.freedesktop.Application")
+                        .unwrap();
+                    connection
+                        .register_object("/org/example/Editor", &interface)
+                        .method_call(move |_, _, _, _, method, params, invocation| {
+                            let platform_data: HashMap<String, Variant> =
+                                params.child_value(0).get().unwrap_or_default();
+                            let platform_data: HashMap<String, String> = platform_data
+                                .into_iter()
+                                .filter_map(|(key, value)| Some((key, value.get()?)))
+                                .collect();
+                            let _ = activated_tx.send((method.to_string(), platform_data));
+                            invocation.return_value(None);
+                        })
+                        .build()
+                        .unwrap();
+                    connection
+                        .call_sync(
+                            Some("org.freedesktop.DBus"),
+                            "/org/freedesktop/DBus",
+                            "org.freedesktop.DBus",
+                            "RequestName",
+                            Some(&("org.example.Editor", 0u32).to_variant()),
+                            None,
+                            gio::DBusCallFlags::NONE,
+                            -1,
+                            None::<&gio::Cancellable>,
+                        )
+                        .unwrap();
+                    ready_tx.send(main_loop.clone()).unwrap();
+                    main_loop.run();
+                })
+                .unwrap();
+        });
+        let app_loop = ready.recv_timeout(Duration::from_secs(5)).unwrap();
+
+        let connection = connect(&address);
+        activate(&connection, "org.example.Editor", Some("token-1")).unwrap();
+        let (method, platform_data) = activated.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert_eq!(method, "Activate");
+        assert_eq!(
+            platform_data.get("activation-token").map(String::as_str),
+            Some("token-1")
+        );
+
+        // Nothing owns this one and there's no service file to start it
+        // from, which is when the Exec line gets run instead.
+        let missing = activate(&connection, "org.example.Missing", None).unwrap_err();
+        assert!(missing.matches(gio::DBusError::ServiceUnknown));
+
+        app_loop.quit();
+        app.join().unwrap();
+        bus.down();
+    }
+
+    #[test]
+    fn object_paths_follow_the_app_id() {
+        assert_eq!(object_path("org.gnome.TextEditor"), "/org/gnome/TextEditor");
+        assert_eq!(object_path("org.example.my-app"), "/org/example/my_app");
+    }
+}
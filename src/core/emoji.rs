@@ -0,0 +1,292 @@
+//! Picking emoji and other Unicode symbols by name. The emoji come from the
+//! CLDR names and GitHub shortcodes bundled with the `emojis` crate, plus a
+//! short table of symbols that aren't emoji but are a pain to type.
+
+use std::process::{Command, Stdio};
+use std::thread;
+use std::time::Duration;
+
+use emojis::{Emoji, Group, SkinTone};
+use tracing::error;
+
+use super::config::{EmojiConfig, EmojiSkinTone};
+use super::{ItemKind, LauncherItem};
+
+/// Queries starting with this only search emoji and symbols.
+pub const PREFIX: char = ':';
+pub const ICON: &str = "face-smile";
+/// Most results an emoji search returns.
+pub const MAX_RESULTS: usize = 50;
+
+/// Words that mean the query is after a particular skin tone, so the toned
+/// variants are worth searching too. Without one of these they'd only bury
+/// everything else under five copies of each hand.
+const TONE_WORDS: [&str; 4] = ["tone", "light", "medium", "dark"];
+
+/// Symbols that aren't emoji: glyph, name and keywords.
+const SYMBOLS: &[(&str, &str, &str)] = &[
+    ("→", "rightwards arrow", "right arrow"),
+    ("←", "leftwards arrow", "left arrow"),
+    ("↑", "upwards arrow", "up arrow"),
+    ("↓", "downwards arrow", "down arrow"),
+    ("↔", "left right arrow", "both arrow"),
+    ("⇒", "rightwards double arrow", "implies"),
+    ("⇔", "left right double arrow", "if and only if iff"),
+    ("↵", "downwards arrow with corner leftwards", "return enter"),
+    ("⌘", "place of interest sign", "command cmd key"),
+    ("⌥", "option key", "alt"),
+    ("⇧", "upwards white arrow", "shift key"),
+    ("⌫", "erase to the left", "backspace"),
+    ("±", "plus-minus sign", "math"),
+    ("×", "multiplication sign", "times math"),
+    ("÷", "division sign", "divide math"),
+    ("≈", "almost equal to", "approximately math"),
+    ("≠", "not equal to", "math"),
+    ("≤", "less-than or equal to", "math"),
+    ("≥", "greater-than or equal to", "math"),
+    ("∞", "infinity", "math"),
+    ("√", "square root", "math"),
+    ("∑", "n-ary summation", "sum sigma math"),
+    ("∆", "increment", "delta change"),
+    ("π", "greek small letter pi", "math"),
+    ("µ", "micro sign", "mu"),
+    ("°", "degree sign", "temperature angle"),
+    ("‰", "per mille sign", "permille"),
+    ("½", "vulgar fraction one half", "half"),
+    ("¼", "vulgar fraction one quarter", "quarter"),
+    ("¾", "vulgar fraction three quarters", "three quarters"),
+    ("²", "superscript two", "squared"),
+    ("³", "superscript three", "cubed"),
+    ("€", "euro sign", "currency money"),
+    ("£", "pound sign", "currency money sterling"),
+    ("¥", "yen sign", "currency money yuan"),
+    ("₹", "indian rupee sign", "currency money"),
+    ("₿", "bitcoin sign", "currency money btc"),
+    ("¢", "cent sign", "currency money"),
+    ("©", "copyright sign", "legal"),
+    ("®", "registered sign", "legal trademark"),
+    ("™", "trade mark sign", "legal trademark"),
+    ("§", "section sign", "legal paragraph"),
+    ("¶", "pilcrow sign", "paragraph"),
+    ("†", "dagger", "footnote"),
+    ("•", "bullet", "list dot"),
+    ("·", "middle dot", "interpunct"),
+    ("…", "horizontal ellipsis", "dots"),
+    ("–", "en dash", "range"),
+    ("—", "em dash", "dash"),
+    ("«", "left guillemet", "double angle quotation mark"),
+    ("»", "right guillemet", "double angle quotation mark"),
+    ("“", "left double quotation mark", "smart quote"),
+    ("”", "right double quotation mark", "smart quote"),
+    ("‘", "left single quotation mark", "smart quote"),
+    ("’", "right single quotation mark", "apostrophe smart quote"),
+    ("✓", "check mark", "tick yes done"),
+    ("✗", "ballot x", "cross no"),
+    ("★", "black star", "favorite rating"),
+    ("☆", "white star", "favorite rating"),
+    ("♥", "black heart suit", "love card"),
+    ("♪", "eighth note", "music"),
+    ("¿", "inverted question mark", "spanish"),
+    ("¡", "inverted exclamation mark", "spanish"),
+    ("\u{a0}", "no-break space", "nbsp whitespace"),
+];
+
+/// Everything an emoji search can turn up, built once since the table
+/// doesn't change while running.
+pub struct EmojiIndex {
+    /// Emoji in the configured skin tone, followed by the symbols
+    items: Vec<LauncherItem>,
+    /// Every single-toned variant of the emoji that have skin tones, other
+    /// than the configured tone's, which are in `items` already
+    tones: Vec<LauncherItem>,
+}
+
+impl EmojiIndex {
+    pub fn new(cfg: &EmojiConfig) -> Self {
+        let preferred = skin_tone(cfg.skin_tone);
+
+        let items = emojis::iter()
+            .map(|emoji| {
+                preferred
+                    .and_then(|tone| emoji.with_skin_tone(tone))
+                    .unwrap_or(emoji)
+            })
+            .map(emoji_item)
+            .chain(SYMBOLS.iter().map(|&(glyph, name, keywords)| LauncherItem {
+                id: glyph.into(),
+                kind: ItemKind::Emoji,
+                title: format!("{glyph} {name}"),
+                description: Some(format!("Symbol · {keywords}")),
+                icon: ICON.into(),
+            }))
+            .collect();
+
+        let tones = emojis::iter()
+            .filter_map(Emoji::skin_tones)
+            .flatten()
+            .filter(|emoji| {
+                matches!(
+                    emoji.skin_tone(),
+                    Some(
+                        SkinTone::Light
+                            | SkinTone::MediumLight
+                            | SkinTone::Medium
+                            | SkinTone::MediumDark
+                            | SkinTone::Dark
+                    )
+                ) && emoji.skin_tone() != preferred
+            })
+            .map(emoji_item)
+            .collect();
+
+        Self { items, tones }
+    }
+
+    /// Everything there is to search for `query`.
+    pub fn candidates(&self, query: &str) -> Vec<&LauncherItem> {
+        let query = query.to_lowercase();
+        let wants_tone = TONE_WORDS.iter().any(|word| query.contains(word));
+
+        let tones: &[LauncherItem] = if wants_tone { &self.tones } else { &[] };
+
+        self.items.iter().chain(tones).collect()
+    }
+
+    /// Look an emoji or symbol up by the text it puts in.
+    pub fn get(&self, glyph: &str) -> Option<&LauncherItem> {
+        self.items
+            .iter()
+            .chain(&self.tones)
+            .find(|item| item.id == glyph)
+    }
+}
+
+fn emoji_item(emoji: &Emoji) -> LauncherItem {
+    // Toned variants have no shortcodes of their own, so they go by the
+    // untoned emoji's.
+    let untoned = emoji.with_skin_tone(SkinTone::Default).unwrap_or(emoji);
+    let shortcodes: Vec<String> = untoned
+        .shortcodes()
+        .map(|code| format!("{PREFIX}{code}{PREFIX}"))
+        .collect();
+    let group = group_name(emoji.group());
+    let description = if shortcodes.is_empty() {
+        group.to_string()
+    } else {
+        format!("{group} · {}", shortcodes.join(" "))
+    };
+
+    LauncherItem {
+        id: emoji.as_str().into(),
+        kind: ItemKind::Emoji,
+        title: format!("{} {}", emoji.as_str(), emoji.name()),
+        description: Some(description),
+        icon: ICON.into(),
+    }
+}
+
+fn group_name(group: Group) -> &'static str {
+    match group {
+        Group::SmileysAndEmotion => "Smileys & Emotion",
+        Group::PeopleAndBody => "People & Body",
+        Group::AnimalsAndNature => "Animals & Nature",
+        Group::FoodAndDrink => "Food & Drink",
+        Group::TravelAndPlaces => "Travel & Places",
+        Group::Activities => "Activities",
+        Group::Objects => "Objects",
+        Group::Symbols => "Symbols",
+        Group::Flags => "Flags",
+    }
+}
+
+fn skin_tone(tone: EmojiSkinTone) -> Option<SkinTone> {
+    match tone {
+        EmojiSkinTone::Default => None,
+        EmojiSkinTone::Light => Some(SkinTone::Light),
+        EmojiSkinTone::MediumLight => Some(SkinTone::MediumLight),
+        EmojiSkinTone::Medium => Some(SkinTone::Medium),
+        EmojiSkinTone::MediumDark => Some(SkinTone::MediumDark),
+        EmojiSkinTone::Dark => Some(SkinTone::Dark),
+    }
+}
+
+/// Type `text` into whatever has keyboard focus with a virtual keyboard
+/// program like `wtype`.
+///
+/// The launcher holds the keyboard until it hides, so this waits `delay`
+/// first to let focus go back to the window the text is meant for.
+pub fn type_text(program: &str, text: &str, delay: Duration) {
+    let mut command = Command::new(program);
+    command
+        .arg("--")
+        .arg(text)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null());
+    let program = program.to_string();
+
+    thread::spawn(move || {
+        thread::sleep(delay);
+        match command.status() {
+            Ok(status) if !status.success() => {
+                error!("{program} exited with {status}");
+            }
+            Ok(_) => {}
+            Err(e) => error!("Failed to run {program}: {e}"),
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn emoji_come_in_the_configured_skin_tone() {
+        let index = EmojiIndex::new(&EmojiConfig {
+            skin_tone: EmojiSkinTone::Medium,
+            ..EmojiConfig::default()
+        });
+
+        let thumbs_up = index
+            .candidates("thumbs")
+            .into_iter()
+            .find(|item| item.title.contains("thumbs up"))
+            .unwrap();
+
+        assert_eq!(thumbs_up.id, "👍🏽");
+        assert!(thumbs_up.description.as_deref().unwrap().contains(":+1:"));
+    }
+
+    #[test]
+    fn toned_variants_only_show_up_when_asked_for() {
+        let index = EmojiIndex::new(&EmojiConfig::default());
+        let variants = |query| {
+            index
+                .candidates(query)
+                .into_iter()
+                .filter(|item| item.title.starts_with("👍"))
+                .count()
+        };
+
+        assert_eq!(variants("thumbs up"), 1);
+        assert_eq!(variants("thumbs up dark"), 6);
+        assert!(index.get("€").is_some());
+
+        // The configured tone is the one listed every time, so asking for
+        // tones adds only the other four.
+        let index = EmojiIndex::new(&EmojiConfig {
+            skin_tone: EmojiSkinTone::Medium,
+            ..EmojiConfig::default()
+        });
+        let thumbs_up: Vec<&str> = index
+            .candidates("thumbs up medium")
+            .into_iter()
+            .filter(|item| item.title.starts_with("👍"))
+            .map(|item| item.id.as_str())
+            .collect();
+        assert_eq!(thumbs_up.len(), 5);
+        assert_eq!(thumbs_up[0], "👍🏽");
+        assert_eq!(thumbs_up.iter().filter(|&&glyph| glyph == "👍🏽").count(), 1);
+    }
+}
@@ -0,0 +1,337 @@
+//! Which keys do what in the launcher. Bindings start from a preset and any
+//! action set in `[keymap]` replaces that action's keys.
+//!
+//! Keys are written like `Ctrl+Shift+c`: modifiers joined by `+`, then a
+//! character or a key name such as `Down`, `PageUp`, `Tab` or `Home`.
+
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum KeymapError {
+    #[error("No key in \"{0}\"")]
+    MissingKey(String),
+    #[error("Unknown modifier \"{0}\"")]
+    UnknownModifier(String),
+}
+
+/// Something a key can do.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum KeyAction {
+    Next,
+    Previous,
+    PageDown,
+    PageUp,
+    First,
+    Last,
+    /// Open or close the action panel
+    Actions,
+    /// Copy the selected result's path, URL or value
+    Copy,
+    /// Delete the last word of the query
+    DeleteWord,
+    /// Clear the query
+    Clear,
+    /// Show only results of the next kind in the list, then all of them
+    /// again
+    CycleKind,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Modifiers {
+    pub ctrl: bool,
+    pub alt: bool,
+    pub shift: bool,
+    /// The Super or Windows key
+    pub logo: bool,
+}
+
+/// A key with the modifiers held for it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct KeyChord {
+    pub modifiers: Modifiers,
+    /// A character, lowercase, or a key name as the UI toolkit spells it,
+    /// like `ArrowDown`
+    pub key: String,
+}
+
+impl KeyChord {
+    /// Whether this is `key` pressed with exactly `modifiers`. Characters
+    /// match either case, since Shift turns them uppercase.
+    pub fn matches(&self, key: &str, modifiers: Modifiers) -> bool {
+        self.modifiers == modifiers && self.key.eq_ignore_ascii_case(key)
+    }
+}
+
+impl FromStr for KeyChord {
+    type Err = KeymapError;
+
+    fn from_str(chord: &str) -> Result<Self, Self::Err> {
+        // A trailing `+` is the plus key itself, as in `Ctrl++`.
+        let (modifier_part, key) = match chord.strip_suffix("++") {
+            Some(rest) => (rest, "+"),
+            None => chord.rsplit_once('+').unwrap_or(("", chord)),
+        };
+        if key.is_empty() {
+            return Err(KeymapError::MissingKey(chord.to_string()));
+        }
+
+        let mut modifiers = Modifiers::default();
+        for modifier in modifier_part.split('+').filter(|m| !m.is_empty()) {
+            match modifier.to_ascii_lowercase().as_str() {
+                "ctrl" | "control" => modifiers.ctrl = true,
+                "alt" => modifiers.alt = true,
+                "shift" => modifiers.shift = true,
+                "super" | "logo" | "meta" => modifiers.logo = true,
+                _ => return Err(KeymapError::UnknownModifier(modifier.to_string())),
+            }
+        }
+
+        let key = match key.to_ascii_lowercase().as_str() {
+            "up" => String::from("ArrowUp"),
+            "down" => String::from("ArrowDown"),
+            "left" => String::from("ArrowLeft"),
+            "right" => String::from("ArrowRight"),
+            "esc" => String::from("Escape"),
+            "return" => String::from("Enter"),
+            "pgup" => String::from("PageUp"),
+            "pgdown" | "pgdn" => String::from("PageDown"),
+            _ if key.chars().count() == 1 => key.to_lowercase(),
+            _ => key.to_string(),
+        };
+
+        Ok(Self { modifiers, key })
+    }
+}
+
+impl TryFrom<String> for KeyChord {
+    type Error = KeymapError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<KeyChord> for String {
+    fn from(chord: KeyChord) -> Self {
+        chord.to_string()
+    }
+}
+
+impl fmt::Display for KeyChord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Modifiers {
+            ctrl,
+            alt,
+            shift,
+            logo,
+        } = self.modifiers;
+        for (held, name) in [
+            (ctrl, "Ctrl"),
+            (alt, "Alt"),
+            (shift, "Shift"),
+            (logo, "Super"),
+        ] {
+            if held {
+                write!(f, "{name}+")?;
+            }
+        }
+        f.write_str(&self.key)
+    }
+}
+
+/// Where bindings start from, before any of `[keymap]`'s own.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum KeymapPreset {
+    /// Arrow keys, with Ctrl+K or Tab for the action panel
+    #[default]
+    Default,
+    /// Ctrl+N and Ctrl+P as well as the arrow keys
+    Emacs,
+    /// Ctrl+J and Ctrl+K as well as the arrow keys. The action panel is on
+    /// Tab only.
+    Vim,
+    /// Tab and Shift+Tab as well as the arrow keys. The action panel is on
+    /// Ctrl+K only.
+    Tab,
+}
+
+impl KeymapPreset {
+    fn keys(self, action: KeyAction) -> &'static [&'static str] {
+        use KeyAction::*;
+
+        match (self, action) {
+            (Self::Emacs, Next) => &["Down", "Ctrl+n"],
+            (Self::Emacs, Previous) => &["Up", "Ctrl+p"],
+            (Self::Vim, Next) => &["Down", "Ctrl+j"],
+            (Self::Vim, Previous) => &["Up", "Ctrl+k"],
+            (Self::Vim, Actions) => &["Tab"],
+            (Self::Tab, Next) => &["Down", "Tab"],
+            (Self::Tab, Previous) => &["Up", "Shift+Tab"],
+            (Self::Tab, Actions) => &["Ctrl+k"],
+            (_, Next) => &["Down"],
+            (_, Previous) => &["Up"],
+            (_, PageDown) => &["PageDown"],
+            (_, PageUp) => &["PageUp"],
+            (_, First) => &["Ctrl+Home"],
+            (_, Last) => &["Ctrl+End"],
+            (_, Actions) => &["Ctrl+k", "Tab"],
+            (_, Copy) => &["Ctrl+Shift+c"],
+            (_, DeleteWord) => &["Ctrl+w"],
+            (_, Clear) => &["Ctrl+u"],
+            (_, CycleKind) => &["Ctrl+Tab"],
+        }
+    }
+}
+
+/// `[keymap]` as written in the config file.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct KeymapSettings {
+    preset: KeymapPreset,
+    next: Option<Vec<KeyChord>>,
+    previous: Option<Vec<KeyChord>>,
+    page_down: Option<Vec<KeyChord>>,
+    page_up: Option<Vec<KeyChord>>,
+    first: Option<Vec<KeyChord>>,
+    last: Option<Vec<KeyChord>>,
+    actions: Option<Vec<KeyChord>>,
+    copy: Option<Vec<KeyChord>>,
+    delete_word: Option<Vec<KeyChord>>,
+    clear: Option<Vec<KeyChord>>,
+    cycle_kind: Option<Vec<KeyChord>>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct Binding {
+    key: KeyChord,
+    action: KeyAction,
+}
+
+/// Every binding, resolved from a preset and the config.
+#[derive(Debug, Clone, Serialize)]
+pub struct Keymap {
+    bindings: Vec<Binding>,
+}
+
+impl Keymap {
+    pub fn new(settings: &KeymapSettings) -> Self {
+        use KeyAction::*;
+
+        let configured = [
+            (Next, &settings.next),
+            (Previous, &settings.previous),
+            (PageDown, &settings.page_down),
+            (PageUp, &settings.page_up),
+            (First, &settings.first),
+            (Last, &settings.last),
+            (Actions, &settings.actions),
+            (Copy, &settings.copy),
+            (DeleteWord, &settings.delete_word),
+            (Clear, &settings.clear),
+            (CycleKind, &settings.cycle_kind),
+        ];
+
+        let mut bindings = Vec::new();
+        for (action, keys) in configured {
+            let keys = match keys {
+                Some(keys) => keys.clone(),
+                None => settings
+                    .preset
+                    .keys(action)
+                    .iter()
+                    .map(|key| key.parse().expect("preset keys parse"))
+                    .collect(),
+            };
+            bindings.extend(keys.into_iter().map(|key| Binding { key, action }));
+        }
+
+        Self { bindings }
+    }
+
+    /// What `key` does when pressed with `modifiers`. When a key is bound to
+    /// more than one action, the first one in `[keymap]` order wins.
+    pub fn action(&self, key: &str, modifiers: Modifiers) -> Option<KeyAction> {
+        self.bindings
+            .iter()
+            .find(|binding| binding.key.matches(key, modifiers))
+            .map(|binding| binding.action)
+    }
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        Self::new(&KeymapSettings::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ctrl() -> Modifiers {
+        Modifiers {
+            ctrl: true,
+            ..Modifiers::default()
+        }
+    }
+
+    #[test]
+    fn parses_chords() {
+        let chord: KeyChord = "Ctrl+Shift+C".parse().unwrap();
+        assert!(chord.matches(
+            "C",
+            Modifiers {
+                shift: true,
+                ..ctrl()
+            }
+        ));
+        assert!(!chord.matches("c", ctrl()));
+        assert_eq!(chord.to_string(), "Ctrl+Shift+c");
+
+        let plus: KeyChord = "ctrl++".parse().unwrap();
+        assert!(plus.matches("+", ctrl()));
+
+        assert!(
+            "Down"
+                .parse::<KeyChord>()
+                .unwrap()
+                .matches("ArrowDown", Modifiers::default())
+        );
+        assert_eq!(
+            "Hyper+x".parse::<KeyChord>(),
+            Err(KeymapError::UnknownModifier("Hyper".into()))
+        );
+        assert_eq!(
+            "Ctrl+".parse::<KeyChord>(),
+            Err(KeymapError::MissingKey("Ctrl+".into()))
+        );
+    }
+
+    #[test]
+    fn config_replaces_preset_keys() {
+        let settings: KeymapSettings = toml::from_str(
+            r#"
+            preset = "vim"
+            clear = ["Ctrl+l"]
+            "#,
+        )
+        .unwrap();
+        let keymap = Keymap::new(&settings);
+
+        assert_eq!(keymap.action("k", ctrl()), Some(KeyAction::Previous));
+        assert_eq!(
+            keymap.action("Tab", Modifiers::default()),
+            Some(KeyAction::Actions)
+        );
+        assert_eq!(keymap.action("l", ctrl()), Some(KeyAction::Clear));
+        assert_eq!(keymap.action("u", ctrl()), None);
+        assert_eq!(keymap.action("j", Modifiers::default()), None);
+    }
+}
@@ -1,8 +1,13 @@
 use freedesktop::{ApplicationEntry, ExecuteError, FindError};
-use gio::prelude::FileExt;
+use gio::prelude::{AppInfoExt, FileExt};
+use glib::ToVariant;
 use thiserror::Error;
 use tracing::{error, info};
 
+use super::apps::AppDirectory;
+use super::config::{LaunchMode, LaunchProfile};
+use super::{desktop_entry, shell};
+
 #[derive(Error, Debug)]
 pub enum LaunchError {
     #[error("Failed to launch {0}")]
@@ -13,33 +18,74 @@ pub enum LaunchError {
     LaunchError(String),
 }
 
-pub fn launch_desktop_entry(id: &str) -> Result<(), LaunchError> {
-    let app = ApplicationEntry::from_id(id)?;
+/// Launch the app with this id from the first of `dirs` that has it,
+/// falling back to the XDG application directories. `DBusActivatable` apps
+/// are activated over D-Bus when they can be, and `Terminal=true` ones are
+/// run in `terminal`. The activation token in `opts`, when there is one,
+/// lets the app take the focus.
+pub fn launch_desktop_entry(
+    id: &str,
+    dirs: &[AppDirectory],
+    terminal: &[String],
+    opts: SpawnOptions<'_>,
+) -> Result<(), LaunchError> {
+    let app = match dirs
+        .iter()
+        .map(|dir| dir.path.join(format!("{id}.desktop")))
+        .find(|path| path.is_file())
+    {
+        Some(path) => ApplicationEntry::from_path(path).map_err(FindError::from)?,
+        None => ApplicationEntry::from_id(id)?,
+    };
     info!("Found app successfully");
     info!("Path: {}", app.path().display());
     info!("ID: {}", app.id().unwrap_or("Not found".into()));
 
-    let (cmd, args) = app.prepare_command(&[], &[])?;
-    info!("Executing with command: {} | args: {}", cmd, args.join(","));
-    let arg_refs: Vec<&str> = args.iter().map(String::as_str).collect();
+    if app.get_bool("DBusActivatable") == Some(true) {
+        let activated = gio::bus_get_sync(gio::BusType::Session, None::<&gio::Cancellable>)
+            .and_then(|bus| desktop_entry::activate(&bus, id, opts.activation_token));
+
+        match activated {
+            Ok(()) => return Ok(()),
+            // The app is most likely still starting, and running the Exec
+            // line too would start it twice.
+            Err(e) if e.matches(gio::IOErrorEnum::TimedOut) => {
+                info!("{id} is still starting up after being activated over D-Bus");
+                return Ok(());
+            }
+            Err(e) => info!("Could not activate {id} over D-Bus ({e}), running its Exec line"),
+        }
+    }
+
+    let mut command = desktop_entry::exec_command(&app)?;
+    if app.terminal() {
+        if terminal.is_empty() {
+            return Err(LaunchError::LaunchError("No terminal configured".into()));
+        }
+        command = desktop_entry::in_terminal(terminal, command);
+    }
+
+    info!("Executing: {}", command.join(" "));
+    let args: Vec<&str> = command[1..].iter().map(String::as_str).collect();
     let working_dir = app.path_dir();
     let opts = SpawnOptions {
         working_dir: working_dir.as_deref(),
         scope_id: Some(id),
+        ..opts
     };
 
-    match spawn_detached(&cmd, &arg_refs, opts) {
+    match spawn_detached(&command[0], &args, opts) {
         Ok(_) => Ok(()),
         Err(e) => Err(LaunchError::LaunchError(e.to_string())),
     }
 }
 
-pub fn open_path(path: &str) -> Result<(), LaunchError> {
+pub fn open_path(path: &str, opts: SpawnOptions<'_>) -> Result<(), LaunchError> {
     info!("Executing: {}", path);
 
     // Use xdg-open directly since it works properly with music files
     // Detach the process so it doesn't die when daemon is killed
-    match spawn_detached("xdg-open", &[path], SpawnOptions::default()) {
+    match spawn_detached("xdg-open", &[path], opts) {
         Ok(_) => {
             info!("Successfully launched with xdg-open");
             Ok(())
@@ -64,7 +110,79 @@ pub fn open_path(path: &str) -> Result<(), LaunchError> {
     }
 }
 
-pub fn run_command(command: &str) -> Result<(), LaunchError> {
+/// Apps that can open `path` other than its default one, as desktop entry
+/// ids and names.
+pub fn apps_for_path(path: &Path) -> Vec<(String, String)> {
+    let content_type = if path.is_dir() {
+        glib::GString::from("inode/directory")
+    } else {
+        gio::content_type_guess(Some(path), None).0
+    };
+    let default = gio::AppInfo::default_for_type(&content_type, false).and_then(|app| app.id());
+
+    gio::AppInfo::all_for_type(&content_type)
+        .into_iter()
+        .filter_map(|app| Some((app.id()?, app.name())))
+        .filter(|(id, _)| Some(id) != default.as_ref())
+        .map(|(id, name)| (id.to_string(), name.to_string()))
+        .collect()
+}
+
+/// Open `path` with the app whose desktop entry id is `app_id`.
+///
+/// `gio launch` does the Exec line's field codes, and runs the app in a
+/// scope of its own like any other launch instead of under the daemon.
+pub fn open_with(app_id: &str, path: &str, opts: SpawnOptions<'_>) -> Result<(), LaunchError> {
+    let desktop_file = gio::DesktopAppInfo::new(app_id)
+        .and_then(|app| app.filename())
+        .ok_or_else(|| LaunchError::LaunchError(format!("No app with id {app_id}")))?;
+    let desktop_file = desktop_file.to_string_lossy();
+    let opts = SpawnOptions {
+        scope_id: Some(app_id),
+        ..opts
+    };
+
+    spawn_detached("gio", &["launch", &desktop_file, path], opts)
+        .map_err(|e| LaunchError::LaunchError(format!("Failed to open {path} with {app_id}: {e}")))
+}
+
+/// Show `path` selected in the file manager. File managers that don't
+/// take the FileManager1 D-Bus call get the folder it's in opened instead.
+pub fn reveal_path(path: &str, opts: SpawnOptions<'_>) -> Result<(), LaunchError> {
+    let uri = gio::File::for_path(path).uri().to_string();
+    let shown =
+        gio::bus_get_sync(gio::BusType::Session, None::<&gio::Cancellable>).and_then(|bus| {
+            bus.call_sync(
+                Some("org.freedesktop.FileManager1"),
+                "/org/freedesktop/FileManager1",
+                "org.freedesktop.FileManager1",
+                "ShowItems",
+                Some(&(vec![uri], opts.activation_token.unwrap_or_default()).to_variant()),
+                None,
+                gio::DBusCallFlags::NONE,
+                1000,
+                None::<&gio::Cancellable>,
+            )
+        });
+
+    match shown {
+        Ok(_) => Ok(()),
+        Err(e) => {
+            info!("No file manager to show {path} in ({e}), opening its folder");
+            let folder = Path::new(path).parent().unwrap_or(Path::new("/"));
+            open_path(&folder.to_string_lossy(), opts)
+        }
+    }
+}
+
+/// Move `path` to the trash, where it can still be restored from.
+pub fn trash_path(path: &str) -> Result<(), LaunchError> {
+    gio::File::for_path(path)
+        .trash(None::<&gio::Cancellable>)
+        .map_err(|e| LaunchError::LaunchError(format!("Failed to move {path} to the trash: {e}")))
+}
+
+pub fn run_command(command: &str, opts: SpawnOptions<'_>) -> Result<(), LaunchError> {
     let parts: Vec<&str> = command.split_whitespace().collect();
     let Some((program, args)) = parts.split_first() else {
         return Err(LaunchError::LaunchError(
@@ -72,7 +190,7 @@ pub fn run_command(command: &str) -> Result<(), LaunchError> {
         ));
     };
 
-    match spawn_detached(program, args, SpawnOptions::default()) {
+    match spawn_detached(program, args, opts) {
         Ok(_) => {
             info!("Successfully ran configured command");
             Ok(())
@@ -83,13 +201,41 @@ pub fn run_command(command: &str) -> Result<(), LaunchError> {
     }
 }
 
+/// Run `command` through `sh`, in `terminal` when one is given.
+pub fn run_shell_command(
+    command: &str,
+    terminal: Option<&[String]>,
+    opts: SpawnOptions<'_>,
+) -> Result<(), LaunchError> {
+    // Named after the program so the scope says what's running in it.
+    let program_name = command.split_whitespace().next().unwrap_or("sh");
+    let opts = SpawnOptions {
+        scope_id: Some(program_name),
+        ..opts
+    };
+
+    let result = match terminal {
+        Some([]) => return Err(LaunchError::LaunchError("No terminal configured".into())),
+        Some(terminal @ [program, ..]) => {
+            let args = shell::terminal_args(terminal, command);
+            let args: Vec<&str> = args[1..].iter().map(String::as_str).collect();
+            spawn_detached(program, &args, opts)
+        }
+        None => spawn_detached("sh", &["-c", command], opts),
+    };
+
+    result.map_err(|e| LaunchError::LaunchError(format!("Failed to run {command}: {e}")))
+}
+
+use std::path::Path;
 use std::process::{Command, Stdio};
 use std::sync::OnceLock;
 
 /// Options controlling how a process is spawned.
 ///
-/// The launched process inherits waycast's environment, so nothing here needs
-/// to forward `WAYLAND_DISPLAY` and friends by hand.
+/// Forked and scoped processes inherit waycast's environment, so nothing here
+/// needs to forward `WAYLAND_DISPLAY` and friends by hand. Services get the
+/// systemd user manager's instead, plus the variables set here.
 #[derive(Default)]
 pub struct SpawnOptions<'a> {
     /// Working directory, e.g. a desktop entry's `Path=` key.
@@ -98,9 +244,16 @@ pub struct SpawnOptions<'a> {
     /// recognizable in `systemd-cgls` and process monitors. Defaults to the
     /// program name.
     pub scope_id: Option<&'a str>,
+    /// xdg-activation token from the launcher, handed to the app as
+    /// `XDG_ACTIVATION_TOKEN` and `DESKTOP_STARTUP_ID` so it can take the
+    /// focus when its window shows up.
+    pub activation_token: Option<&'a str>,
+    /// Prefix, environment and systemd unit settings from `[launch]`.
+    pub launch: LaunchProfile,
 }
 
-/// Whether launched processes can be placed in their own systemd scope.
+/// Whether launched processes can be placed in their own systemd scope or
+/// service.
 ///
 /// Reparenting a process does not move it out of the daemon's cgroup, so
 /// without a scope of its own every app stays subject to the daemon unit's
@@ -162,7 +315,8 @@ fn scope_unit_name(id: &str) -> String {
 /// Detached means three things:
 /// the process leaves waycast's process tree.
 /// leaves its session, and gets reaped rather than left behind as a zombie.
-/// Where systemd is available it also lands in a cgroup of its own.
+/// Where systemd is available it also lands in a cgroup of its own, as a
+/// scope or a service depending on the launch mode.
 pub fn spawn_detached(
     program: &str,
     args: &[&str],
@@ -170,27 +324,28 @@ pub fn spawn_detached(
 ) -> Result<(), std::io::Error> {
     use std::os::unix::process::CommandExt;
 
-    let mut cmd = if systemd_scopes_available() {
-        let unit = scope_unit_name(opts.scope_id.unwrap_or(program));
-        let mut cmd = Command::new("systemd-run");
-        cmd.args(["--user", "--scope", "--collect", "--quiet"])
-            .arg(format!("--unit={unit}"))
-            .arg("--")
-            .arg(program)
-            .args(args);
-        cmd
-    } else {
-        let mut cmd = Command::new(program);
-        cmd.args(args);
-        cmd
+    let mode = match opts.launch.mode {
+        LaunchMode::Scope | LaunchMode::Service if !systemd_scopes_available() => LaunchMode::Fork,
+        mode => mode,
     };
+    let unit =
+        (mode != LaunchMode::Fork).then(|| scope_unit_name(opts.scope_id.unwrap_or(program)));
+    let command = command_line(program, args, &opts, mode, unit.as_deref());
 
-    cmd.stdin(Stdio::null())
+    let mut cmd = Command::new(&command[0]);
+    cmd.args(&command[1..])
+        .stdin(Stdio::null())
         .stdout(Stdio::null())
         .stderr(Stdio::null());
 
-    if let Some(dir) = opts.working_dir {
-        cmd.current_dir(dir);
+    // A scope runs the program as systemd-run itself, so the environment
+    // reaches it either way. A service is started by the user manager and
+    // gets it on the command line instead.
+    if mode != LaunchMode::Service {
+        cmd.envs(environment(&opts));
+        if let Some(dir) = opts.working_dir {
+            cmd.current_dir(dir);
+        }
     }
 
     unsafe {
@@ -219,3 +374,204 @@ pub fn spawn_detached(
 
     Ok(())
 }
+
+/// The command that starts `program` with its launch prefix, run by
+/// systemd-run as the unit `unit` unless `mode` is to fork.
+fn command_line(
+    program: &str,
+    args: &[&str],
+    opts: &SpawnOptions<'_>,
+    mode: LaunchMode,
+    unit: Option<&str>,
+) -> Vec<String> {
+    let mut command = Vec::new();
+
+    if let (LaunchMode::Scope | LaunchMode::Service, Some(unit)) = (mode, unit) {
+        command.extend(["systemd-run", "--user"].map(String::from));
+        if mode == LaunchMode::Scope {
+            command.push(String::from("--scope"));
+        }
+        command.extend(["--collect", "--quiet"].map(String::from));
+        command.push(format!("--unit={unit}"));
+
+        if let Some(slice) = &opts.launch.slice {
+            command.push(format!("--slice={slice}"));
+        }
+        for (key, value) in &opts.launch.properties {
+            command.push(format!("--property={key}={value}"));
+        }
+
+        if mode == LaunchMode::Service {
+            if let Some(dir) = opts.working_dir {
+                command.push(format!("--working-directory={dir}"));
+            }
+            for (key, value) in environment(opts) {
+                command.push(format!("--setenv={key}={value}"));
+            }
+        }
+
+        command.push(String::from("--"));
+    }
+
+    command.extend(opts.launch.prefix.iter().cloned());
+    command.push(program.to_string());
+    command.extend(args.iter().map(|arg| arg.to_string()));
+    command
+}
+
+/// The variables a launch sets: the configured ones, then the activation
+/// token under both of its names.
+fn environment<'a>(opts: &'a SpawnOptions<'_>) -> Vec<(&'a str, &'a str)> {
+    let mut env: Vec<(&str, &str)> = opts
+        .launch
+        .env
+        .iter()
+        .map(|(key, value)| (key.as_str(), value.as_str()))
+        .collect();
+
+    if let Some(token) = opts.activation_token {
+        env.extend([
+            ("XDG_ACTIVATION_TOKEN", token),
+            ("DESKTOP_STARTUP_ID", token),
+        ]);
+    }
+
+    env
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::config::{AppLaunchConfig, LaunchConfig};
+    use std::collections::BTreeMap;
+
+    fn launch_config() -> LaunchConfig {
+        LaunchConfig {
+            prefix: vec![
+                String::from("uwsm"),
+                String::from("app"),
+                String::from("--"),
+            ],
+            env: BTreeMap::from([(String::from("MOZ_ENABLE_WAYLAND"), String::from("1"))]),
+            slice: Some(String::from("app-graphical.slice")),
+            apps: BTreeMap::from([(
+                String::from("steam"),
+                AppLaunchConfig {
+                    mode: Some(LaunchMode::Service),
+                    prefix: vec![String::from("gamemoderun")],
+                    env: BTreeMap::from([(String::from("MOZ_ENABLE_WAYLAND"), String::from("0"))]),
+                    properties: BTreeMap::from([(String::from("CPUWeight"), String::from("200"))]),
+                    ..AppLaunchConfig::default()
+                },
+            )]),
+            ..LaunchConfig::default()
+        }
+    }
+
+    #[test]
+    fn app_settings_go_on_top_of_the_global_ones() {
+        let config = launch_config();
+
+        let global = config.profile(None);
+        assert_eq!(global, config.profile(Some("firefox")));
+        assert_eq!(global.mode, LaunchMode::Scope);
+
+        let steam = config.profile(Some("steam"));
+        assert_eq!(steam.mode, LaunchMode::Service);
+        assert_eq!(steam.prefix, ["uwsm", "app", "--", "gamemoderun"]);
+        assert_eq!(steam.env["MOZ_ENABLE_WAYLAND"], "0");
+        assert_eq!(steam.slice.as_deref(), Some("app-graphical.slice"));
+        assert_eq!(steam.properties["CPUWeight"], "200");
+    }
+
+    #[test]
+    fn scopes_get_properties_and_inherit_the_environment() {
+        let opts = SpawnOptions {
+            activation_token: Some("token-1"),
+            launch: launch_config().profile(None),
+            ..SpawnOptions::default()
+        };
+
+        assert_eq!(
+            command_line(
+                "firefox",
+                &["--new-window"],
+                &opts,
+                LaunchMode::Scope,
+                Some("app-waycast-firefox-1")
+            ),
+            [
+                "systemd-run",
+                "--user",
+                "--scope",
+                "--collect",
+                "--quiet",
+                "--unit=app-waycast-firefox-1",
+                "--slice=app-graphical.slice",
+                "--",
+                "uwsm",
+                "app",
+                "--",
+                "firefox",
+                "--new-window",
+            ]
+        );
+        assert_eq!(
+            environment(&opts),
+            [
+                ("MOZ_ENABLE_WAYLAND", "1"),
+                ("XDG_ACTIVATION_TOKEN", "token-1"),
+                ("DESKTOP_STARTUP_ID", "token-1"),
+            ]
+        );
+    }
+
+    #[test]
+    fn services_get_the_environment_on_the_command_line() {
+        let opts = SpawnOptions {
+            working_dir: Some("/opt/steam"),
+            launch: launch_config().profile(Some("steam")),
+            ..SpawnOptions::default()
+        };
+
+        assert_eq!(
+            command_line(
+                "steam",
+                &[],
+                &opts,
+                LaunchMode::Service,
+                Some("app-waycast-steam-1")
+            ),
+            [
+                "systemd-run",
+                "--user",
+                "--collect",
+                "--quiet",
+                "--unit=app-waycast-steam-1",
+                "--slice=app-graphical.slice",
+                "--property=CPUWeight=200",
+                "--working-directory=/opt/steam",
+                "--setenv=MOZ_ENABLE_WAYLAND=0",
+                "--",
+                "uwsm",
+                "app",
+                "--",
+                "gamemoderun",
+                "steam",
+            ]
+        );
+    }
+
+    #[test]
+    fn forks_only_get_the_prefix() {
+        let opts = SpawnOptions {
+            launch: launch_config().profile(Some("steam")),
+            ..SpawnOptions::default()
+        };
+
+        assert_eq!(
+            command_line("steam", &["-silent"], &opts, LaunchMode::Fork, None),
+            ["uwsm", "app", "--", "gamemoderun", "steam", "-silent"]
+        );
+    }
+}
@@ -1,9 +1,26 @@
+pub mod actions;
+pub mod apps;
+pub mod calculator;
+pub mod clipboard;
 pub mod config;
+pub mod custom;
 pub mod data;
+pub mod desktop_entry;
+pub mod emoji;
 pub mod icon;
+pub mod keymap;
 pub mod launcher;
 mod model;
+pub mod plugins;
+pub mod preview;
+pub mod ranking;
+pub mod recent;
 mod search;
+pub mod shell;
+pub mod ssh;
+pub mod windows;
 
 pub use model::{ItemKind, LauncherItem, WaycastScanner};
-pub use search::{FuzzyMatcher, FuzzySearchable};
+pub use search::{
+    AliasedItem, FuzzyMatch, FuzzyMatcher, FuzzySearchable, MatchIndices, SearchMode, SearchResult,
+};
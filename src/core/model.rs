@@ -1,11 +1,35 @@
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq, sqlx::Type)]
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq, Hash, sqlx::Type)]
 #[sqlx(type_name = "TEXT", rename_all = "lowercase")]
 pub enum ItemKind {
     DesktopEntry,
     File,
     Project,
+    /// A calculation evaluated from the search query. Never indexed.
+    Calculator,
+    /// An entry in the clipboard history. Never indexed.
+    Clipboard,
+    /// An emoji or symbol from the built-in table. Never indexed.
+    Emoji,
+    /// An open window, listed live from the compositor. Never indexed.
+    Window,
+    /// A shell command typed after the run prefix, or run before
+    Command,
+    /// Same as `Command`, run in a terminal
+    TerminalCommand,
+    /// A program on `$PATH`
+    Executable,
+    /// A bookmarked or visited web page
+    Bookmark,
+    /// A host from the SSH config or `known_hosts`
+    SshHost,
+    /// A file some app opened recently, from the GTK recent files list
+    RecentFile,
+    /// An entry from `[[commands]]` in the config
+    Custom,
+    /// An item from a plugin, indexed or from a search
+    Plugin,
     Unknown,
 }
 
@@ -15,11 +39,47 @@ impl From<String> for ItemKind {
             "desktopentry" => Self::DesktopEntry,
             "file" => Self::File,
             "project" => Self::Project,
+            "calculator" => Self::Calculator,
+            "clipboard" => Self::Clipboard,
+            "emoji" => Self::Emoji,
+            "window" => Self::Window,
+            "command" => Self::Command,
+            "terminalcommand" => Self::TerminalCommand,
+            "executable" => Self::Executable,
+            "bookmark" => Self::Bookmark,
+            "sshhost" => Self::SshHost,
+            "recentfile" => Self::RecentFile,
+            "custom" => Self::Custom,
+            "plugin" => Self::Plugin,
             _ => Self::Unknown,
         }
     }
 }
 
+impl ItemKind {
+    /// What items of this kind are called, for showing which kind the
+    /// results are filtered to.
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::DesktopEntry => "Apps",
+            Self::File => "Files",
+            Self::Project => "Projects",
+            Self::Calculator => "Calculations",
+            Self::Clipboard => "Clipboard",
+            Self::Emoji => "Emoji",
+            Self::Window => "Windows",
+            Self::Command | Self::TerminalCommand => "Commands",
+            Self::Executable => "Programs",
+            Self::Bookmark => "Bookmarks",
+            Self::SshHost => "SSH hosts",
+            Self::RecentFile => "Recent files",
+            Self::Custom => "Custom commands",
+            Self::Plugin => "Plugins",
+            Self::Unknown => "Other",
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct LauncherItem {
     pub id: String,
@@ -0,0 +1,293 @@
+//! Item sources that run as their own processes, so they can be written in
+//! any language and can't take the daemon down with them.
+//!
+//! A plugin is any program that speaks JSON-RPC 2.0 on stdin and stdout,
+//! one message per line:
+//!
+//! - `list` returns items to index. It's called on every scan.
+//! - `search` with `{"query": "..."}` returns items for a query.
+//! - `activate` with `{"id": "..."}` does whatever picking an item does.
+//!
+//! Items are objects with an `id`, a `title`, and optionally a
+//! `description` and an `icon`. A plugin only needs the methods it uses;
+//! answering the others with "method not found" is fine.
+
+mod process;
+
+use std::io;
+use std::sync::Arc;
+use std::time::Duration;
+
+use serde::Deserialize;
+use serde_json::json;
+use thiserror::Error;
+use tracing::{error, warn};
+
+use super::config::PluginConfig;
+use super::{ItemKind, LauncherItem};
+
+pub use process::Plugin;
+
+pub const ICON: &str = "application-x-addon";
+
+/// Goes between the plugin's name and its own id for the item in an item
+/// id, which is how activating an item finds its plugin again.
+const ID_SEPARATOR: char = ':';
+
+#[derive(Debug, Error)]
+pub enum PluginError {
+    #[error("Could not start plugin {0}: {1}")]
+    Spawn(String, io::Error),
+    #[error("Plugin {0} is waiting to be restarted")]
+    NotRunning(String),
+    #[error("Plugin {0} did not answer within {1:?}")]
+    Timeout(String, Duration),
+    #[error("Plugin {0} stopped: {1}")]
+    Crashed(String, String),
+    #[error("Plugin {0} does not support {1}")]
+    Unsupported(String, String),
+    #[error("Plugin {0} failed: {1}")]
+    Remote(String, String),
+    #[error("Unexpected reply from plugin {0}: {1}")]
+    Json(String, serde_json::Error),
+    #[error("No plugin for {0}")]
+    Unknown(String),
+}
+
+#[derive(Deserialize)]
+struct PluginItem {
+    id: String,
+    title: String,
+    description: Option<String>,
+    icon: Option<String>,
+}
+
+impl Plugin {
+    /// Items to index. Plugins without a `list` have none.
+    pub fn list(&self) -> Result<Vec<LauncherItem>, PluginError> {
+        self.items("list", json!({}))
+    }
+
+    pub fn search(&self, query: &str) -> Result<Vec<LauncherItem>, PluginError> {
+        self.items("search", json!({ "query": query }))
+    }
+
+    /// Activate the plugin's item with its own id, not the item id.
+    pub fn activate(&self, id: &str) -> Result<(), PluginError> {
+        let _: serde_json::Value = self.call("activate", json!({ "id": id }))?;
+        Ok(())
+    }
+
+    /// The prefix that searches only this plugin, if it has one.
+    pub fn prefix(&self) -> Option<&str> {
+        self.cfg
+            .prefix
+            .as_deref()
+            .filter(|prefix| !prefix.is_empty())
+    }
+
+    fn items(
+        &self,
+        method: &str,
+        params: serde_json::Value,
+    ) -> Result<Vec<LauncherItem>, PluginError> {
+        let items: Vec<PluginItem> = match self.call(method, params) {
+            Err(PluginError::Unsupported(..)) => return Ok(Vec::new()),
+            result => result?,
+        };
+
+        Ok(items
+            .into_iter()
+            .map(|item| LauncherItem {
+                id: format!("{}{ID_SEPARATOR}{}", self.name(), item.id),
+                kind: ItemKind::Plugin,
+                title: item.title,
+                description: item.description,
+                icon: item
+                    .icon
+                    .or_else(|| self.cfg.icon.clone())
+                    .unwrap_or_else(|| ICON.into()),
+            })
+            .collect())
+    }
+}
+
+/// Every configured plugin.
+pub struct PluginHost {
+    plugins: Vec<Arc<Plugin>>,
+}
+
+impl PluginHost {
+    /// Plugins without a name or with a name that's taken are left out,
+    /// since their items couldn't be told apart.
+    pub fn new(configs: &[PluginConfig]) -> Self {
+        let mut plugins: Vec<Arc<Plugin>> = Vec::new();
+
+        for cfg in configs {
+            if cfg.name.is_empty() || cfg.name.contains(ID_SEPARATOR) {
+                warn!(
+                    "Skipping plugin \"{}\": names can't be empty or contain {ID_SEPARATOR}",
+                    cfg.name
+                );
+                continue;
+            }
+            if plugins.iter().any(|plugin| plugin.name() == cfg.name) {
+                warn!("Skipping plugin \"{}\": the name is taken", cfg.name);
+                continue;
+            }
+            plugins.push(Arc::new(Plugin::new(cfg.clone())));
+        }
+
+        Self { plugins }
+    }
+
+    /// Indexed items from every plugin. One failing leaves the others'.
+    pub fn list(&self) -> Vec<LauncherItem> {
+        self.each(&self.plugins.iter().collect::<Vec<_>>(), Plugin::list)
+    }
+
+    /// Whether any plugin takes part in every search, by having no prefix.
+    pub fn searches_everything(&self) -> bool {
+        self.plugins.iter().any(|plugin| plugin.prefix().is_none())
+    }
+
+    /// Results from the plugins that take part in every search, which are
+    /// the ones without a prefix.
+    pub fn search(&self, query: &str) -> Vec<LauncherItem> {
+        let plugins: Vec<&Arc<Plugin>> = self
+            .plugins
+            .iter()
+            .filter(|plugin| plugin.prefix().is_none())
+            .collect();
+
+        self.each(&plugins, |plugin| plugin.search(query))
+    }
+
+    /// The plugin whose prefix `query` starts with, and the rest of the
+    /// query.
+    pub fn for_query<'q>(&self, query: &'q str) -> Option<(Arc<Plugin>, &'q str)> {
+        self.plugins.iter().find_map(|plugin| {
+            let rest = query.strip_prefix(plugin.prefix()?)?;
+            Some((Arc::clone(plugin), rest))
+        })
+    }
+
+    pub fn activate(&self, item_id: &str) -> Result<(), PluginError> {
+        let (name, id) = item_id
+            .split_once(ID_SEPARATOR)
+            .ok_or_else(|| PluginError::Unknown(item_id.to_string()))?;
+        let plugin = self
+            .plugins
+            .iter()
+            .find(|plugin| plugin.name() == name)
+            .ok_or_else(|| PluginError::Unknown(item_id.to_string()))?;
+
+        plugin.activate(id)
+    }
+
+    /// Run `call` on every plugin at once, so one slow plugin costs its
+    /// own timeout rather than everyone's.
+    fn each(
+        &self,
+        plugins: &[&Arc<Plugin>],
+        call: impl Fn(&Plugin) -> Result<Vec<LauncherItem>, PluginError> + Sync,
+    ) -> Vec<LauncherItem> {
+        let call = &call;
+        std::thread::scope(|scope| {
+            let calls: Vec<_> = plugins
+                .iter()
+                .map(|plugin| scope.spawn(move || call(plugin)))
+                .collect();
+
+            calls
+                .into_iter()
+                .filter_map(|call| match call.join() {
+                    Ok(Ok(items)) => Some(items),
+                    Ok(Err(e)) => {
+                        error!("{e}");
+                        None
+                    }
+                    Err(_) => None,
+                })
+                .flatten()
+                .collect()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Instant;
+
+    /// A stand-in plugin: `sh` running `script`, which gets each request
+    /// on stdin with its id in `$id`.
+    fn plugin(script: &str, timeout_ms: u64) -> Plugin {
+        let script = format!(
+            r#"while read -r line; do id=$(echo "$line" | sed 's/.*"id":\([0-9][0-9]*\).*/\1/'); {script}; done"#
+        );
+
+        Plugin::new(PluginConfig {
+            name: String::from("test"),
+            command: vec![String::from("sh"), String::from("-c"), script],
+            prefix: None,
+            icon: None,
+            timeout_ms,
+        })
+    }
+
+    const ANSWER: &str = r#"echo "{\"jsonrpc\":\"2.0\",\"id\":$id,\"result\":[{\"id\":\"a\",\"title\":\"Alpha\"}]}""#;
+
+    #[test]
+    fn searches_and_prefixes_item_ids() {
+        let items = plugin(ANSWER, 5000).search("al").unwrap();
+
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].id, "test:a");
+        assert_eq!(items[0].title, "Alpha");
+        assert_eq!(items[0].icon, ICON);
+    }
+
+    #[test]
+    fn treats_missing_methods_as_no_items() {
+        let missing = r#"echo "{\"jsonrpc\":\"2.0\",\"id\":$id,\"error\":{\"code\":-32601,\"message\":\"Method not found\"}}""#;
+
+        assert!(plugin(missing, 5000).list().unwrap().is_empty());
+    }
+
+    #[test]
+    fn times_out_and_restarts_after_crashing() {
+        let slow = plugin("sleep 5", 100);
+        assert!(matches!(slow.search("x"), Err(PluginError::Timeout(..))));
+
+        // Answers once, then exits.
+        let mut flaky = plugin(&format!("{ANSWER}; exit 1"), 5000);
+        flaky.restart_delay = Duration::ZERO;
+        assert!(flaky.search("x").is_ok());
+        assert!(matches!(flaky.search("x"), Err(PluginError::Crashed(..))));
+        assert!(flaky.search("x").is_ok());
+    }
+
+    #[test]
+    fn kills_and_restarts_a_plugin_that_stops_answering() {
+        let dir = tempfile::tempdir().unwrap();
+        let marker = dir.path().join("hung");
+        // Hangs on its first request, and answers once it's been restarted.
+        let script = format!(
+            r#"if [ ! -e "{marker}" ]; then touch "{marker}"; sleep 5; fi; {ANSWER}"#,
+            marker = marker.display()
+        );
+        let mut hung = plugin(&script, 100);
+        hung.restart_delay = Duration::from_millis(300);
+        assert!(matches!(hung.search("x"), Err(PluginError::Timeout(..))));
+
+        // Left alone until the restart delay is up, rather than waited on
+        // for the whole timeout again.
+        let started = Instant::now();
+        assert!(matches!(hung.search("x"), Err(PluginError::NotRunning(..))));
+        assert!(started.elapsed() < Duration::from_millis(100));
+
+        std::thread::sleep(Duration::from_millis(300));
+        assert_eq!(hung.search("x").unwrap().len(), 1);
+    }
+}
@@ -0,0 +1,230 @@
+//! One plugin process and the JSON-RPC calls made to it.
+
+use std::io::{self, BufRead, BufReader, Write};
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::sync::Mutex;
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::time::{Duration, Instant};
+
+use serde::Deserialize;
+use serde::de::DeserializeOwned;
+use serde_json::{Value, json};
+use tracing::{info, warn};
+
+use super::PluginError;
+use crate::core::config::PluginConfig;
+
+/// How long a plugin that crashed, hung or failed to start is left alone
+/// before it's started again, so a broken one isn't respawned on every
+/// keystroke.
+const RESTART_DELAY: Duration = Duration::from_secs(10);
+
+/// JSON-RPC's code for a method the plugin doesn't implement.
+const METHOD_NOT_FOUND: i64 = -32601;
+
+enum State {
+    Stopped,
+    Running(Process),
+    /// Crashed, hung or failed to start at this point
+    Failed(Instant),
+}
+
+/// A plugin process, started on its first call and restarted after it
+/// crashes or stops answering. Calls to one plugin are made one at a time.
+pub struct Plugin {
+    pub(super) cfg: PluginConfig,
+    state: Mutex<State>,
+    pub(super) restart_delay: Duration,
+}
+
+impl Plugin {
+    pub fn new(cfg: PluginConfig) -> Self {
+        Self {
+            cfg,
+            state: Mutex::new(State::Stopped),
+            restart_delay: RESTART_DELAY,
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.cfg.name
+    }
+
+    /// Call `method` and wait for the result, at most the configured
+    /// timeout.
+    pub(super) fn call<T: DeserializeOwned>(
+        &self,
+        method: &str,
+        params: Value,
+    ) -> Result<T, PluginError> {
+        let mut state = self.state.lock().expect("plugin state lock poisoned");
+
+        if let State::Failed(at) = *state
+            && at.elapsed() < self.restart_delay
+        {
+            return Err(PluginError::NotRunning(self.cfg.name.clone()));
+        }
+        if !matches!(*state, State::Running(_)) {
+            match self.spawn() {
+                Ok(process) => *state = State::Running(process),
+                Err(e) => {
+                    *state = State::Failed(Instant::now());
+                    return Err(e);
+                }
+            }
+        }
+        let State::Running(process) = &mut *state else {
+            unreachable!("plugin was just started");
+        };
+
+        let timeout = Duration::from_millis(self.cfg.timeout_ms);
+        let result = process.request(method, params, timeout);
+        // A plugin that doesn't answer in time is likely stuck, and would
+        // hold up every search after this one too. Dropping the process
+        // kills it.
+        if let Err(PluginError::Crashed(..) | PluginError::Timeout(..)) = result {
+            *state = State::Failed(Instant::now());
+        }
+
+        serde_json::from_value(result?).map_err(|e| PluginError::Json(self.cfg.name.clone(), e))
+    }
+
+    fn spawn(&self) -> Result<Process, PluginError> {
+        let spawn_error = |e| PluginError::Spawn(self.cfg.name.clone(), e);
+        let Some((program, args)) = self.cfg.command.split_first() else {
+            return Err(spawn_error(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "no command configured",
+            )));
+        };
+
+        info!("Starting plugin {}", self.cfg.name);
+        let mut child = Command::new(program)
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::inherit())
+            .spawn()
+            .map_err(spawn_error)?;
+
+        let stdin = child.stdin.take().expect("piped stdin");
+        let stdout = child.stdout.take().expect("piped stdout");
+
+        // Replies are read on their own thread so a request can stop
+        // waiting on a plugin that's stuck. The channel closes when the
+        // plugin exits.
+        let (tx, replies) = mpsc::channel();
+        std::thread::spawn(move || {
+            for line in BufReader::new(stdout).lines() {
+                let Ok(line) = line else {
+                    break;
+                };
+                if tx.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+
+        Ok(Process {
+            name: self.cfg.name.clone(),
+            child,
+            stdin,
+            replies,
+            next_id: 0,
+        })
+    }
+}
+
+struct Process {
+    name: String,
+    child: Child,
+    stdin: ChildStdin,
+    replies: Receiver<String>,
+    next_id: u64,
+}
+
+#[derive(Deserialize)]
+struct Reply {
+    id: Option<u64>,
+    #[serde(default)]
+    result: Value,
+    error: Option<RpcError>,
+}
+
+#[derive(Deserialize)]
+struct RpcError {
+    code: i64,
+    message: String,
+}
+
+impl Process {
+    fn request(
+        &mut self,
+        method: &str,
+        params: Value,
+        timeout: Duration,
+    ) -> Result<Value, PluginError> {
+        let id = self.next_id;
+        self.next_id += 1;
+
+        let request = json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params });
+        if let Err(e) = writeln!(self.stdin, "{request}").and_then(|()| self.stdin.flush()) {
+            return Err(self.crashed(e.to_string()));
+        }
+
+        let deadline = Instant::now() + timeout;
+        loop {
+            let line = match self
+                .replies
+                .recv_timeout(deadline.saturating_duration_since(Instant::now()))
+            {
+                Ok(line) => line,
+                Err(RecvTimeoutError::Timeout) => {
+                    return Err(PluginError::Timeout(self.name.clone(), timeout));
+                }
+                Err(RecvTimeoutError::Disconnected) => {
+                    return Err(self.crashed(String::from("closed its output")));
+                }
+            };
+
+            let reply: Reply = match serde_json::from_str(&line) {
+                Ok(reply) => reply,
+                Err(e) => {
+                    warn!(
+                        "Ignoring output from plugin {} that isn't a reply: {e}",
+                        self.name
+                    );
+                    continue;
+                }
+            };
+            // Answers to requests that already timed out.
+            if reply.id != Some(id) {
+                continue;
+            }
+
+            return match reply.error {
+                Some(error) if error.code == METHOD_NOT_FOUND => Err(PluginError::Unsupported(
+                    self.name.clone(),
+                    method.to_string(),
+                )),
+                Some(error) => Err(PluginError::Remote(self.name.clone(), error.message)),
+                None => Ok(reply.result),
+            };
+        }
+    }
+
+    fn crashed(&mut self, reason: String) -> PluginError {
+        let reason = match self.child.try_wait() {
+            Ok(Some(status)) => status.to_string(),
+            _ => reason,
+        };
+        PluginError::Crashed(self.name.clone(), reason)
+    }
+}
+
+impl Drop for Process {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
@@ -0,0 +1,364 @@
+//! What's in a file, for the preview pane: the first lines of text, whether
+//! it's an image, or what a directory holds, along with its type, size and
+//! when it last changed.
+
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// How much of a text file is shown.
+const MAX_LINES: usize = 60;
+const MAX_TEXT_BYTES: u64 = 16 * 1024;
+/// How many entries of a directory are listed.
+const MAX_ENTRIES: usize = 50;
+/// Longest side of an image preview. Images are shrunk to this once, when
+/// they're loaded, rather than decoded at full size every time they're shown.
+const THUMBNAIL_SIZE: u32 = 256;
+/// Previews kept before the least recently shown one is dropped.
+const CACHE_SIZE: usize = 64;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Preview {
+    /// MIME type, guessed from the name and the first bytes
+    pub mime: String,
+    /// In bytes
+    pub size: u64,
+    /// Unix timestamp (seconds) of the last change
+    pub modified: Option<i64>,
+    pub content: PreviewContent,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PreviewContent {
+    /// The first lines of a text file, and the file's extension for picking
+    /// a syntax to highlight them with
+    Text { text: String, extension: String },
+    /// An image, shrunk to fit the pane
+    Image(Thumbnail),
+    /// An SVG, which is drawn from the file at any size
+    Svg,
+    /// The first entries of a directory, folders first, and how many there
+    /// are in all
+    Directory {
+        entries: Vec<DirEntry>,
+        total: usize,
+    },
+    /// Nothing to show besides the metadata
+    Other,
+}
+
+/// RGBA pixels of an image, at most `THUMBNAIL_SIZE` on its longest side.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Thumbnail {
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Vec<u8>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DirEntry {
+    pub name: String,
+    pub is_dir: bool,
+}
+
+pub fn load(path: &Path) -> io::Result<Preview> {
+    let metadata = fs::metadata(path)?;
+    let modified = metadata
+        .modified()
+        .ok()
+        .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+        .map(|elapsed| elapsed.as_secs() as i64);
+
+    if metadata.is_dir() {
+        let (entries, total) = list_dir(path)?;
+        return Ok(Preview {
+            mime: String::from("inode/directory"),
+            size: metadata.len(),
+            modified,
+            content: PreviewContent::Directory { entries, total },
+        });
+    }
+
+    let mut head = Vec::new();
+    File::open(path)?
+        .take(MAX_TEXT_BYTES)
+        .read_to_end(&mut head)?;
+
+    let (content_type, _) = gio::content_type_guess(Some(path), Some(head.as_slice()));
+    let mime = gio::content_type_get_mime_type(&content_type)
+        .map_or_else(|| content_type.to_string(), |mime| mime.to_string());
+    let is_text = gio::content_type_is_a(&content_type, "text/plain") || mime == "application/json";
+
+    let content = if mime == "image/svg+xml" {
+        PreviewContent::Svg
+    } else if mime.starts_with("image/") {
+        // Formats that can't be decoded get their details shown all the same.
+        thumbnail(path).map_or(PreviewContent::Other, PreviewContent::Image)
+    } else if is_text && let Some(text) = first_lines(&head) {
+        PreviewContent::Text {
+            text,
+            extension: path
+                .extension()
+                .map(|extension| extension.to_string_lossy().into_owned())
+                .unwrap_or_default(),
+        }
+    } else {
+        PreviewContent::Other
+    };
+
+    Ok(Preview {
+        mime,
+        size: metadata.len(),
+        modified,
+        content,
+    })
+}
+
+/// The image at `path`, shrunk to fit `THUMBNAIL_SIZE` if it's any bigger.
+fn thumbnail(path: &Path) -> Option<Thumbnail> {
+    let image = image::io::Reader::open(path)
+        .ok()?
+        .with_guessed_format()
+        .ok()?
+        .decode()
+        .ok()?;
+    let image = if image.width() > THUMBNAIL_SIZE || image.height() > THUMBNAIL_SIZE {
+        image.thumbnail(THUMBNAIL_SIZE, THUMBNAIL_SIZE)
+    } else {
+        image
+    };
+
+    let rgba = image.into_rgba8();
+    Some(Thumbnail {
+        width: rgba.width(),
+        height: rgba.height(),
+        pixels: rgba.into_raw(),
+    })
+}
+
+/// The first lines of `head`, the start of a file, if it's text. A
+/// character cut in half at the end of `head` is dropped.
+fn first_lines(head: &[u8]) -> Option<String> {
+    if head.contains(&0) {
+        return None;
+    }
+    let text = match std::str::from_utf8(head) {
+        Ok(text) => text,
+        Err(e) if e.error_len().is_none() => std::str::from_utf8(&head[..e.valid_up_to()]).ok()?,
+        Err(_) => return None,
+    };
+
+    Some(text.lines().take(MAX_LINES).collect::<Vec<_>>().join("\n"))
+}
+
+fn list_dir(path: &Path) -> io::Result<(Vec<DirEntry>, usize)> {
+    let mut entries: Vec<DirEntry> = fs::read_dir(path)?
+        .flatten()
+        .map(|entry| DirEntry {
+            name: entry.file_name().to_string_lossy().into_owned(),
+            is_dir: entry.file_type().is_ok_and(|kind| kind.is_dir()),
+        })
+        .filter(|entry| !entry.name.starts_with('.'))
+        .collect();
+    entries.sort_by(|a, b| {
+        b.is_dir
+            .cmp(&a.is_dir)
+            .then_with(|| a.name.to_lowercase().cmp(&b.name.to_lowercase()))
+    });
+
+    let total = entries.len();
+    entries.truncate(MAX_ENTRIES);
+    Ok((entries, total))
+}
+
+/// `bytes` in the largest unit that keeps it at least 1, like `4.2 MB`.
+pub fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["KB", "MB", "GB", "TB", "PB"];
+
+    if bytes < 1000 {
+        return format!("{bytes} bytes");
+    }
+    let mut size = bytes as f64 / 1000.0;
+    let mut unit = 0;
+    while size >= 1000.0 && unit < UNITS.len() - 1 {
+        size /= 1000.0;
+        unit += 1;
+    }
+    format!("{size:.1} {}", UNITS[unit])
+}
+
+/// How long ago `then` was, roughly, like `3 hours ago`.
+pub fn format_age(then: i64, now: i64) -> String {
+    let seconds = now.saturating_sub(then).max(0);
+    let (count, unit) = match seconds {
+        0..60 => return String::from("just now"),
+        60..3600 => (seconds / 60, "minute"),
+        3600..86400 => (seconds / 3600, "hour"),
+        86400..2_592_000 => (seconds / 86400, "day"),
+        2_592_000..31_536_000 => (seconds / 2_592_000, "month"),
+        _ => (seconds / 31_536_000, "year"),
+    };
+
+    match count {
+        1 => format!("1 {unit} ago"),
+        _ => format!("{count} {unit}s ago"),
+    }
+}
+
+/// Previews by path, loaded again only once the file has changed. Moving
+/// through the results goes back and forth over the same few files.
+#[derive(Default)]
+pub struct PreviewCache {
+    previews: Mutex<CachedPreviews>,
+}
+
+#[derive(Default)]
+struct CachedPreviews {
+    previews: HashMap<PathBuf, CachedPreview>,
+    /// Counts up with every preview shown, to tell which was shown longest ago
+    clock: u64,
+}
+
+struct CachedPreview {
+    modified: SystemTime,
+    preview: Arc<Preview>,
+    shown_at: u64,
+}
+
+impl PreviewCache {
+    pub fn get(&self, path: &Path) -> io::Result<Arc<Preview>> {
+        let modified = fs::metadata(path)?.modified()?;
+
+        {
+            let mut cache = self.lock();
+            cache.clock += 1;
+            let now = cache.clock;
+            if let Some(cached) = cache.previews.get_mut(path)
+                && cached.modified == modified
+            {
+                cached.shown_at = now;
+                return Ok(Arc::clone(&cached.preview));
+            }
+        }
+
+        let preview = Arc::new(load(path)?);
+        let mut cache = self.lock();
+        if cache.previews.len() >= CACHE_SIZE
+            && !cache.previews.contains_key(path)
+            && let Some(oldest) = cache
+                .previews
+                .iter()
+                .min_by_key(|(_, cached)| cached.shown_at)
+                .map(|(path, _)| path.clone())
+        {
+            cache.previews.remove(&oldest);
+        }
+        cache.clock += 1;
+        let shown_at = cache.clock;
+        cache.previews.insert(
+            path.to_path_buf(),
+            CachedPreview {
+                modified,
+                preview: Arc::clone(&preview),
+                shown_at,
+            },
+        );
+
+        Ok(preview)
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, CachedPreviews> {
+        self.previews.lock().expect("preview cache lock poisoned")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_whole_lines_of_text() {
+        let mut text = "fn main() {}\n".repeat(MAX_LINES + 5).into_bytes();
+        assert_eq!(first_lines(&text).unwrap().lines().count(), MAX_LINES);
+
+        // "é" cut in half by the read
+        text = b"caf\xc3".to_vec();
+        assert_eq!(first_lines(&text).as_deref(), Some("caf"));
+
+        assert_eq!(first_lines(b"\x89PNG\r\n\x1a\n\0\0"), None);
+    }
+
+    #[test]
+    fn lists_folders_first() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir(dir.path().join("src")).unwrap();
+        fs::write(dir.path().join("README.md"), "").unwrap();
+        fs::write(dir.path().join("build.rs"), "").unwrap();
+        fs::write(dir.path().join(".gitignore"), "").unwrap();
+
+        let (entries, total) = list_dir(dir.path()).unwrap();
+
+        let names: Vec<&str> = entries.iter().map(|entry| entry.name.as_str()).collect();
+        assert_eq!(names, ["src", "build.rs", "README.md"]);
+        assert_eq!(total, 3);
+    }
+
+    #[test]
+    fn shrinks_images_to_thumbnails() {
+        let dir = tempfile::tempdir().unwrap();
+        let big = dir.path().join("big.png");
+        let small = dir.path().join("small");
+        image::RgbaImage::new(1024, 512).save(&big).unwrap();
+        image::RgbaImage::new(40, 30)
+            .save_with_format(&small, image::ImageFormat::Png)
+            .unwrap();
+
+        let thumbnail = super::thumbnail(&big).unwrap();
+        assert_eq!((thumbnail.width, thumbnail.height), (256, 128));
+        assert_eq!(thumbnail.pixels.len(), 256 * 128 * 4);
+        // Small ones are kept as they are, whatever they're named.
+        let thumbnail = super::thumbnail(&small).unwrap();
+        assert_eq!((thumbnail.width, thumbnail.height), (40, 30));
+
+        fs::write(dir.path().join("broken.png"), "not a png").unwrap();
+        assert!(super::thumbnail(&dir.path().join("broken.png")).is_none());
+    }
+
+    #[test]
+    fn drops_the_preview_shown_longest_ago() {
+        let dir = tempfile::tempdir().unwrap();
+        let folders: Vec<PathBuf> = (0..=CACHE_SIZE)
+            .map(|i| {
+                let folder = dir.path().join(i.to_string());
+                fs::create_dir(&folder).unwrap();
+                folder
+            })
+            .collect();
+        let cache = PreviewCache::default();
+
+        for folder in &folders[..CACHE_SIZE] {
+            cache.get(folder).unwrap();
+        }
+        // Shown again, so the second folder is now the one shown longest ago.
+        cache.get(&folders[0]).unwrap();
+        cache.get(&folders[CACHE_SIZE]).unwrap();
+
+        let cached = cache.lock();
+        assert_eq!(cached.previews.len(), CACHE_SIZE);
+        assert!(cached.previews.contains_key(&folders[0]));
+        assert!(!cached.previews.contains_key(&folders[1]));
+        assert!(cached.previews.contains_key(&folders[CACHE_SIZE]));
+    }
+
+    #[test]
+    fn formats_sizes_and_ages() {
+        assert_eq!(format_size(512), "512 bytes");
+        assert_eq!(format_size(4_200_000), "4.2 MB");
+        assert_eq!(format_age(1000, 1030), "just now");
+        assert_eq!(format_age(0, 3 * 3600 + 59), "3 hours ago");
+        assert_eq!(format_age(0, 86400), "1 day ago");
+    }
+}
@@ -0,0 +1,181 @@
+use std::collections::HashMap;
+
+use serde::Serialize;
+
+use super::config::RankingConfig;
+use super::data::launches::LaunchStats;
+use super::{ItemKind, LauncherItem};
+
+/// Launch count at which an item gets half of the frecency weight.
+const FRECENCY_HALF_LAUNCHES: f32 = 5.0;
+/// Time since the last launch after which an item gets half of the recency
+/// weight.
+const RECENCY_HALF_LIFE_SECS: f32 = 3.0 * 24.0 * 60.0 * 60.0;
+
+/// The parts that make up a result's score, kept around so it's possible to
+/// tell why something ranked where it did.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct ScoreBreakdown {
+    /// Fuzzy match score. Already scaled down when it came from a secondary key.
+    pub fuzzy: f32,
+    /// Multiplier for the item's kind
+    pub kind_weight: f32,
+    /// Points for how often the item was launched
+    pub frecency: f32,
+    /// Points for how recently the item was launched
+    pub recency: f32,
+}
+
+impl Default for ScoreBreakdown {
+    fn default() -> Self {
+        Self {
+            fuzzy: 0.0,
+            kind_weight: 1.0,
+            frecency: 0.0,
+            recency: 0.0,
+        }
+    }
+}
+
+impl ScoreBreakdown {
+    pub fn total(&self) -> f32 {
+        self.fuzzy * self.kind_weight + self.frecency + self.recency
+    }
+}
+
+/// Turns raw fuzzy scores into final rankings using the configured weights
+/// and the launch history.
+pub struct Ranker<'a> {
+    config: &'a RankingConfig,
+    stats: &'a HashMap<(String, ItemKind), LaunchStats>,
+    /// Unix timestamp (seconds) recency is measured against
+    now: i64,
+}
+
+impl<'a> Ranker<'a> {
+    pub fn new(
+        config: &'a RankingConfig,
+        stats: &'a HashMap<(String, ItemKind), LaunchStats>,
+        now: i64,
+    ) -> Self {
+        Self { config, stats, now }
+    }
+
+    pub fn score(&self, fuzzy: u16, item: &LauncherItem) -> ScoreBreakdown {
+        let mut breakdown = ScoreBreakdown {
+            fuzzy: fuzzy as f32,
+            kind_weight: self.config.kind(item.kind).weight,
+            ..ScoreBreakdown::default()
+        };
+
+        if let Some(stats) = self.stats.get(&(item.id.clone(), item.kind)) {
+            let launches = stats.launches as f32;
+            breakdown.frecency =
+                self.config.frecency_weight * launches / (launches + FRECENCY_HALF_LAUNCHES);
+
+            let age = (self.now - stats.last_launched_at).max(0) as f32;
+            breakdown.recency =
+                self.config.recency_weight * 0.5_f32.powf(age / RECENCY_HALF_LIFE_SECS);
+        }
+
+        breakdown
+    }
+
+    /// Score, sort and cut fuzzy matches down to what a search returns,
+    /// honoring both the per-kind limits and the overall limit.
+    pub fn rank<'i>(
+        &self,
+        matches: Vec<(u16, &'i LauncherItem)>,
+    ) -> Vec<(ScoreBreakdown, &'i LauncherItem)> {
+        let mut scored: Vec<(ScoreBreakdown, &'i LauncherItem)> = matches
+            .into_iter()
+            .map(|(fuzzy, item)| (self.score(fuzzy, item), item))
+            .collect();
+
+        scored.sort_by(|(a, _), (b, _)| b.total().total_cmp(&a.total()));
+
+        let mut per_kind: HashMap<ItemKind, usize> = HashMap::new();
+        scored
+            .into_iter()
+            .filter(|(_, item)| {
+                let taken = per_kind.entry(item.kind).or_default();
+                *taken += 1;
+                *taken <= self.config.kind(item.kind).limit
+            })
+            .take(self.config.max_results)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::config::KindRanking;
+
+    fn item(id: &str, kind: ItemKind) -> LauncherItem {
+        LauncherItem {
+            id: id.into(),
+            kind,
+            title: id.into(),
+            description: None,
+            icon: "application-x-executable".into(),
+        }
+    }
+
+    #[test]
+    fn launch_history_lifts_an_otherwise_weaker_match() {
+        let config = RankingConfig::default();
+        let now = 1_000_000;
+        let stats = HashMap::from([(
+            ("often".to_string(), ItemKind::DesktopEntry),
+            LaunchStats {
+                launches: 20,
+                last_launched_at: now - 60,
+            },
+        )]);
+        let often = item("often", ItemKind::DesktopEntry);
+        let never = item("never", ItemKind::DesktopEntry);
+
+        let ranked = Ranker::new(&config, &stats, now).rank(vec![(100, &never), (90, &often)]);
+
+        assert_eq!(ranked[0].1.id, "often");
+        assert!(ranked[0].0.frecency > 0.0);
+        assert!(ranked[0].0.recency > 0.0);
+        assert_eq!(ranked[1].0.frecency, 0.0);
+    }
+
+    #[test]
+    fn per_kind_limits_and_the_overall_limit_both_apply() {
+        let config = RankingConfig {
+            max_results: 3,
+            files: KindRanking {
+                weight: 1.0,
+                limit: 1,
+            },
+            ..RankingConfig::default()
+        };
+        let stats = HashMap::new();
+        let files = [item("a", ItemKind::File), item("b", ItemKind::File)];
+        let apps = [
+            item("c", ItemKind::DesktopEntry),
+            item("d", ItemKind::DesktopEntry),
+            item("e", ItemKind::DesktopEntry),
+        ];
+
+        let matches = files
+            .iter()
+            .map(|item| (200, item))
+            .chain(apps.iter().map(|item| (100, item)))
+            .collect();
+        let ranked = Ranker::new(&config, &stats, 0).rank(matches);
+
+        assert_eq!(ranked.len(), 3);
+        assert_eq!(
+            ranked
+                .iter()
+                .filter(|(_, item)| item.kind == ItemKind::File)
+                .count(),
+            1
+        );
+    }
+}
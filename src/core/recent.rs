@@ -0,0 +1,284 @@
+//! Files opened in any app, from the `recently-used.xbel` list GTK apps
+//! (and most others through them) record every opened file in.
+
+use std::collections::HashMap;
+use std::ffi::OsString;
+use std::fs;
+use std::io;
+use std::os::unix::ffi::OsStringExt;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+use roxmltree::{Document, Node, ParsingOptions};
+use thiserror::Error;
+
+use super::ItemKind;
+use super::data::launches::LaunchStats;
+
+const BOOKMARK_NS: &str = "http://www.freedesktop.org/standards/desktop-bookmarks";
+
+#[derive(Debug, Error)]
+pub enum RecentError {
+    #[error("Could not read the recent files list: {0}")]
+    Io(#[from] io::Error),
+    #[error("Could not parse the recent files list: {0}")]
+    Xml(#[from] roxmltree::Error),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecentFile {
+    pub path: PathBuf,
+    /// Unix timestamp (seconds) of the last time any app opened it
+    pub visited: i64,
+    /// Name of the app that opened it last
+    pub application: Option<String>,
+}
+
+/// Where GTK keeps the list, `$XDG_DATA_HOME/recently-used.xbel`.
+pub fn default_path() -> Option<PathBuf> {
+    directories::BaseDirs::new().map(|dirs| dirs.data_dir().join("recently-used.xbel"))
+}
+
+pub fn read(path: &Path) -> Result<Vec<RecentFile>, RecentError> {
+    Ok(parse(&fs::read_to_string(path)?)?)
+}
+
+/// Local files in an XBEL document, most recently opened first. Anything
+/// that isn't a `file://` URI, like a recent network share, is left out.
+pub fn parse(contents: &str) -> Result<Vec<RecentFile>, roxmltree::Error> {
+    let options = ParsingOptions {
+        allow_dtd: true,
+        ..ParsingOptions::default()
+    };
+    let document = Document::parse_with_options(contents, options)?;
+
+    let mut files: Vec<RecentFile> = document
+        .root_element()
+        .children()
+        .filter(|node| node.has_tag_name("bookmark"))
+        .filter_map(recent_file)
+        .collect();
+    files.sort_by_key(|file| std::cmp::Reverse(file.visited));
+
+    Ok(files)
+}
+
+fn recent_file(bookmark: Node) -> Option<RecentFile> {
+    let path = file_path(bookmark.attribute("href")?)?;
+
+    // Apps that only update their own entry leave `visited` behind, so the
+    // newest stamp anywhere on the bookmark is the real last visit.
+    let visited = ["visited", "modified", "added"]
+        .into_iter()
+        .filter_map(|name| bookmark.attribute(name).and_then(parse_timestamp))
+        .max()
+        .unwrap_or(0);
+
+    let mut last_app: Option<(i64, Option<&str>)> = None;
+    for app in bookmark
+        .descendants()
+        .filter(|node| node.has_tag_name((BOOKMARK_NS, "application")))
+    {
+        // Older files have a Unix `timestamp` instead of `modified`.
+        let Some(stamp) = app
+            .attribute("modified")
+            .and_then(parse_timestamp)
+            .or_else(|| app.attribute("timestamp")?.parse().ok())
+        else {
+            continue;
+        };
+
+        if last_app.is_none_or(|(latest, _)| stamp >= latest) {
+            last_app = Some((stamp, app.attribute("name")));
+        }
+    }
+
+    let visited = last_app.map_or(visited, |(stamp, _)| visited.max(stamp));
+    let application = last_app.and_then(|(_, name)| name).map(String::from);
+
+    Some(RecentFile {
+        path,
+        visited,
+        application,
+    })
+}
+
+/// The path in a `file://` URI, percent-decoded.
+fn file_path(uri: &str) -> Option<PathBuf> {
+    let encoded = uri.strip_prefix("file://")?.as_bytes();
+
+    let mut bytes = Vec::with_capacity(encoded.len());
+    let mut i = 0;
+    while i < encoded.len() {
+        let decoded = match encoded[i] {
+            b'%' => encoded
+                .get(i + 1..i + 3)
+                .and_then(|hex| std::str::from_utf8(hex).ok())
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok()),
+            _ => None,
+        };
+        match decoded {
+            Some(byte) => {
+                bytes.push(byte);
+                i += 3;
+            }
+            None => {
+                bytes.push(encoded[i]);
+                i += 1;
+            }
+        }
+    }
+
+    Some(PathBuf::from(OsString::from_vec(bytes)))
+}
+
+/// Seconds since the epoch for an XBEL stamp like
+/// `2024-03-09T14:07:31.123456Z`. They're always UTC.
+fn parse_timestamp(stamp: &str) -> Option<i64> {
+    let (date, time) = stamp.strip_suffix('Z')?.split_once('T')?;
+    let time = time.split('.').next()?;
+
+    let mut date = date.splitn(3, '-').map(|part| part.parse::<i64>().ok());
+    let mut time = time.splitn(3, ':').map(|part| part.parse::<i64>().ok());
+    let (year, month, day) = (date.next()??, date.next()??, date.next()??);
+    let (hours, minutes, seconds) = (time.next()??, time.next()??, time.next()??);
+
+    Some(days_from_civil(year, month, day) * 86400 + hours * 3600 + minutes * 60 + seconds)
+}
+
+/// Days between 1970-01-01 and a date in the proleptic Gregorian calendar.
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    // Counted from March, so the leap day is the last day of the year.
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+
+    era * 146_097 + day_of_era - 719_468
+}
+
+/// The recent files list, read again only once it has changed. Searches
+/// use the visit times for ranking, and the list can run into thousands of
+/// entries.
+pub struct RecentFiles {
+    path: Option<PathBuf>,
+    cache: Mutex<Option<(SystemTime, Arc<Vec<RecentFile>>)>>,
+}
+
+impl RecentFiles {
+    pub fn new(path: Option<PathBuf>) -> Self {
+        Self {
+            path,
+            cache: Mutex::new(None),
+        }
+    }
+
+    pub fn load(&self) -> Arc<Vec<RecentFile>> {
+        let Some(path) = &self.path else {
+            return Arc::default();
+        };
+        let Ok(modified) = fs::metadata(path).and_then(|metadata| metadata.modified()) else {
+            return Arc::default();
+        };
+
+        let mut cache = self.cache.lock().expect("recent files lock poisoned");
+        if let Some((read_at, files)) = cache.as_ref()
+            && *read_at == modified
+        {
+            return Arc::clone(files);
+        }
+
+        let files = Arc::new(read(path).unwrap_or_else(|e| {
+            tracing::error!("{e}");
+            Vec::new()
+        }));
+        *cache = Some((modified, Arc::clone(&files)));
+        files
+    }
+
+    /// Count opening a file in any app as a visit for ranking, the same as
+    /// launching it from here. Launch counts stay waycast's own.
+    pub fn add_visits(&self, stats: &mut HashMap<(String, ItemKind), LaunchStats>) {
+        for file in self.load().iter() {
+            let key = (
+                file.path.to_string_lossy().into_owned(),
+                ItemKind::RecentFile,
+            );
+            stats
+                .entry(key)
+                .and_modify(|stats| {
+                    stats.last_launched_at = stats.last_launched_at.max(file.visited)
+                })
+                .or_insert(LaunchStats {
+                    launches: 0,
+                    last_launched_at: file.visited,
+                });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_timestamps() {
+        assert_eq!(parse_timestamp("1970-01-01T00:00:00Z"), Some(0));
+        assert_eq!(
+            parse_timestamp("2024-03-09T14:07:31.123456Z"),
+            Some(1_709_993_251)
+        );
+        assert_eq!(parse_timestamp("2023-12-31T23:59:59Z"), Some(1_704_067_199));
+        assert_eq!(parse_timestamp("2024-03-09 14:07:31"), None);
+    }
+
+    #[test]
+    fn lists_local_files_by_last_visit() {
+        let xbel = r#"<?xml version="1.0" encoding="UTF-8"?>
+<xbel version="1.0"
+      xmlns:bookmark="http://www.freedesktop.org/standards/desktop-bookmarks"
+      xmlns:mime="http://www.freedesktop.org/standards/shared-mime-info">
+  <bookmark href="file:///home/me/Documents/Tax%20return%202023.pdf" added="2024-03-01T09:00:00Z" modified="2024-03-01T09:00:00Z" visited="2024-03-01T09:00:00Z">
+    <info>
+      <metadata owner="http://freedesktop.org">
+        <mime:mime-type type="application/pdf"/>
+        <bookmark:applications>
+          <bookmark:application name="Firefox" exec="&apos;firefox %u&apos;" modified="2024-03-01T09:00:00Z" count="1"/>
+          <bookmark:application name="Document Viewer" exec="&apos;papers %u&apos;" modified="2024-03-09T14:07:31.123456Z" count="4"/>
+        </bookmark:applications>
+      </metadata>
+    </info>
+  </bookmark>
+  <bookmark href="sftp://server/notes.txt" added="2025-01-01T00:00:00Z" modified="2025-01-01T00:00:00Z" visited="2025-01-01T00:00:00Z"/>
+  <bookmark href="file:///home/me/notes.md" added="2023-12-31T23:59:59Z" modified="2023-12-31T23:59:59Z" visited="2024-03-05T00:00:00Z">
+    <info>
+      <metadata owner="http://freedesktop.org">
+        <bookmark:applications>
+          <bookmark:application name="gedit" exec="&apos;gedit %u&apos;" timestamp="1704067199" count="1"/>
+        </bookmark:applications>
+      </metadata>
+    </info>
+  </bookmark>
+</xbel>"#;
+
+        let files = parse(xbel).unwrap();
+
+        assert_eq!(
+            files,
+            [
+                RecentFile {
+                    path: PathBuf::from("/home/me/Documents/Tax return 2023.pdf"),
+                    visited: 1_709_993_251,
+                    application: Some("Document Viewer".into()),
+                },
+                RecentFile {
+                    path: PathBuf::from("/home/me/notes.md"),
+                    visited: 1_709_596_800,
+                    application: Some("gedit".into()),
+                },
+            ]
+        );
+    }
+}
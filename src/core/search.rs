@@ -3,8 +3,12 @@ use nucleo_matcher::{
     pattern::{Atom, AtomKind, CaseMatching, Normalization},
 };
 use std::cmp::Reverse;
+use std::fmt;
+use std::str::FromStr;
 
 use super::LauncherItem;
+use super::ranking::ScoreBreakdown;
+use super::{emoji, windows};
 
 impl FuzzySearchable for LauncherItem {
     fn primary_key(&self) -> String {
@@ -16,16 +20,158 @@ impl FuzzySearchable for LauncherItem {
     }
 }
 
+/// An item along with the other things it can be found by. Aliases come
+/// after the description, so the description stays the first secondary
+/// key.
+#[derive(Debug, Clone)]
+pub struct KeyedItem<'a> {
+    pub item: &'a LauncherItem,
+    pub aliases: &'a [String],
+    pub keywords: Vec<String>,
+}
+
+impl FuzzySearchable for KeyedItem<'_> {
+    fn primary_key(&self) -> String {
+        self.item.primary_key()
+    }
+
+    fn secondary_keys(&self) -> Vec<String> {
+        let mut keys = self.item.secondary_keys();
+        keys.extend(self.aliases.iter().cloned());
+        keys
+    }
+
+    fn keywords(&self) -> Vec<String> {
+        self.keywords.clone()
+    }
+}
+
 pub trait FuzzySearchable {
     fn primary_key(&self) -> String;
 
     fn secondary_keys(&self) -> Vec<String> {
         Vec::new()
     }
+
+    /// Words the item can be found by that are never shown, so they count
+    /// for less than the keys that are.
+    fn keywords(&self) -> Vec<String> {
+        Vec::new()
+    }
+}
+
+/// Lets a filtered list of references be searched without cloning what
+/// they point at.
+impl<T: FuzzySearchable> FuzzySearchable for &T {
+    fn primary_key(&self) -> String {
+        (*self).primary_key()
+    }
+
+    fn secondary_keys(&self) -> Vec<String> {
+        (*self).secondary_keys()
+    }
+
+    fn keywords(&self) -> Vec<String> {
+        (*self).keywords()
+    }
+}
+
+/// Character positions in each key that matched the query. Positions count
+/// chars, not bytes, so they can be used to split the key for display.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MatchIndices {
+    pub primary: Vec<u32>,
+    /// One entry per secondary key, in the order `secondary_keys` returns
+    /// them. A key that did not match gets an empty list.
+    pub secondary: Vec<Vec<u32>>,
+}
+
+/// A candidate that matched the query.
+#[derive(Debug)]
+pub struct FuzzyMatch<'a, T> {
+    pub item: &'a T,
+    pub score: u16,
+    pub indices: MatchIndices,
+}
+
+/// An item returned by a search along with what made it match and how it
+/// was scored.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SearchResult {
+    pub item: LauncherItem,
+    pub indices: MatchIndices,
+    pub score: ScoreBreakdown,
+}
+
+impl SearchResult {
+    pub fn title_indices(&self) -> &[u32] {
+        &self.indices.primary
+    }
+
+    /// The description is the first (and only) secondary key of a
+    /// `LauncherItem`.
+    pub fn description_indices(&self) -> &[u32] {
+        self.indices
+            .secondary
+            .first()
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+}
+
+impl From<LauncherItem> for SearchResult {
+    fn from(item: LauncherItem) -> Self {
+        Self {
+            item,
+            indices: MatchIndices::default(),
+            score: ScoreBreakdown::default(),
+        }
+    }
+}
+
+/// A search that only covers one kind of result, like emoji. Each one is
+/// entered by starting the query with its prefix, so showing the launcher in
+/// a mode just means starting out with that prefix typed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SearchMode {
+    Emoji,
+    Windows,
+}
+
+impl SearchMode {
+    pub fn prefix(self) -> String {
+        match self {
+            Self::Emoji => emoji::PREFIX.to_string(),
+            Self::Windows => windows::PREFIX.to_string(),
+        }
+    }
+}
+
+impl FromStr for SearchMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "emoji" => Ok(Self::Emoji),
+            "windows" => Ok(Self::Windows),
+            _ => Err(format!("unknown mode {s}, expected emoji or windows")),
+        }
+    }
+}
+
+impl fmt::Display for SearchMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Emoji => f.write_str("emoji"),
+            Self::Windows => f.write_str("windows"),
+        }
+    }
 }
 
 pub struct FuzzyMatcher {
     matcher: Matcher,
+    secondary_key_weight: f32,
+    keyword_weight: f32,
 }
 
 impl Default for FuzzyMatcher {
@@ -38,43 +184,79 @@ impl FuzzyMatcher {
     pub fn new() -> Self {
         Self {
             matcher: Matcher::new(nucleo_matcher::Config::DEFAULT),
+            secondary_key_weight: 0.9,
+            keyword_weight: 0.7,
         }
     }
 
+    /// Multiplier applied to scores from secondary keys, so a title match
+    /// beats an equally good description match.
+    pub fn with_secondary_key_weight(mut self, weight: f32) -> Self {
+        self.secondary_key_weight = weight;
+        self
+    }
+
+    /// Multiplier applied to scores from keywords.
+    pub fn with_keyword_weight(mut self, weight: f32) -> Self {
+        self.keyword_weight = weight;
+        self
+    }
+
     pub fn match_items<'a, T: FuzzySearchable>(
         &mut self,
         query: &str,
         candidates: &'a [T],
         max_results: usize,
-    ) -> Vec<&'a T> {
+    ) -> Vec<FuzzyMatch<'a, T>> {
+        self.score_items(query, candidates)
+            .into_iter()
+            .take(max_results)
+            .map(|(score, item)| FuzzyMatch {
+                item,
+                score,
+                indices: self.match_indices(query, item),
+            })
+            .collect()
+    }
+
+    /// Score every candidate that matches, best first. Cheaper than
+    /// `match_items` since no match positions are computed, which makes it
+    /// the right call when results get re-ranked before being cut.
+    pub fn score_items<'a, T: FuzzySearchable>(
+        &mut self,
+        query: &str,
+        candidates: &'a [T],
+    ) -> Vec<(u16, &'a T)> {
         if query.is_empty() {
             return Vec::new();
         }
 
-        let atom = Atom::new(
-            query,
-            CaseMatching::Ignore,
-            Normalization::Smart,
-            AtomKind::Fuzzy,
-            false,
-        );
-
+        let atom = atom(query);
+        let mut buf = Vec::new();
         let mut scored_matches: Vec<(u16, &'a T)> = Vec::new();
 
         for candidate in candidates {
             let mut best_score = None;
             let primary_key = candidate.primary_key();
             if let Some(score) =
-                atom.score(Utf32Str::Ascii(primary_key.as_bytes()), &mut self.matcher)
+                atom.score(Utf32Str::new(&primary_key, &mut buf), &mut self.matcher)
             {
                 best_score = Some(score);
             }
 
-            for secondary_key in &candidate.secondary_keys() {
-                if let Some(score) =
-                    atom.score(Utf32Str::Ascii(secondary_key.as_bytes()), &mut self.matcher)
-                {
-                    let adjusted_score = (score as f32 * 0.9) as u16;
+            let weighted_keys = candidate
+                .secondary_keys()
+                .into_iter()
+                .map(|key| (key, self.secondary_key_weight))
+                .chain(
+                    candidate
+                        .keywords()
+                        .into_iter()
+                        .map(|key| (key, self.keyword_weight)),
+                );
+            for (key, weight) in weighted_keys {
+                if let Some(score) = atom.score(Utf32Str::new(&key, &mut buf), &mut self.matcher) {
+                    let adjusted_score = (score as f32 * weight) as u16;
                     best_score = Some(
                         best_score.map_or(adjusted_score, |existing| existing.max(adjusted_score)),
                     );
@@ -88,9 +270,144 @@ impl FuzzyMatcher {
 
         scored_matches.sort_by_key(|item| Reverse(item.0));
         scored_matches
-            .into_iter()
-            .take(max_results)
-            .map(|(_, item)| item)
-            .collect()
+    }
+
+    /// Positions in each of `item`'s keys that match `query`. Only worth
+    /// computing for what is actually shown, so this is kept separate from
+    /// scoring.
+    pub fn match_indices<T: FuzzySearchable>(&mut self, query: &str, item: &T) -> MatchIndices {
+        if query.is_empty() {
+            return MatchIndices::default();
+        }
+
+        let atom = atom(query);
+        let mut buf = Vec::new();
+
+        MatchIndices {
+            primary: self.indices(&atom, &item.primary_key(), &mut buf),
+            secondary: item
+                .secondary_keys()
+                .iter()
+                .map(|key| self.indices(&atom, key, &mut buf))
+                .collect(),
+        }
+    }
+
+    fn indices(&mut self, atom: &Atom, key: &str, buf: &mut Vec<char>) -> Vec<u32> {
+        let mut indices = Vec::new();
+        atom.indices(Utf32Str::new(key, buf), &mut self.matcher, &mut indices);
+
+        // nucleo neither sorts nor dedups, and callers walk these in order.
+        indices.sort_unstable();
+        indices.dedup();
+        indices
+    }
+}
+
+fn atom(query: &str) -> Atom {
+    Atom::new(
+        query,
+        CaseMatching::Ignore,
+        Normalization::Smart,
+        AtomKind::Fuzzy,
+        false,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::ItemKind;
+
+    fn item(title: &str, description: &str) -> LauncherItem {
+        LauncherItem {
+            id: title.into(),
+            kind: ItemKind::File,
+            title: title.into(),
+            description: Some(description.into()),
+            icon: "text-x-generic".into(),
+        }
+    }
+
+    #[test]
+    fn matches_carry_the_positions_that_matched_in_each_key() {
+        let candidates = [item("Firefox", "/usr/share/firefox")];
+
+        let matches = FuzzyMatcher::new().match_items("ffx", &candidates, 5);
+
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].indices.primary, [0, 4, 6]);
+        assert_eq!(matches[0].indices.secondary.len(), 1);
+        assert!(!matches[0].indices.secondary[0].is_empty());
+    }
+
+    #[test]
+    fn keys_that_do_not_match_get_no_positions() {
+        let candidates = [item("Notes", "/home/user/Documents")];
+
+        let matches = FuzzyMatcher::new().match_items("docs", &candidates, 5);
+
+        assert_eq!(matches.len(), 1);
+        assert!(matches[0].indices.primary.is_empty());
+        assert!(!matches[0].indices.secondary[0].is_empty());
+    }
+
+    #[test]
+    fn aliases_are_matched_like_descriptions() {
+        let firefox = item("Firefox", "Web browser");
+        let notes = item("Notes", "");
+        let aliases = [String::from("www")];
+        let candidates = [
+            KeyedItem {
+                item: &firefox,
+                aliases: &aliases,
+                keywords: Vec::new(),
+            },
+            KeyedItem {
+                item: &notes,
+                aliases: &[],
+                keywords: Vec::new(),
+            },
+        ];
+
+        let matches = FuzzyMatcher::new().match_items("www", &candidates, 5);
+
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].item.item.title, "Firefox");
+        assert!(matches[0].indices.secondary[0].is_empty());
+        assert!(!matches[0].indices.secondary[1].is_empty());
+    }
+
+    #[test]
+    fn keywords_count_for_less_than_descriptions() {
+        let gimp = item("GIMP", "");
+        let editor = item("Editor", "image editor");
+        let candidates = [
+            KeyedItem {
+                item: &gimp,
+                aliases: &[],
+                keywords: vec![String::from("image editor")],
+            },
+            KeyedItem {
+                item: &editor,
+                aliases: &[],
+                keywords: Vec::new(),
+            },
+        ];
+
+        let scores = FuzzyMatcher::new().score_items("image", &candidates);
+
+        assert_eq!(scores.len(), 2);
+        assert_eq!(scores[0].1.item.title, "Editor");
+        assert!(scores[1].0 < scores[0].0);
+    }
+
+    #[test]
+    fn non_ascii_keys_report_char_positions() {
+        let candidates = [item("Café Menü", "")];
+
+        let matches = FuzzyMatcher::new().match_items("menu", &candidates, 5);
+
+        assert_eq!(matches[0].indices.primary, [5, 6, 7, 8]);
     }
 }
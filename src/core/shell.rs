@@ -0,0 +1,73 @@
+//! Running whatever is typed after the run prefix as a shell command, either
+//! in the background or in a terminal.
+
+use super::{ItemKind, LauncherItem};
+
+pub const ICON: &str = "utilities-terminal";
+pub const EXECUTABLE_ICON: &str = "application-x-executable";
+/// Most history and executable matches a run search returns.
+pub const MAX_RESULTS: usize = 20;
+
+/// The two ways of running `command`: in the background, and in a
+/// terminal. Past commands come back out of the launch history as these
+/// same items.
+pub fn command_items(command: &str) -> [LauncherItem; 2] {
+    [
+        command_item(command, ItemKind::Command),
+        command_item(command, ItemKind::TerminalCommand),
+    ]
+}
+
+pub fn command_item(command: &str, kind: ItemKind) -> LauncherItem {
+    let description = match kind {
+        ItemKind::TerminalCommand => "Run in terminal",
+        _ => "Run",
+    };
+
+    LauncherItem {
+        id: command.into(),
+        kind,
+        title: command.into(),
+        description: Some(description.into()),
+        icon: ICON.into(),
+    }
+}
+
+/// Arguments that run `command` in `terminal`, which is the terminal
+/// program plus whatever it needs before the command to execute, like
+/// `["alacritty", "-e"]`.
+///
+/// The terminal stays open on a shell once the command exits, otherwise
+/// anything that prints and quits would flash by unread.
+pub fn terminal_args(terminal: &[String], command: &str) -> Vec<String> {
+    terminal
+        .iter()
+        .cloned()
+        .chain([
+            String::from("sh"),
+            String::from("-c"),
+            format!("{command}; exec \"${{SHELL:-sh}}\""),
+        ])
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wraps_commands_for_the_terminal() {
+        let terminal = vec![String::from("alacritty"), String::from("-e")];
+
+        assert_eq!(
+            terminal_args(&terminal, "htop -d 5"),
+            [
+                "alacritty",
+                "-e",
+                "sh",
+                "-c",
+                "htop -d 5; exec \"${SHELL:-sh}\""
+            ]
+        );
+    }
+}
@@ -0,0 +1,260 @@
+//! Hosts to connect to, out of the OpenSSH client config and `known_hosts`.
+
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::{ItemKind, LauncherItem};
+
+pub const ICON: &str = "network-server";
+
+/// Same limit ssh puts on nested `Include`s, which also stops include
+/// loops.
+const MAX_INCLUDE_DEPTH: usize = 16;
+
+/// Where a host was found, shown as its description.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HostSource {
+    Config,
+    KnownHosts,
+}
+
+/// The item for connecting to `target`, which is whatever goes after `ssh`
+/// on the command line.
+pub fn host_item(target: &str, source: HostSource) -> LauncherItem {
+    let title = target.strip_prefix("ssh://").unwrap_or(target);
+    let description = match source {
+        HostSource::Config => "SSH host",
+        HostSource::KnownHosts => "Known SSH host",
+    };
+
+    LauncherItem {
+        id: target.into(),
+        kind: ItemKind::SshHost,
+        title: title.into(),
+        description: Some(description.into()),
+        icon: ICON.into(),
+    }
+}
+
+/// The command line that opens `ssh target` in `terminal`. Each argument
+/// stays whole, so terminal arguments and targets can have spaces in them.
+pub fn ssh_command(terminal: &[String], target: &str) -> Vec<String> {
+    terminal
+        .iter()
+        .cloned()
+        .chain([String::from("ssh"), target.to_string()])
+        .collect()
+}
+
+/// Every host named in `ssh_dir/config` and the files it includes. Only
+/// literal names are kept: a `Host` pattern with wildcards or a negation
+/// matches hosts but doesn't name one.
+pub fn config_hosts(ssh_dir: &Path) -> Vec<String> {
+    let mut hosts = Vec::new();
+    read_config(&ssh_dir.join("config"), ssh_dir, 0, &mut hosts);
+
+    let mut seen = HashSet::new();
+    hosts.retain(|host| seen.insert(host.clone()));
+    hosts
+}
+
+fn read_config(path: &Path, ssh_dir: &Path, depth: usize, hosts: &mut Vec<String>) {
+    if depth > MAX_INCLUDE_DEPTH {
+        return;
+    }
+    let Ok(contents) = fs::read_to_string(path) else {
+        return;
+    };
+
+    for line in contents.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        // Keywords are separated from their arguments by whitespace, an
+        // `=`, or both.
+        let (keyword, args) = line
+            .split_once(|c: char| c.is_whitespace() || c == '=')
+            .unwrap_or((line, ""));
+        let args = args.trim_start_matches(|c: char| c.is_whitespace() || c == '=');
+
+        if keyword.eq_ignore_ascii_case("host") {
+            hosts.extend(
+                arguments(args)
+                    .filter(|pattern| !pattern.contains(['*', '?', '!']))
+                    .map(String::from),
+            );
+        } else if keyword.eq_ignore_ascii_case("include") {
+            for include in arguments(args) {
+                for file in expand_include(include, ssh_dir) {
+                    read_config(&file, ssh_dir, depth + 1, hosts);
+                }
+            }
+        }
+    }
+}
+
+/// Whitespace separated arguments, some of which may be quoted.
+fn arguments(args: &str) -> impl Iterator<Item = &str> {
+    args.split_whitespace()
+        .map(|arg| arg.trim_matches('"'))
+        .filter(|arg| !arg.is_empty())
+}
+
+/// The files an `Include` argument names. Relative paths are relative to
+/// `~/.ssh`, and wildcards are expanded in the file name, in lexical order
+/// like ssh does.
+fn expand_include(include: &str, ssh_dir: &Path) -> Vec<PathBuf> {
+    let path = match include.strip_prefix("~/") {
+        Some(rest) => ssh_dir.parent().unwrap_or(ssh_dir).join(rest),
+        None => ssh_dir.join(include),
+    };
+
+    let Some(name) = path.file_name().and_then(|name| name.to_str()) else {
+        return Vec::new();
+    };
+    if !name.contains(['*', '?']) {
+        return vec![path];
+    }
+
+    let dir = path.parent().unwrap_or(ssh_dir);
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut files: Vec<PathBuf> = entries
+        .flatten()
+        .filter(|entry| wildcard_match(name, &entry.file_name().to_string_lossy()))
+        .map(|entry| entry.path())
+        .collect();
+    files.sort();
+    files
+}
+
+/// `*` and `?` matching, the only wildcards ssh config patterns have.
+fn wildcard_match(pattern: &str, name: &str) -> bool {
+    match pattern.chars().next() {
+        None => name.is_empty(),
+        Some('*') => {
+            let rest = &pattern[1..];
+            name.char_indices()
+                .map(|(i, _)| i)
+                .chain([name.len()])
+                .any(|i| wildcard_match(rest, &name[i..]))
+        }
+        Some(p) => {
+            let mut chars = name.chars();
+            match chars.next() {
+                Some(c) if p == '?' || p == c => {
+                    wildcard_match(&pattern[p.len_utf8()..], chars.as_str())
+                }
+                _ => false,
+            }
+        }
+    }
+}
+
+/// Hosts in a `known_hosts` file, as ssh targets. Hashed entries can't be
+/// turned back into names and are skipped, as are patterns and CA or
+/// revocation lines. Hosts on other ports come back as `ssh://host:port`.
+pub fn known_hosts(contents: &str) -> Vec<String> {
+    let mut seen = HashSet::new();
+
+    contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with(['#', '@', '|']))
+        .filter_map(|line| line.split_whitespace().next())
+        .flat_map(|names| names.split(','))
+        .filter(|name| !name.contains(['*', '?', '!']))
+        .filter_map(known_host_target)
+        .filter(|target| seen.insert(target.clone()))
+        .collect()
+}
+
+fn known_host_target(name: &str) -> Option<String> {
+    let Some(bracketed) = name.strip_prefix('[') else {
+        return (!name.is_empty()).then(|| name.to_string());
+    };
+
+    let (host, port) = bracketed.split_once("]:")?;
+    match port {
+        "22" => Some(host.to_string()),
+        // An IPv6 address keeps its brackets, or the port would read as
+        // part of it.
+        _ if host.contains(':') => Some(format!("ssh://[{host}]:{port}")),
+        _ => Some(format!("ssh://{host}:{port}")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_hosts_through_includes() {
+        let home = tempfile::tempdir().unwrap();
+        let ssh_dir = home.path().join(".ssh");
+        fs::create_dir_all(ssh_dir.join("config.d")).unwrap();
+        fs::write(
+            ssh_dir.join("config"),
+            "Include config.d/*.conf ~/.ssh/extra\n\
+             Host *\n  ServerAliveInterval 30\n\
+             Host=bastion \"db-1\" !jump *.internal\n  User admin\n",
+        )
+        .unwrap();
+        fs::write(ssh_dir.join("config.d/b.conf"), "host builder\n").unwrap();
+        fs::write(ssh_dir.join("config.d/a.conf"), "Host archive bastion\n").unwrap();
+        fs::write(ssh_dir.join("config.d/notes.txt"), "Host ignored\n").unwrap();
+        fs::write(
+            ssh_dir.join("extra"),
+            "# Host commented\nMatch host x\nHost extra\n",
+        )
+        .unwrap();
+
+        assert_eq!(
+            config_hosts(&ssh_dir),
+            ["archive", "bastion", "builder", "extra", "db-1"]
+        );
+    }
+
+    #[test]
+    fn skips_hashed_known_hosts() {
+        let contents = "\
+            github.com,140.82.121.4 ssh-ed25519 AAAA\n\
+            |1|F1E1KeoE/eEWhi10WpGv4OdiO6Y=|3988QV0VE8wmZL7suNrYQLITLCg= ssh-rsa AAAA\n\
+            [git.example.com]:2222 ssh-ed25519 AAAA\n\
+            [pi.local]:22 ssh-ed25519 AAAA\n\
+            [::1]:2222 ssh-ed25519 AAAA\n\
+            [fe80::1]:22 ssh-ed25519 AAAA\n\
+            @cert-authority *.example.com ssh-rsa AAAA\n\
+            github.com ecdsa-sha2-nistp256 AAAA\n";
+
+        assert_eq!(
+            known_hosts(contents),
+            [
+                "github.com",
+                "140.82.121.4",
+                "ssh://git.example.com:2222",
+                "pi.local",
+                "ssh://[::1]:2222",
+                "fe80::1",
+            ]
+        );
+    }
+
+    #[test]
+    fn keeps_arguments_with_spaces_whole() {
+        let terminal = vec![String::from("foot"), String::from("--title=SSH session")];
+
+        assert_eq!(
+            ssh_command(&terminal, "my host"),
+            ["foot", "--title=SSH session", "ssh", "my host"]
+        );
+        assert_eq!(
+            ssh_command(&[], "ssh://[::1]:2222"),
+            ["ssh", "ssh://[::1]:2222"]
+        );
+    }
+}
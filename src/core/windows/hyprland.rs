@@ -0,0 +1,146 @@
+//! Hyprland's request socket: one plain text request per connection, with
+//! a `j/` prefix asking for a JSON reply.
+
+use std::ffi::OsStr;
+use std::io::{Read, Write};
+use std::net::Shutdown;
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+
+use super::{Window, WindowBackend, WindowError, connect};
+
+pub struct HyprlandBackend {
+    socket: PathBuf,
+}
+
+impl HyprlandBackend {
+    pub fn new(socket: PathBuf) -> Self {
+        Self { socket }
+    }
+
+    /// Where the instance with `signature` listens. Releases before 0.40
+    /// kept their sockets in /tmp instead of the runtime directory.
+    pub fn socket_path(runtime_dir: &Path, signature: &OsStr) -> PathBuf {
+        let socket = runtime_dir
+            .join("hypr")
+            .join(signature)
+            .join(".socket.sock");
+        if socket.exists() {
+            return socket;
+        }
+
+        Path::new("/tmp/hypr").join(signature).join(".socket.sock")
+    }
+
+    fn request(&self, request: &str) -> Result<Vec<u8>, WindowError> {
+        let mut stream = connect(&self.socket)?;
+        stream.write_all(request.as_bytes())?;
+        stream.shutdown(Shutdown::Write)?;
+
+        let mut reply = Vec::new();
+        stream.read_to_end(&mut reply)?;
+        Ok(reply)
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Client {
+    address: String,
+    mapped: bool,
+    hidden: bool,
+    class: String,
+    title: String,
+    workspace: Workspace,
+    /// 0 for the focused window, counting up from there
+    focus_history_id: i32,
+}
+
+#[derive(Deserialize)]
+struct Workspace {
+    name: String,
+}
+
+impl WindowBackend for HyprlandBackend {
+    fn windows(&self) -> Result<Vec<Window>, WindowError> {
+        let clients: Vec<Client> = serde_json::from_slice(&self.request("j/clients")?)?;
+
+        Ok(clients
+            .into_iter()
+            .filter(|client| client.mapped && !client.hidden)
+            .map(|client| Window {
+                id: client.address,
+                app_id: Some(client.class).filter(|class| !class.is_empty()),
+                title: client.title,
+                workspace: Some(client.workspace.name),
+                focused: client.focus_history_id == 0,
+            })
+            .collect())
+    }
+
+    fn focus(&self, id: &str) -> Result<(), WindowError> {
+        if !id.starts_with("0x") {
+            return Err(WindowError::InvalidId(id.to_string()));
+        }
+
+        let reply = self.request(&format!("dispatch focuswindow address:{id}"))?;
+        let reply = String::from_utf8_lossy(&reply);
+        match reply.trim() {
+            "ok" => Ok(()),
+            refusal => Err(WindowError::Refused(refusal.to_string())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::os::unix::net::UnixStream;
+
+    use super::super::fake_compositor;
+    use super::*;
+
+    fn answer(reply: &'static str) -> impl FnOnce(UnixStream) -> String {
+        move |mut stream| {
+            let mut request = String::new();
+            stream.read_to_string(&mut request).unwrap();
+            stream.write_all(reply.as_bytes()).unwrap();
+            request
+        }
+    }
+
+    #[test]
+    fn lists_mapped_clients() {
+        let clients = r#"[
+            {"address": "0x55d1", "mapped": true, "hidden": false, "class": "firefox",
+             "title": "Waycast", "workspace": {"id": 2, "name": "2"}, "focusHistoryID": 1},
+            {"address": "0x55d2", "mapped": true, "hidden": false, "class": "kitty",
+             "title": "~", "workspace": {"id": 1, "name": "1"}, "focusHistoryID": 0},
+            {"address": "0x55d3", "mapped": false, "hidden": false, "class": "",
+             "title": "", "workspace": {"id": -1, "name": ""}, "focusHistoryID": 2}
+        ]"#;
+        let (_dir, socket, server) = fake_compositor(answer(clients));
+
+        let windows = HyprlandBackend::new(socket).windows().unwrap();
+
+        assert_eq!(server.join().unwrap(), "j/clients");
+        assert_eq!(windows.len(), 2);
+        assert_eq!(windows[0].app_id.as_deref(), Some("firefox"));
+        assert!(!windows[0].focused);
+        assert!(windows[1].focused);
+    }
+
+    #[test]
+    fn focuses_by_address_and_reports_refusals() {
+        let (_dir, socket, server) = fake_compositor(answer("ok"));
+        HyprlandBackend::new(socket).focus("0x55d1").unwrap();
+        assert_eq!(
+            server.join().unwrap(),
+            "dispatch focuswindow address:0x55d1"
+        );
+
+        let (_dir, socket, _server) = fake_compositor(answer("No such window found"));
+        let result = HyprlandBackend::new(socket).focus("0x55d1");
+        assert!(matches!(result, Err(WindowError::Refused(_))));
+    }
+}
@@ -0,0 +1,166 @@
+//! Listing and focusing open windows through the compositor's IPC socket.
+//!
+//! Wayland has no standard way for a client to see other clients' windows,
+//! so every compositor gets its own `WindowBackend` speaking its own
+//! protocol. Compositors we don't know about get no window switching.
+
+mod hyprland;
+mod niri;
+mod sway;
+
+use std::io;
+use std::os::unix::net::UnixStream;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+use thiserror::Error;
+
+use super::{ItemKind, LauncherItem};
+
+pub use hyprland::HyprlandBackend;
+pub use niri::NiriBackend;
+pub use sway::SwayBackend;
+
+/// Queries starting with this only search open windows.
+pub const PREFIX: &str = "win:";
+pub const ICON: &str = "preferences-system-windows";
+
+/// How long to wait on the compositor before giving up. It answers from
+/// memory, so anything close to this means it's stuck.
+const IO_TIMEOUT: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Error)]
+pub enum WindowError {
+    #[error("Could not talk to the compositor: {0}")]
+    Io(#[from] io::Error),
+    #[error("Unexpected reply from the compositor: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("The compositor refused: {0}")]
+    Refused(String),
+    #[error("No window with id {0}")]
+    InvalidId(String),
+}
+
+/// An open window as the compositor reports it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Window {
+    /// Whatever the compositor identifies the window by. Only meaningful to
+    /// the backend it came from.
+    pub id: String,
+    /// Wayland app id, or the X11 class for Xwayland windows
+    pub app_id: Option<String>,
+    pub title: String,
+    pub workspace: Option<String>,
+    pub focused: bool,
+}
+
+impl Window {
+    /// Whether this window belongs to the desktop entry `desktop_id`, e.g.
+    /// `org.gnome.Nautilus.desktop`. Apps mostly set their app id to the
+    /// entry's name, some only to its last part.
+    pub fn is_instance_of(&self, desktop_id: &str) -> bool {
+        let Some(app_id) = &self.app_id else {
+            return false;
+        };
+        let stem = desktop_id.strip_suffix(".desktop").unwrap_or(desktop_id);
+        let short = stem.rsplit('.').next().unwrap_or(stem);
+
+        app_id.eq_ignore_ascii_case(stem) || app_id.eq_ignore_ascii_case(short)
+    }
+
+    /// The item the UI shows. `icon` is the icon of the app the window
+    /// belongs to, when that's known.
+    pub fn to_item(&self, icon: Option<&str>) -> LauncherItem {
+        let description = match (&self.app_id, &self.workspace) {
+            (Some(app_id), Some(workspace)) => format!("{app_id} · workspace {workspace}"),
+            (Some(app_id), None) => app_id.clone(),
+            (None, Some(workspace)) => format!("Workspace {workspace}"),
+            (None, None) => String::new(),
+        };
+
+        LauncherItem {
+            id: self.id.clone(),
+            kind: ItemKind::Window,
+            title: self.title.clone(),
+            description: Some(description),
+            icon: icon.unwrap_or(ICON).into(),
+        }
+    }
+}
+
+pub trait WindowBackend: Send + Sync {
+    /// Every open window, in whatever order the compositor lists them.
+    fn windows(&self) -> Result<Vec<Window>, WindowError>;
+
+    /// Focus the window with `id`, switching workspaces if needed.
+    fn focus(&self, id: &str) -> Result<(), WindowError>;
+}
+
+/// The backend for the compositor this session is running, picked the same
+/// way as when resolving outputs: by the IPC socket it exports.
+pub fn detect() -> Option<Arc<dyn WindowBackend>> {
+    let var = |name: &str| std::env::var_os(name).map(PathBuf::from);
+
+    if let Some(signature) = std::env::var_os("HYPRLAND_INSTANCE_SIGNATURE") {
+        let runtime = var("XDG_RUNTIME_DIR").unwrap_or_else(std::env::temp_dir);
+        return Some(Arc::new(HyprlandBackend::new(
+            HyprlandBackend::socket_path(&runtime, &signature),
+        )));
+    }
+    if let Some(socket) = var("SWAYSOCK").or_else(|| var("I3SOCK")) {
+        return Some(Arc::new(SwayBackend::new(socket)));
+    }
+    if let Some(socket) = var("NIRI_SOCKET") {
+        return Some(Arc::new(NiriBackend::new(socket)));
+    }
+
+    None
+}
+
+fn connect(path: &Path) -> io::Result<UnixStream> {
+    let stream = UnixStream::connect(path)?;
+    stream.set_read_timeout(Some(IO_TIMEOUT))?;
+    stream.set_write_timeout(Some(IO_TIMEOUT))?;
+    Ok(stream)
+}
+
+/// A compositor that answers one connection with `reply`, for testing
+/// backends without one. The handle returns what `reply` returns, which is
+/// normally the request it read.
+#[cfg(test)]
+fn fake_compositor<T: Send + 'static>(
+    reply: impl FnOnce(UnixStream) -> T + Send + 'static,
+) -> (tempfile::TempDir, PathBuf, std::thread::JoinHandle<T>) {
+    let dir = tempfile::tempdir().expect("temporary directory");
+    let path = dir.path().join("compositor.sock");
+    let listener = std::os::unix::net::UnixListener::bind(&path).expect("bind fake socket");
+
+    let handle = std::thread::spawn(move || {
+        let (stream, _) = listener.accept().expect("accept");
+        reply(stream)
+    });
+
+    (dir, path, handle)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_windows_to_desktop_entries_by_app_id() {
+        let window = |app_id: &str| Window {
+            id: "1".into(),
+            app_id: Some(app_id.into()),
+            title: String::new(),
+            workspace: None,
+            focused: false,
+        };
+
+        assert!(window("firefox").is_instance_of("firefox.desktop"));
+        assert!(window("org.gnome.Nautilus").is_instance_of("org.gnome.Nautilus.desktop"));
+        assert!(window("Nautilus").is_instance_of("org.gnome.Nautilus.desktop"));
+        assert!(!window("firefox").is_instance_of("firefox-esr.desktop"));
+    }
+}
@@ -0,0 +1,137 @@
+//! niri's IPC socket: one JSON request per line, answered with one JSON
+//! reply per line wrapped in `Ok` or `Err`.
+
+use std::io::{BufRead, BufReader, Write};
+use std::path::PathBuf;
+
+use serde::Deserialize;
+use serde::de::DeserializeOwned;
+use serde_json::json;
+
+use super::{Window, WindowBackend, WindowError, connect};
+
+pub struct NiriBackend {
+    socket: PathBuf,
+}
+
+impl NiriBackend {
+    pub fn new(socket: PathBuf) -> Self {
+        Self { socket }
+    }
+
+    fn request<T: DeserializeOwned>(&self, request: serde_json::Value) -> Result<T, WindowError> {
+        let mut stream = connect(&self.socket)?;
+        let mut line = serde_json::to_vec(&request)?;
+        line.push(b'\n');
+        stream.write_all(&line)?;
+
+        let mut reply = String::new();
+        BufReader::new(stream).read_line(&mut reply)?;
+
+        match serde_json::from_str(&reply)? {
+            Reply::Ok(value) => Ok(value),
+            Reply::Err(message) => Err(WindowError::Refused(message)),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+enum Reply<T> {
+    Ok(T),
+    Err(String),
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+enum Response {
+    Windows(Vec<NiriWindow>),
+}
+
+#[derive(Deserialize)]
+struct NiriWindow {
+    id: u64,
+    title: Option<String>,
+    app_id: Option<String>,
+    workspace_id: Option<u64>,
+    is_focused: bool,
+}
+
+impl WindowBackend for NiriBackend {
+    fn windows(&self) -> Result<Vec<Window>, WindowError> {
+        let Response::Windows(windows) = self.request(json!("Windows"))?;
+
+        Ok(windows
+            .into_iter()
+            .map(|window| Window {
+                id: window.id.to_string(),
+                app_id: window.app_id,
+                title: window.title.unwrap_or_default(),
+                workspace: window.workspace_id.map(|id| id.to_string()),
+                focused: window.is_focused,
+            })
+            .collect())
+    }
+
+    fn focus(&self, id: &str) -> Result<(), WindowError> {
+        let id: u64 = id
+            .parse()
+            .map_err(|_| WindowError::InvalidId(id.to_string()))?;
+
+        // Actions are answered with a bare "Handled".
+        let _: String = self.request(json!({ "Action": { "FocusWindow": { "id": id } } }))?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::os::unix::net::UnixStream;
+
+    use super::super::fake_compositor;
+    use super::*;
+
+    fn answer(reply: &'static str) -> impl FnOnce(UnixStream) -> String {
+        move |mut stream| {
+            let mut request = String::new();
+            BufReader::new(&stream).read_line(&mut request).unwrap();
+            stream.write_all(reply.as_bytes()).unwrap();
+            stream.write_all(b"\n").unwrap();
+            request.trim_end().to_string()
+        }
+    }
+
+    #[test]
+    fn lists_windows() {
+        let reply = r#"{"Ok": {"Windows": [
+            {"id": 7, "title": "niri.kdl", "app_id": "dev.zed.Zed", "pid": 4242,
+             "workspace_id": 3, "is_focused": true, "is_floating": false, "is_urgent": false},
+            {"id": 9, "title": null, "app_id": null, "pid": null,
+             "workspace_id": null, "is_focused": false, "is_floating": true, "is_urgent": false}
+        ]}}"#;
+        let (_dir, socket, server) = fake_compositor(answer(reply));
+
+        let windows = NiriBackend::new(socket).windows().unwrap();
+
+        assert_eq!(server.join().unwrap(), r#""Windows""#);
+        assert_eq!(windows.len(), 2);
+        assert_eq!(windows[0].app_id.as_deref(), Some("dev.zed.Zed"));
+        assert!(windows[0].focused);
+        assert_eq!(windows[1].title, "");
+    }
+
+    #[test]
+    fn focuses_by_id_and_reports_errors() {
+        let (_dir, socket, server) = fake_compositor(answer(r#"{"Ok": "Handled"}"#));
+        NiriBackend::new(socket).focus("7").unwrap();
+        assert_eq!(
+            server.join().unwrap(),
+            r#"{"Action":{"FocusWindow":{"id":7}}}"#
+        );
+
+        let (_dir, socket, _server) = fake_compositor(answer(r#"{"Err": "no such window"}"#));
+        let result = NiriBackend::new(socket).focus("8");
+        assert!(
+            matches!(result, Err(WindowError::Refused(message)) if message == "no such window")
+        );
+    }
+}
@@ -0,0 +1,208 @@
+//! Sway and i3, which share the i3 IPC protocol: a fixed header followed by
+//! a JSON payload, in both directions.
+
+use std::io::{Read, Write};
+use std::path::PathBuf;
+
+use serde::Deserialize;
+use serde_json::Value;
+
+use super::{Window, WindowBackend, WindowError, connect};
+
+const MAGIC: &[u8] = b"i3-ipc";
+const RUN_COMMAND: u32 = 0;
+const GET_TREE: u32 = 4;
+
+pub struct SwayBackend {
+    socket: PathBuf,
+}
+
+impl SwayBackend {
+    pub fn new(socket: PathBuf) -> Self {
+        Self { socket }
+    }
+
+    fn request(&self, kind: u32, payload: &str) -> Result<Vec<u8>, WindowError> {
+        let mut stream = connect(&self.socket)?;
+
+        let mut message = MAGIC.to_vec();
+        message.extend_from_slice(&(payload.len() as u32).to_ne_bytes());
+        message.extend_from_slice(&kind.to_ne_bytes());
+        message.extend_from_slice(payload.as_bytes());
+        stream.write_all(&message)?;
+
+        let mut header = [0; 14];
+        stream.read_exact(&mut header)?;
+        if &header[..6] != MAGIC {
+            return Err(WindowError::Refused("not an i3 IPC reply".into()));
+        }
+        let len = u32::from_ne_bytes(header[6..10].try_into().expect("4 bytes"));
+
+        let mut reply = vec![0; len as usize];
+        stream.read_exact(&mut reply)?;
+        Ok(reply)
+    }
+}
+
+impl WindowBackend for SwayBackend {
+    fn windows(&self) -> Result<Vec<Window>, WindowError> {
+        let tree: Node = serde_json::from_slice(&self.request(GET_TREE, "")?)?;
+        let mut windows = Vec::new();
+        tree.collect_windows(None, &mut windows);
+        Ok(windows)
+    }
+
+    fn focus(&self, id: &str) -> Result<(), WindowError> {
+        let id: i64 = id
+            .parse()
+            .map_err(|_| WindowError::InvalidId(id.to_string()))?;
+
+        #[derive(Deserialize)]
+        struct Outcome {
+            success: bool,
+            error: Option<String>,
+        }
+
+        let outcomes: Vec<Outcome> =
+            serde_json::from_slice(&self.request(RUN_COMMAND, &format!("[con_id={id}] focus"))?)?;
+        match outcomes.into_iter().find(|outcome| !outcome.success) {
+            Some(failed) => Err(WindowError::Refused(failed.error.unwrap_or_default())),
+            None => Ok(()),
+        }
+    }
+}
+
+/// A node in the layout tree. Only the fields needed to find windows.
+#[derive(Deserialize)]
+struct Node {
+    id: i64,
+    #[serde(rename = "type")]
+    kind: String,
+    name: Option<String>,
+    #[serde(default)]
+    focused: bool,
+    /// Set on Wayland windows under sway
+    app_id: Option<String>,
+    /// Set on X11 windows
+    window: Option<i64>,
+    window_properties: Option<Value>,
+    #[serde(default)]
+    nodes: Vec<Node>,
+    #[serde(default)]
+    floating_nodes: Vec<Node>,
+}
+
+impl Node {
+    fn collect_windows(&self, workspace: Option<&str>, windows: &mut Vec<Window>) {
+        let workspace = match self.kind.as_str() {
+            "workspace" => self.name.as_deref(),
+            _ => workspace,
+        };
+
+        // The scratchpad is a workspace like any other in the tree, but its
+        // windows are hidden until summoned.
+        if workspace == Some("__i3_scratch") {
+            return;
+        }
+
+        if self.is_window() {
+            let class = self
+                .window_properties
+                .as_ref()
+                .and_then(|props| props.get("class"))
+                .and_then(Value::as_str);
+
+            windows.push(Window {
+                id: self.id.to_string(),
+                app_id: self.app_id.as_deref().or(class).map(String::from),
+                title: self.name.clone().unwrap_or_default(),
+                workspace: workspace.map(String::from),
+                focused: self.focused,
+            });
+        }
+
+        for child in self.nodes.iter().chain(&self.floating_nodes) {
+            child.collect_windows(workspace, windows);
+        }
+    }
+
+    fn is_window(&self) -> bool {
+        matches!(self.kind.as_str(), "con" | "floating_con")
+            && (self.app_id.is_some() || self.window.is_some())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::os::unix::net::UnixStream;
+
+    use super::super::fake_compositor;
+    use super::*;
+
+    /// Read one request and answer it with `reply`, returning the request's
+    /// type and payload.
+    fn answer(reply: &'static str) -> impl FnOnce(UnixStream) -> (u32, String) {
+        move |mut stream| {
+            let mut header = [0; 14];
+            stream.read_exact(&mut header).unwrap();
+            let len = u32::from_ne_bytes(header[6..10].try_into().unwrap());
+            let kind = u32::from_ne_bytes(header[10..14].try_into().unwrap());
+            let mut payload = vec![0; len as usize];
+            stream.read_exact(&mut payload).unwrap();
+
+            let mut message = MAGIC.to_vec();
+            message.extend_from_slice(&(reply.len() as u32).to_ne_bytes());
+            message.extend_from_slice(&kind.to_ne_bytes());
+            message.extend_from_slice(reply.as_bytes());
+            stream.write_all(&message).unwrap();
+
+            (kind, String::from_utf8(payload).unwrap())
+        }
+    }
+
+    #[test]
+    fn lists_windows_from_the_layout_tree() {
+        let tree = r#"{
+            "id": 1, "type": "root", "name": "root", "nodes": [
+                {"id": 2, "type": "output", "name": "DP-1", "nodes": [
+                    {"id": 3, "type": "workspace", "name": "1", "nodes": [
+                        {"id": 10, "type": "con", "name": "Inbox", "app_id": "thunderbird", "focused": true},
+                        {"id": 11, "type": "con", "name": "split", "nodes": [
+                            {"id": 12, "type": "con", "name": "xterm", "window": 4194307,
+                             "window_properties": {"class": "XTerm"}}
+                        ]}
+                    ], "floating_nodes": [
+                        {"id": 13, "type": "floating_con", "name": "Calculator", "app_id": "qalculate"}
+                    ]}
+                ]},
+                {"id": 4, "type": "output", "name": "__i3", "nodes": [
+                    {"id": 5, "type": "workspace", "name": "__i3_scratch", "nodes": [
+                        {"id": 14, "type": "con", "name": "hidden", "app_id": "foot"}
+                    ]}
+                ]}
+            ]
+        }"#;
+        let (_dir, socket, server) = fake_compositor(answer(tree));
+
+        let windows = SwayBackend::new(socket).windows().unwrap();
+
+        assert_eq!(server.join().unwrap().0, GET_TREE);
+        let ids: Vec<&str> = windows.iter().map(|window| window.id.as_str()).collect();
+        assert_eq!(ids, ["10", "12", "13"]);
+        assert!(windows[0].focused);
+        assert_eq!(windows[1].app_id.as_deref(), Some("XTerm"));
+        assert_eq!(windows[2].workspace.as_deref(), Some("1"));
+    }
+
+    #[test]
+    fn focuses_by_container_id() {
+        let (_dir, socket, server) = fake_compositor(answer(r#"[{"success": true}]"#));
+
+        SwayBackend::new(socket).focus("12").unwrap();
+
+        assert_eq!(
+            server.join().unwrap(),
+            (RUN_COMMAND, "[con_id=12] focus".to_string())
+        );
+    }
+}
@@ -1,9 +1,11 @@
 use std::sync::Arc;
 use std::{path::PathBuf, time::Instant};
 
+use crate::core::apps::AppSource;
+use crate::core::clipboard::{self, Contents};
 use crate::core::data::DataError;
-use crate::core::{LauncherItem, WaycastScanner};
-use crate::daemon::watcher::{FileEvent, watch_directories};
+use crate::core::{ItemKind, LauncherItem, WaycastScanner, custom};
+use crate::daemon::watcher::{FileEvent, watch_directories, watch_files};
 use crate::facade::WaycastFacade;
 use std::time::Duration;
 use tokio::sync::mpsc;
@@ -12,9 +14,16 @@ use tracing::{Instrument, error, info, info_span};
 
 pub mod scanners;
 pub mod watcher;
-use scanners::{ApplicationScanner, FileScanner, projects::ProjectScanner};
+use scanners::{
+    ApplicationScanner, FileScanner, appimages::AppImageExtractor, browsers::BrowserScanner,
+    executables::ExecutableScanner, projects::ProjectScanner, recent::RecentFileScanner,
+    ssh::SshScanner,
+};
 
 static MAX_MPSC_BUF_SIZE: usize = 1;
+/// Copies waiting to be written to the clipboard history. Unlike directory
+/// changes these can't be coalesced, so there's some room to queue.
+static CLIPBOARD_BUF_SIZE: usize = 16;
 
 pub struct WaycastDaemon {
     waycast: Arc<WaycastFacade>,
@@ -22,17 +31,39 @@ pub struct WaycastDaemon {
     app_scanner: Arc<ApplicationScanner>,
     project_scanner: Arc<ProjectScanner>,
     file_scanner: Arc<FileScanner>,
+    executable_scanner: Arc<ExecutableScanner>,
+    browser_scanner: Arc<BrowserScanner>,
+    ssh_scanner: Arc<SshScanner>,
+    recent_file_scanner: Arc<RecentFileScanner>,
 }
 
 impl WaycastDaemon {
     pub fn new(waycast: Arc<WaycastFacade>, rt: tokio::runtime::Runtime) -> Self {
         let scan_paths = &waycast.config().scan_paths;
-        let app_scanner = Arc::new(ApplicationScanner);
+        let app_scanner = Arc::new(ApplicationScanner::new(
+            scan_paths.apps.clone(),
+            AppImageExtractor::new(
+                scan_paths.appimages.clone(),
+                scan_paths.appimage_data.clone(),
+            ),
+            waycast.config().apps.duplicates,
+        ));
         let project_scanner = Arc::new(ProjectScanner::new(scan_paths.projects.clone()));
         let file_scanner = Arc::new(FileScanner::new(
             scan_paths.files.clone(),
             scan_paths.ignore_dirs.clone(),
         ));
+        let executable_scanner = Arc::new(if waycast.config().run.index_path {
+            ExecutableScanner::from_env()
+        } else {
+            ExecutableScanner::new(Vec::new())
+        });
+        let browser_scanner = Arc::new(BrowserScanner::new(
+            waycast.config().browsers,
+            waycast.config().app_dir.cache.clone(),
+        ));
+        let ssh_scanner = Arc::new(SshScanner::new(waycast.config().ssh));
+        let recent_file_scanner = Arc::new(RecentFileScanner::new(waycast.config().recent_files));
 
         Self {
             waycast,
@@ -40,6 +71,10 @@ impl WaycastDaemon {
             app_scanner,
             project_scanner,
             file_scanner,
+            executable_scanner,
+            browser_scanner,
+            ssh_scanner,
+            recent_file_scanner,
         }
     }
 }
@@ -50,11 +85,26 @@ impl WaycastDaemon {
         let (project_event_tx, mut project_event_rx) = mpsc::channel(MAX_MPSC_BUF_SIZE);
         let _app_watcher_handle = self.watch_app_directories(app_event_tx);
         let _project_watcher_handle = self.watch_project_directories(project_event_tx);
+        let (ssh_event_tx, mut ssh_event_rx) = mpsc::channel(MAX_MPSC_BUF_SIZE);
+        let ssh_watcher_handle = self.watch_ssh_directory(ssh_event_tx);
+        let (recent_event_tx, mut recent_event_rx) = mpsc::channel(MAX_MPSC_BUF_SIZE);
+        let recent_watcher_handle = self.watch_recent_files(recent_event_tx);
+        let (clipboard_tx, mut clipboard_rx) = mpsc::channel(CLIPBOARD_BUF_SIZE);
+        let clipboard_watcher_handle = self.watch_clipboard(clipboard_tx);
 
         self.rt.block_on(async move {
+            // These come straight from the config, so there's no reason to
+            // wait on the first full scan for them.
+            if let Err(e) = self.update_custom_commands().await {
+                error!("Error inserting custom commands: {e}");
+            }
+
             let mut cadence = time::interval(Duration::from_secs(20));
             let mut app_watcher_open = true;
             let mut project_watcher_open = true;
+            let mut ssh_watcher_open = ssh_watcher_handle.is_some();
+            let mut recent_watcher_open = recent_watcher_handle.is_some();
+            let mut clipboard_watcher_open = clipboard_watcher_handle.is_some();
 
             loop {
                 tokio::select! {
@@ -101,22 +151,65 @@ impl WaycastDaemon {
                             }
                         }
                     },
+                    ssh_event = ssh_event_rx.recv(), if ssh_watcher_open => {
+                        match ssh_event {
+                            Some(FileEvent::ChangeInDirectory) => {
+                                let scan_span = info_span!("scan_and_update_ssh_hosts");
+                                if let Err(e) = self.scan_and_update_ssh_hosts().instrument(scan_span).await {
+                                    error!("Error updating SSH hosts: {e}");
+                                }
+                            }
+                            None => {
+                                error!("SSH directory watcher stopped");
+                                ssh_watcher_open = false;
+                            }
+                        }
+                    },
+                    recent_event = recent_event_rx.recv(), if recent_watcher_open => {
+                        match recent_event {
+                            Some(FileEvent::ChangeInDirectory) => {
+                                let scan_span = info_span!("scan_and_update_recent_files");
+                                if let Err(e) = self.scan_and_update_recent_files().instrument(scan_span).await {
+                                    error!("Error updating recent files: {e}");
+                                }
+                            }
+                            None => {
+                                error!("Recent files watcher stopped");
+                                recent_watcher_open = false;
+                            }
+                        }
+                    },
+                    copied = clipboard_rx.recv(), if clipboard_watcher_open => {
+                        match copied {
+                            Some(contents) => {
+                                if let Err(e) = self.record_clipboard(&contents).await {
+                                    error!("Error recording clipboard history: {e}");
+                                }
+                            }
+                            None => {
+                                error!("Clipboard watcher stopped");
+                                clipboard_watcher_open = false;
+                            }
+                        }
+                    },
                 }
             }
         });
     }
 
+    /// Watches the AppImages themselves rather than where they're extracted
+    /// to, which the rescan writes to.
     fn watch_app_directories(
         &self,
         event_tx: mpsc::Sender<FileEvent>,
     ) -> std::thread::JoinHandle<()> {
-        let app_dirs: Vec<PathBuf> = self
-            .waycast
-            .config()
-            .scan_paths
+        let scan_paths = &self.waycast.config().scan_paths;
+        let app_dirs: Vec<PathBuf> = scan_paths
             .apps
             .iter()
-            .cloned()
+            .filter(|dir| dir.source != AppSource::AppImage)
+            .map(|dir| dir.path.clone())
+            .chain(scan_paths.appimages.iter().cloned())
             .collect();
 
         std::thread::spawn(move || {
@@ -141,19 +234,72 @@ impl WaycastDaemon {
         })
     }
 
+    /// Only runs when SSH hosts are indexed. Recursive, since included
+    /// config files tend to live in a directory like `~/.ssh/config.d`.
+    fn watch_ssh_directory(
+        &self,
+        event_tx: mpsc::Sender<FileEvent>,
+    ) -> Option<std::thread::JoinHandle<()>> {
+        let ssh_dir = self.ssh_scanner.ssh_dir()?;
+
+        Some(std::thread::spawn(move || {
+            info!("Watching for changes to SSH hosts");
+            watch_directories(vec![ssh_dir], event_tx, notify::RecursiveMode::Recursive);
+        }))
+    }
+
+    /// Only runs when recent files are indexed. GTK replaces the list on
+    /// every write, which a watch on the file itself wouldn't survive, so
+    /// its directory is watched for changes to just that file.
+    fn watch_recent_files(
+        &self,
+        event_tx: mpsc::Sender<FileEvent>,
+    ) -> Option<std::thread::JoinHandle<()>> {
+        let list = self.recent_file_scanner.list_path()?.to_path_buf();
+        let dir = list.parent()?.to_path_buf();
+
+        Some(std::thread::spawn(move || {
+            info!("Watching for changes to recent files");
+            watch_files(dir, vec![list], event_tx);
+        }))
+    }
+
+    /// Only runs when clipboard history is enabled.
+    fn watch_clipboard(&self, tx: mpsc::Sender<Contents>) -> Option<std::thread::JoinHandle<()>> {
+        let cfg = self.waycast.config().clipboard.history.clone();
+        if !cfg.enabled {
+            return None;
+        }
+
+        Some(std::thread::spawn(move || {
+            if let Err(e) = clipboard::watch(&cfg, tx) {
+                error!("Could not watch the clipboard: {e}");
+            }
+        }))
+    }
+
+    async fn record_clipboard(&self, contents: &Contents) -> Result<(), DataError> {
+        let cfg = &self.waycast.config().clipboard.history;
+        self.waycast.db().clipboard().record(contents, cfg).await
+    }
+
     async fn scan_and_update_apps(&self) -> Result<(), DataError> {
         info!("Application directory changed; rescanning application entries");
 
         let scanner = Arc::clone(&self.app_scanner);
-        let app_entries = tokio::task::spawn_blocking(move || scanner.scan())
-            .await
-            .map_err(|e| DataError::QueryError(format!("Application scanner task failed: {e}")))?;
+        let (app_entries, app_details) =
+            tokio::task::spawn_blocking(move || scanner.scan_with_details())
+                .await
+                .map_err(|e| {
+                    DataError::QueryError(format!("Application scanner task failed: {e}"))
+                })?;
 
         self.waycast
             .db()
             .items()
-            .insert_of_kind(app_entries, crate::core::ItemKind::DesktopEntry)
+            .insert_of_kind(app_entries, ItemKind::DesktopEntry)
             .await?;
+        self.waycast.db().apps().replace(app_details).await?;
 
         info!("Application entry rescan successful; updating icon cache");
         self.update_icon_cache().await?;
@@ -172,7 +318,7 @@ impl WaycastDaemon {
         self.waycast
             .db()
             .items()
-            .insert_of_kind(project_entries, crate::core::ItemKind::Project)
+            .insert_of_kind(project_entries, ItemKind::Project)
             .await?;
 
         info!("Projects entry rescan successful");
@@ -180,6 +326,56 @@ impl WaycastDaemon {
         Ok(())
     }
 
+    async fn scan_and_update_ssh_hosts(&self) -> Result<(), DataError> {
+        info!("SSH directory changed; rescanning SSH hosts");
+
+        let scanner = Arc::clone(&self.ssh_scanner);
+        let hosts = tokio::task::spawn_blocking(move || scanner.scan())
+            .await
+            .map_err(|e| DataError::QueryError(format!("SSH scanner task failed: {e}")))?;
+
+        self.waycast
+            .db()
+            .items()
+            .insert_of_kind(hosts, ItemKind::SshHost)
+            .await?;
+
+        info!("SSH host rescan successful");
+
+        Ok(())
+    }
+
+    async fn scan_and_update_recent_files(&self) -> Result<(), DataError> {
+        info!("Recent files list changed; rescanning recent files");
+
+        let scanner = Arc::clone(&self.recent_file_scanner);
+        let files = tokio::task::spawn_blocking(move || scanner.scan())
+            .await
+            .map_err(|e| DataError::QueryError(format!("Recent files scanner task failed: {e}")))?;
+
+        self.waycast
+            .db()
+            .items()
+            .insert_of_kind(files, ItemKind::RecentFile)
+            .await?;
+
+        info!("Recent files rescan successful; updating icon cache");
+        self.update_icon_cache().await?;
+
+        Ok(())
+    }
+
+    async fn update_custom_commands(&self) -> Result<(), DataError> {
+        let commands = custom::items(&self.waycast.config().commands);
+        info!("Inserting {} custom commands", commands.len());
+
+        self.waycast
+            .db()
+            .items()
+            .insert_of_kind(commands, ItemKind::Custom)
+            .await
+    }
+
     async fn scan_and_update(&self) -> Result<(), DataError> {
         info!("Gathering data");
         let start = Instant::now();
@@ -187,30 +383,68 @@ impl WaycastDaemon {
         let s_app = self.app_scanner.clone();
         let s_projects = self.project_scanner.clone();
         let s_files = self.file_scanner.clone();
-        let (de, f, p) = tokio::join!(
-            tokio::task::spawn_blocking(move || s_app.scan()),
+        let s_executables = self.executable_scanner.clone();
+        let s_browsers = self.browser_scanner.clone();
+        let s_ssh = self.ssh_scanner.clone();
+        let s_recent = self.recent_file_scanner.clone();
+        let s_plugins = Arc::clone(self.waycast.plugins());
+        let (de, f, p, e, b, s, r, pl) = tokio::join!(
+            tokio::task::spawn_blocking(move || s_app.scan_with_details()),
             tokio::task::spawn_blocking(move || s_files.scan()),
             tokio::task::spawn_blocking(move || s_projects.scan()),
+            tokio::task::spawn_blocking(move || s_executables.scan()),
+            tokio::task::spawn_blocking(move || s_browsers.scan()),
+            tokio::task::spawn_blocking(move || s_ssh.scan()),
+            tokio::task::spawn_blocking(move || s_recent.scan()),
+            tokio::task::spawn_blocking(move || s_plugins.list()),
         );
 
-        let desktop_entries = de.unwrap_or(Vec::new());
+        let (desktop_entries, app_details) = de.unwrap_or_default();
         let files = f.unwrap_or(Vec::new());
         let projects = p.unwrap_or(Vec::new());
+        let executables = e.unwrap_or(Vec::new());
+        let bookmarks = b.unwrap_or(Vec::new());
+        let ssh_hosts = s.unwrap_or(Vec::new());
+        let recent_files = r.unwrap_or(Vec::new());
+        let plugin_items = pl.unwrap_or(Vec::new());
+        // Everything not in the list is removed, so these have to be in it.
+        let custom_commands = custom::items(&self.waycast.config().commands);
         let elapsed = start.elapsed();
         info!("Scan all took {:?}", elapsed);
         info!(
-            "{} DE | {} Files | {} Projects",
+            "{} DE | {} Files | {} Projects | {} Executables | {} Bookmarks | {} SSH hosts | {} Recent files | {} Custom | {} Plugin items",
             desktop_entries.len(),
             files.len(),
-            projects.len()
+            projects.len(),
+            executables.len(),
+            bookmarks.len(),
+            ssh_hosts.len(),
+            recent_files.len(),
+            custom_commands.len(),
+            plugin_items.len()
         );
 
-        let mut items: Vec<LauncherItem> =
-            Vec::with_capacity(desktop_entries.len() + files.len() + projects.len());
+        let mut items: Vec<LauncherItem> = Vec::with_capacity(
+            desktop_entries.len()
+                + files.len()
+                + projects.len()
+                + executables.len()
+                + bookmarks.len()
+                + ssh_hosts.len()
+                + recent_files.len()
+                + custom_commands.len()
+                + plugin_items.len(),
+        );
 
         items.extend(desktop_entries);
         items.extend(files);
         items.extend(projects);
+        items.extend(executables);
+        items.extend(bookmarks);
+        items.extend(ssh_hosts);
+        items.extend(recent_files);
+        items.extend(custom_commands);
+        items.extend(plugin_items);
 
         info!("Inserting {} items", items.len());
         let insert_span = info_span!("inserting");
@@ -220,6 +454,7 @@ impl WaycastDaemon {
             .insert(items)
             .instrument(insert_span)
             .await?;
+        self.waycast.db().apps().replace(app_details).await?;
 
         Ok(())
     }
@@ -0,0 +1,380 @@
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io;
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime};
+
+use tracing::{error, info};
+
+use crate::core::apps;
+
+/// How long an AppImage gets to extract a file before it's killed.
+const EXTRACT_TIMEOUT: Duration = Duration::from_secs(10);
+/// Starts the name of every entry extracted from an AppImage, so the ones
+/// whose AppImage is gone can be cleaned up.
+const ENTRY_PREFIX: &str = "appimage-";
+
+/// Pulls the desktop entry and icon out of each executable AppImage in
+/// `dirs`, so they're scanned like any other app. They end up in `data`,
+/// under `applications` and `icons`.
+pub struct AppImageExtractor {
+    dirs: HashSet<PathBuf>,
+    data: PathBuf,
+    /// AppImages that failed to extract, and when they were last changed.
+    /// Extracting runs them, so one whose runtime ignores
+    /// `--appimage-extract` would otherwise start up on every scan.
+    failed: Mutex<HashMap<PathBuf, SystemTime>>,
+}
+
+impl AppImageExtractor {
+    pub fn new(dirs: HashSet<PathBuf>, data: PathBuf) -> Self {
+        Self {
+            dirs,
+            data,
+            failed: Mutex::default(),
+        }
+    }
+
+    /// Extract the AppImages that are new or changed since they were last
+    /// extracted, and remove what was extracted from ones that are gone.
+    pub fn extract_all(&self) {
+        if self.dirs.is_empty() {
+            return;
+        }
+
+        let applications = self.data.join("applications");
+        let icons = self.data.join("icons");
+        if let Err(e) = fs::create_dir_all(&applications).and_then(|()| fs::create_dir_all(&icons))
+        {
+            error!("Could not create {}: {e}", self.data.display());
+            return;
+        }
+
+        let mut kept = HashSet::new();
+        let mut failed = self.failed.lock().expect("failed AppImages lock poisoned");
+        let appimages: Vec<PathBuf> = self.dirs.iter().flat_map(|dir| appimages_in(dir)).collect();
+        failed.retain(|appimage, _| appimages.contains(appimage));
+
+        for appimage in appimages {
+            let name = entry_name(&appimage);
+            let entry = applications.join(format!("{name}.desktop"));
+            kept.insert(name.clone());
+
+            let changed = modified(&appimage).ok();
+            if is_up_to_date(&entry, &appimage)
+                || changed.is_some_and(|changed| failed.get(&appimage) == Some(&changed))
+            {
+                continue;
+            }
+
+            info!("Extracting {}", appimage.display());
+            match self.extract(&appimage, &name, &entry) {
+                Ok(()) => {
+                    failed.remove(&appimage);
+                }
+                Err(e) => {
+                    error!(
+                        "Could not extract {}, not trying again until it changes: {e}",
+                        appimage.display()
+                    );
+                    if let Some(changed) = changed {
+                        failed.insert(appimage, changed);
+                    }
+                }
+            }
+        }
+
+        remove_stale(&applications, &kept);
+        remove_stale(&icons, &kept);
+    }
+
+    fn extract(&self, appimage: &Path, name: &str, entry: &Path) -> io::Result<()> {
+        let work = self.data.join("extract").join(name);
+        let _ = fs::remove_dir_all(&work);
+        fs::create_dir_all(&work)?;
+
+        let result = self.extract_into(appimage, name, entry, &work);
+        let _ = fs::remove_dir_all(&work);
+        result
+    }
+
+    fn extract_into(
+        &self,
+        appimage: &Path,
+        name: &str,
+        entry: &Path,
+        work: &Path,
+    ) -> io::Result<()> {
+        let root = work.join("squashfs-root");
+        run_extract(appimage, "*.desktop", work)?;
+
+        // AppImages keep their desktop entry, and the icon it names, at the
+        // top of the image.
+        let desktop = fs::read_dir(&root)?
+            .flatten()
+            .map(|file| file.path())
+            .find(|path| path.extension().is_some_and(|ext| ext == "desktop"))
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "it has no desktop entry"))?;
+        let contents = fs::read_to_string(desktop)?;
+        let icon = self.extract_icon(appimage, name, &contents, work);
+
+        fs::write(
+            entry,
+            apps::appimage_entry(&contents, appimage, icon.as_deref()),
+        )
+    }
+
+    fn extract_icon(
+        &self,
+        appimage: &Path,
+        name: &str,
+        contents: &str,
+        work: &Path,
+    ) -> Option<PathBuf> {
+        let icon = contents
+            .lines()
+            .find_map(|line| line.trim_start().strip_prefix("Icon="))?
+            .trim();
+        if icon.is_empty() || icon.contains('/') {
+            return None;
+        }
+
+        for ext in ["svg", "png"] {
+            let _ = fs::remove_file(self.data.join("icons").join(format!("{name}.{ext}")));
+        }
+
+        ["svg", "png"].into_iter().find_map(|ext| {
+            let file = format!("{icon}.{ext}");
+            run_extract(appimage, &file, work).ok()?;
+
+            let extracted = work.join("squashfs-root").join(&file);
+            let dest = self.data.join("icons").join(format!("{name}.{ext}"));
+            fs::copy(extracted, &dest).ok().map(|_| dest)
+        })
+    }
+}
+
+/// Executable AppImages directly in `dir`. One that isn't executable yet
+/// hasn't been set up to run, so it's left alone.
+fn appimages_in(dir: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+
+    entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.extension()
+                .is_some_and(|ext| ext.eq_ignore_ascii_case("appimage"))
+        })
+        .filter(|path| {
+            fs::metadata(path).is_ok_and(|metadata| {
+                metadata.is_file() && metadata.permissions().mode() & 0o111 != 0
+            })
+        })
+        .collect()
+}
+
+/// What the AppImage's extracted files are named, and so its desktop entry
+/// id, like `appimage-krita-5-2-6-x86-64` for `krita-5.2.6-x86_64.AppImage`.
+fn entry_name(appimage: &Path) -> String {
+    let stem = appimage
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    let slug: Vec<&str> = stem
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|part| !part.is_empty())
+        .collect();
+
+    format!("{ENTRY_PREFIX}{}", slug.join("-"))
+}
+
+fn modified(path: &Path) -> io::Result<SystemTime> {
+    fs::metadata(path).and_then(|metadata| metadata.modified())
+}
+
+fn is_up_to_date(entry: &Path, appimage: &Path) -> bool {
+    match (modified(entry), modified(appimage)) {
+        (Ok(extracted), Ok(changed)) => extracted >= changed,
+        _ => false,
+    }
+}
+
+/// Have the AppImage extract the files matching `pattern` into
+/// `work/squashfs-root`. Extracting means running it, so one that hangs is
+/// killed rather than holding up the scan.
+fn run_extract(appimage: &Path, pattern: &str, work: &Path) -> io::Result<()> {
+    let mut child = Command::new(appimage)
+        .arg("--appimage-extract")
+        .arg(pattern)
+        .current_dir(work)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()?;
+
+    let started = Instant::now();
+    loop {
+        if let Some(status) = child.try_wait()? {
+            return if status.success() {
+                Ok(())
+            } else {
+                Err(io::Error::other(format!("extracting exited with {status}")))
+            };
+        }
+
+        if started.elapsed() > EXTRACT_TIMEOUT {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err(io::Error::new(
+                io::ErrorKind::TimedOut,
+                "it took too long to extract",
+            ));
+        }
+
+        std::thread::sleep(Duration::from_millis(50));
+    }
+}
+
+/// Remove what was extracted from AppImages that aren't in `kept` anymore.
+fn remove_stale(dir: &Path, kept: &HashSet<String>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+
+    for path in entries.flatten().map(|entry| entry.path()) {
+        let Some(stem) = path.file_stem().and_then(|stem| stem.to_str()) else {
+            continue;
+        };
+
+        if stem.starts_with(ENTRY_PREFIX) && !kept.contains(stem) {
+            info!("Removing {}", path.display());
+            let _ = fs::remove_file(&path);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::File;
+    use std::time::SystemTime;
+
+    fn touch(path: &Path, modified: SystemTime) {
+        File::create(path).unwrap().set_modified(modified).unwrap();
+    }
+
+    #[test]
+    fn names_entries_after_the_appimage() {
+        assert_eq!(
+            entry_name(Path::new("/apps/krita-5.2.6-x86_64.AppImage")),
+            "appimage-krita-5-2-6-x86-64"
+        );
+        assert_eq!(
+            entry_name(Path::new("/apps/Some  App__v2.appimage")),
+            "appimage-some-app-v2"
+        );
+    }
+
+    #[test]
+    fn extracts_again_only_once_the_appimage_changes() {
+        let dir = tempfile::tempdir().unwrap();
+        let appimage = dir.path().join("tool.AppImage");
+        let entry = dir.path().join("appimage-tool.desktop");
+        let earlier = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let later = earlier + Duration::from_secs(60);
+
+        touch(&appimage, earlier);
+        assert!(!is_up_to_date(&entry, &appimage), "never extracted");
+        touch(&entry, later);
+        assert!(is_up_to_date(&entry, &appimage));
+        touch(&appimage, later + Duration::from_secs(60));
+        assert!(!is_up_to_date(&entry, &appimage), "replaced since");
+    }
+
+    #[test]
+    fn removes_only_what_came_from_appimages_that_are_gone() {
+        let dir = tempfile::tempdir().unwrap();
+        for name in [
+            "appimage-kept.desktop",
+            "appimage-gone.desktop",
+            "appimage-gone.png",
+            "firefox.desktop",
+        ] {
+            fs::write(dir.path().join(name), "").unwrap();
+        }
+
+        remove_stale(dir.path(), &HashSet::from([String::from("appimage-kept")]));
+
+        let mut left: Vec<String> = fs::read_dir(dir.path())
+            .unwrap()
+            .flatten()
+            .map(|entry| entry.file_name().to_string_lossy().into_owned())
+            .collect();
+        left.sort();
+        assert_eq!(left, ["appimage-kept.desktop", "firefox.desktop"]);
+    }
+
+    #[test]
+    fn runs_an_appimage_that_fails_to_extract_again_only_once_it_changes() {
+        let dir = tempfile::tempdir().unwrap();
+        let apps = dir.path().join("apps");
+        fs::create_dir(&apps).unwrap();
+        let runs = dir.path().join("runs");
+        let appimage = apps.join("broken.AppImage");
+        fs::write(
+            &appimage,
+            format!("#!/bin/sh\necho run >> '{}'\nexit 1\n", runs.display()),
+        )
+        .unwrap();
+        fs::set_permissions(&appimage, fs::Permissions::from_mode(0o755)).unwrap();
+        let extractor =
+            AppImageExtractor::new(HashSet::from([apps.clone()]), dir.path().join("data"));
+        let run_count = || {
+            fs::read_to_string(&runs)
+                .unwrap_or_default()
+                .lines()
+                .count()
+        };
+
+        extractor.extract_all();
+        extractor.extract_all();
+        assert_eq!(run_count(), 1);
+
+        let later = modified(&appimage).unwrap() + Duration::from_secs(60);
+        File::options()
+            .write(true)
+            .open(&appimage)
+            .unwrap()
+            .set_modified(later)
+            .unwrap();
+        extractor.extract_all();
+        assert_eq!(run_count(), 2);
+    }
+
+    #[test]
+    fn only_finds_appimages_that_can_be_run() {
+        let dir = tempfile::tempdir().unwrap();
+        for (name, mode) in [
+            ("ready.AppImage", 0o755),
+            ("downloaded.AppImage", 0o644),
+            ("script.sh", 0o755),
+        ] {
+            let path = dir.path().join(name);
+            fs::write(&path, "").unwrap();
+            fs::set_permissions(&path, fs::Permissions::from_mode(mode)).unwrap();
+        }
+        fs::create_dir(dir.path().join("folder.AppImage")).unwrap();
+
+        assert_eq!(
+            appimages_in(dir.path()),
+            [dir.path().join("ready.AppImage")]
+        );
+        assert!(appimages_in(&dir.path().join("missing")).is_empty());
+    }
+}
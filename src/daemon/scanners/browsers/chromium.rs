@@ -0,0 +1,201 @@
+//! Chromium and the browsers built on it: bookmarks in a JSON `Bookmarks`
+//! file, history in the `History` database and icons in `Favicons`.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+
+use super::{BrowserError, Page, ProfileData, open_copy, read_favicons, with_suffix};
+
+/// Where each browser keeps its profiles, relative to home. Profiles are
+/// the `Default` and `Profile N` directories in these.
+const ROOTS: &[&str] = &[
+    ".config/chromium",
+    ".config/google-chrome",
+    ".config/google-chrome-beta",
+    ".config/BraveSoftware/Brave-Browser",
+    ".config/microsoft-edge",
+    ".config/vivaldi",
+    ".var/app/org.chromium.Chromium/config/chromium",
+    ".var/app/com.google.Chrome/config/google-chrome",
+    ".var/app/com.brave.Browser/config/BraveSoftware/Brave-Browser",
+];
+
+const HISTORY: &str = r#"
+    select url, title
+    from urls
+    where hidden = 0
+    order by visit_count desc, last_visit_time desc
+    limit ?1
+"#;
+
+const ICONS: &str = r#"
+    select m.page_url, b.id, b.width
+    from icon_mapping m
+    join favicon_bitmaps b on b.icon_id = m.icon_id
+"#;
+
+const ICON_DATA: &str = "select image_data from favicon_bitmaps where id = ?1";
+
+pub(super) fn profiles(home: &Path) -> Vec<PathBuf> {
+    ROOTS
+        .iter()
+        .filter_map(|root| fs::read_dir(home.join(root)).ok())
+        .flat_map(|entries| entries.flatten())
+        .filter(|entry| {
+            let name = entry.file_name();
+            let name = name.to_string_lossy();
+            name == "Default" || name.starts_with("Profile ")
+        })
+        .map(|entry| entry.path())
+        .filter(|path| path.join("Bookmarks").is_file() || path.join("History").is_file())
+        .collect()
+}
+
+pub(super) fn sources(profile: &Path) -> Vec<PathBuf> {
+    let history = profile.join("History");
+    let favicons = profile.join("Favicons");
+
+    vec![
+        profile.join("Bookmarks"),
+        with_suffix(&history, "-wal"),
+        history,
+        with_suffix(&favicons, "-wal"),
+        favicons,
+    ]
+}
+
+pub(super) async fn read(
+    profile: &Path,
+    scratch: &Path,
+    history_limit: u32,
+) -> Result<ProfileData, BrowserError> {
+    // Bookmarks are written by replacing the file, so there's nothing to
+    // copy around a lock for.
+    let bookmarks = match fs::read(profile.join("Bookmarks")) {
+        Ok(contents) => {
+            let file: BookmarkFile = serde_json::from_slice(&contents)?;
+            let mut bookmarks = Vec::new();
+            for root in file.roots.into_values() {
+                root.collect(&mut bookmarks);
+            }
+            bookmarks
+        }
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+        Err(e) => return Err(e.into()),
+    };
+
+    let history_file = profile.join("History");
+    let history = if history_limit > 0 && history_file.is_file() {
+        let mut conn = open_copy(&history_file, scratch).await?;
+        let rows: Vec<(String, Option<String>)> = sqlx::query_as(HISTORY)
+            .bind(history_limit)
+            .fetch_all(&mut conn)
+            .await?;
+        rows.into_iter()
+            .map(|(url, title)| Page { url, title })
+            .collect()
+    } else {
+        Vec::new()
+    };
+
+    let favicons_file = profile.join("Favicons");
+    let favicons = if favicons_file.is_file() {
+        let mut icons = open_copy(&favicons_file, scratch).await?;
+        let pages: Vec<&Page> = bookmarks.iter().chain(&history).collect();
+        read_favicons(&mut icons, ICONS, ICON_DATA, &pages).await?
+    } else {
+        Default::default()
+    };
+
+    Ok(ProfileData {
+        bookmarks,
+        history,
+        favicons,
+    })
+}
+
+#[derive(Deserialize)]
+struct BookmarkFile {
+    roots: HashMap<String, BookmarkNode>,
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+enum BookmarkNode {
+    Url {
+        name: String,
+        url: String,
+    },
+    Folder {
+        #[serde(default)]
+        children: Vec<BookmarkNode>,
+    },
+}
+
+impl BookmarkNode {
+    fn collect(self, bookmarks: &mut Vec<Page>) {
+        match self {
+            Self::Url { name, url } => bookmarks.push(Page {
+                url,
+                title: Some(name),
+            }),
+            Self::Folder { children } => {
+                for child in children {
+                    child.collect(bookmarks);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BOOKMARKS: &str = r#"{
+        "checksum": "0123",
+        "roots": {
+            "bookmark_bar": {
+                "type": "folder",
+                "name": "Bookmarks bar",
+                "children": [
+                    { "type": "url", "name": "Docs", "url": "https://docs.rs/" },
+                    {
+                        "type": "folder",
+                        "name": "Work",
+                        "children": [
+                            { "type": "url", "name": "Tracker", "url": "https://tracker.example/" },
+                            { "type": "folder", "name": "Empty" }
+                        ]
+                    }
+                ]
+            },
+            "other": { "type": "folder", "name": "Other", "children": [] }
+        },
+        "version": 1
+    }"#;
+
+    #[test]
+    fn collects_bookmarks_from_nested_folders() {
+        let file: BookmarkFile = serde_json::from_str(BOOKMARKS).unwrap();
+        let mut bookmarks = Vec::new();
+        for root in file.roots.into_values() {
+            root.collect(&mut bookmarks);
+        }
+
+        let found: Vec<(&str, Option<&str>)> = bookmarks
+            .iter()
+            .map(|page| (page.url.as_str(), page.title.as_deref()))
+            .collect();
+        assert_eq!(
+            found,
+            [
+                ("https://docs.rs/", Some("Docs")),
+                ("https://tracker.example/", Some("Tracker")),
+            ]
+        );
+    }
+}
@@ -0,0 +1,177 @@
+//! Firefox and its forks: bookmarks and history in `places.sqlite`, icons
+//! in `favicons.sqlite`.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::{BrowserError, Page, ProfileData, open_copy, read_favicons, with_suffix};
+
+/// Where profiles live, relative to home. Each profile is a directory in
+/// one of these.
+const ROOTS: &[&str] = &[
+    ".mozilla/firefox",
+    ".librewolf",
+    ".var/app/org.mozilla.firefox/.mozilla/firefox",
+    ".var/app/io.gitlab.librewolf-community/.librewolf",
+];
+
+const BOOKMARKS: &str = r#"
+    select p.url, coalesce(nullif(b.title, ''), p.title)
+    from moz_bookmarks b
+    join moz_places p on p.id = b.fk
+    where b.type = 1
+"#;
+
+const HISTORY: &str = r#"
+    select url, title
+    from moz_places
+    where hidden = 0 and visit_count > 0
+    order by frecency desc
+    limit ?1
+"#;
+
+/// Icons are mapped to the pages that use them, except for a site's root
+/// `/favicon.ico`, which applies to every page on the host.
+const ICONS: &str = r#"
+    select pw.page_url, i.id, i.width
+    from moz_pages_w_icons pw
+    join moz_icons_to_pages ip on ip.page_id = pw.id
+    join moz_icons i on i.id = ip.icon_id
+    union all
+    select icon_url, id, width
+    from moz_icons
+    where root = 1
+"#;
+
+const ICON_DATA: &str = "select data from moz_icons where id = ?1";
+
+pub(super) fn profiles(home: &Path) -> Vec<PathBuf> {
+    ROOTS
+        .iter()
+        .filter_map(|root| fs::read_dir(home.join(root)).ok())
+        .flat_map(|entries| entries.flatten())
+        .map(|entry| entry.path())
+        .filter(|path| path.join("places.sqlite").is_file())
+        .collect()
+}
+
+pub(super) fn sources(profile: &Path) -> Vec<PathBuf> {
+    ["places.sqlite", "favicons.sqlite"]
+        .into_iter()
+        .flat_map(|name| {
+            let path = profile.join(name);
+            [with_suffix(&path, "-wal"), path]
+        })
+        .collect()
+}
+
+pub(super) async fn read(
+    profile: &Path,
+    scratch: &Path,
+    history_limit: u32,
+) -> Result<ProfileData, BrowserError> {
+    let mut places = open_copy(&profile.join("places.sqlite"), scratch).await?;
+
+    let bookmarks = pages(sqlx::query_as(BOOKMARKS).fetch_all(&mut places).await?);
+    let history = if history_limit > 0 {
+        pages(
+            sqlx::query_as(HISTORY)
+                .bind(history_limit)
+                .fetch_all(&mut places)
+                .await?,
+        )
+    } else {
+        Vec::new()
+    };
+
+    // Older profiles and some forks have no separate favicon database.
+    let favicons_file = profile.join("favicons.sqlite");
+    let favicons = if favicons_file.is_file() {
+        let mut icons = open_copy(&favicons_file, scratch).await?;
+        let pages: Vec<&Page> = bookmarks.iter().chain(&history).collect();
+        read_favicons(&mut icons, ICONS, ICON_DATA, &pages).await?
+    } else {
+        Default::default()
+    };
+
+    Ok(ProfileData {
+        bookmarks,
+        history,
+        favicons,
+    })
+}
+
+fn pages(rows: Vec<(String, Option<String>)>) -> Vec<Page> {
+    rows.into_iter()
+        .map(|(url, title)| Page { url, title })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlx::sqlite::SqliteConnectOptions;
+    use sqlx::{Connection, SqliteConnection};
+
+    /// The parts of the places schema the queries use.
+    const PLACES: &str = r#"
+        create table moz_places (
+            id integer primary key,
+            url text,
+            title text,
+            hidden integer default 0,
+            visit_count integer default 0,
+            frecency integer default -1
+        );
+        create table moz_bookmarks (
+            id integer primary key,
+            type integer,
+            fk integer,
+            title text
+        );
+        insert into moz_places (id, url, title, hidden, visit_count, frecency) values
+            (1, 'https://docs.rs/', 'Docs.rs', 0, 3, 300),
+            (2, 'https://news.example/', 'News', 0, 40, 900),
+            (3, 'https://example.com/embed', 'Embedded', 1, 12, 800),
+            (4, 'https://never.visited/', 'Never', 0, 0, 100),
+            (5, 'https://blog.example/', 'Blog', 0, 2, 200);
+        insert into moz_bookmarks (id, type, fk, title) values
+            (1, 2, null, 'Toolbar'),
+            (2, 1, 1, ''),
+            (3, 1, 4, 'Read later');
+    "#;
+
+    #[tokio::test]
+    async fn reads_bookmarks_and_history_from_places() {
+        let profile = tempfile::tempdir().unwrap();
+        let scratch = tempfile::tempdir().unwrap();
+        let options = SqliteConnectOptions::new()
+            .filename(profile.path().join("places.sqlite"))
+            .create_if_missing(true);
+        let mut conn = SqliteConnection::connect_with(&options).await.unwrap();
+        sqlx::raw_sql(PLACES).execute(&mut conn).await.unwrap();
+        conn.close().await.unwrap();
+
+        let data = read(profile.path(), scratch.path(), 2).await.unwrap();
+
+        let mut bookmarks: Vec<(&str, Option<&str>)> = data
+            .bookmarks
+            .iter()
+            .map(|page| (page.url.as_str(), page.title.as_deref()))
+            .collect();
+        bookmarks.sort();
+        // An untitled bookmark has the page's title, and folders aren't pages.
+        assert_eq!(
+            bookmarks,
+            [
+                ("https://docs.rs/", Some("Docs.rs")),
+                ("https://never.visited/", Some("Read later")),
+            ]
+        );
+
+        // Visited and not hidden, by frecency, up to the limit.
+        let history: Vec<&str> = data.history.iter().map(|page| page.url.as_str()).collect();
+        assert_eq!(history, ["https://news.example/", "https://docs.rs/"]);
+        assert!(data.favicons.is_empty());
+    }
+}
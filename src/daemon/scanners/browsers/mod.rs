@@ -0,0 +1,486 @@
+//! Bookmarks and history out of the browsers' own profile databases.
+//!
+//! Browsers keep those databases open and locked while they run, so every
+//! file is copied into a scratch directory and read from the copy.
+
+mod chromium;
+mod firefox;
+
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+use sqlx::sqlite::SqliteConnectOptions;
+use sqlx::{Connection, SqliteConnection};
+use thiserror::Error;
+use tracing::{error, info, warn};
+
+use crate::core::config::BrowsersConfig;
+use crate::core::{ItemKind, LauncherItem, WaycastScanner};
+
+pub const ICON: &str = "web-browser";
+
+/// Favicon width to aim for. Rows are small, so anything bigger only costs
+/// memory in the icon cache.
+const FAVICON_SIZE: i64 = 32;
+/// Width Firefox records for SVG favicons, which are good at any size.
+const VECTOR_WIDTH: i64 = u16::MAX as i64;
+
+#[derive(Debug, Error)]
+enum BrowserError {
+    #[error(transparent)]
+    Io(#[from] io::Error),
+    #[error(transparent)]
+    Sqlite(#[from] sqlx::Error),
+    #[error("Unreadable bookmarks: {0}")]
+    Json(#[from] serde_json::Error),
+}
+
+/// A bookmarked or visited page.
+struct Page {
+    url: String,
+    title: Option<String>,
+}
+
+impl Page {
+    /// Only pages that open somewhere useful. Bookmarks can also be
+    /// bookmarklets, and the history is full of browser-internal pages.
+    fn is_openable(&self) -> bool {
+        ["http://", "https://", "file://"]
+            .iter()
+            .any(|scheme| self.url.starts_with(scheme))
+    }
+
+    fn to_item(&self, icon: Option<&String>) -> LauncherItem {
+        let title = self
+            .title
+            .as_deref()
+            .map(str::trim)
+            .filter(|title| !title.is_empty())
+            .unwrap_or(&self.url);
+
+        LauncherItem {
+            id: self.url.clone(),
+            kind: ItemKind::Bookmark,
+            title: title.to_string(),
+            description: Some(self.url.clone()),
+            icon: icon.map_or(ICON, String::as_str).to_string(),
+        }
+    }
+}
+
+/// Everything read out of one browser profile.
+#[derive(Default)]
+struct ProfileData {
+    bookmarks: Vec<Page>,
+    history: Vec<Page>,
+    /// Favicon image data by host
+    favicons: HashMap<String, Vec<u8>>,
+}
+
+#[derive(Clone, Copy)]
+enum Browser {
+    Firefox,
+    Chromium,
+}
+
+struct Profile {
+    browser: Browser,
+    path: PathBuf,
+}
+
+impl Profile {
+    /// The files read from this profile. They're what decides whether
+    /// anything changed since the last scan.
+    fn sources(&self) -> Vec<PathBuf> {
+        match self.browser {
+            Browser::Firefox => firefox::sources(&self.path),
+            Browser::Chromium => chromium::sources(&self.path),
+        }
+    }
+
+    async fn read(&self, scratch: &Path, history_limit: u32) -> Result<ProfileData, BrowserError> {
+        match self.browser {
+            Browser::Firefox => firefox::read(&self.path, scratch, history_limit).await,
+            Browser::Chromium => chromium::read(&self.path, scratch, history_limit).await,
+        }
+    }
+}
+
+/// Modification time and size of each source file, `None` for ones that
+/// don't exist.
+type Fingerprint = Vec<(PathBuf, Option<(SystemTime, u64)>)>;
+
+/// Bookmarks and history from Firefox and Chromium-based browsers, with
+/// their favicons saved to the cache directory.
+pub struct BrowserScanner {
+    cfg: BrowsersConfig,
+    home: Option<PathBuf>,
+    cache: PathBuf,
+    /// The previous scan's items and the files they were read from. Profile
+    /// databases run into the tens of megabytes, so they are only copied
+    /// again once the browser has written to them.
+    last: Mutex<Option<(Fingerprint, Vec<LauncherItem>)>>,
+}
+
+impl BrowserScanner {
+    pub fn new(cfg: BrowsersConfig, cache: PathBuf) -> Self {
+        Self {
+            cfg,
+            home: directories::UserDirs::new().map(|dirs| dirs.home_dir().to_path_buf()),
+            cache,
+            last: Mutex::new(None),
+        }
+    }
+
+    fn profiles(&self) -> Vec<Profile> {
+        let Some(home) = &self.home else {
+            return Vec::new();
+        };
+
+        let firefox = firefox::profiles(home).into_iter().map(|path| Profile {
+            browser: Browser::Firefox,
+            path,
+        });
+        let chromium = chromium::profiles(home).into_iter().map(|path| Profile {
+            browser: Browser::Chromium,
+            path,
+        });
+
+        firefox.chain(chromium).collect()
+    }
+
+    fn read_all(&self, profiles: &[Profile]) -> Vec<LauncherItem> {
+        let scratch = self.cache.join("browsers");
+        let history_limit = if self.cfg.history {
+            self.cfg.history_limit
+        } else {
+            0
+        };
+        let rt = tokio::runtime::Handle::current();
+
+        let mut bookmarks = Vec::new();
+        let mut history = Vec::new();
+        let mut favicons = HashMap::new();
+        for (i, profile) in profiles.iter().enumerate() {
+            let dir = scratch.join(i.to_string());
+            let data = fs::create_dir_all(&dir)
+                .map_err(BrowserError::from)
+                .and_then(|()| rt.block_on(profile.read(&dir, history_limit)));
+
+            match data {
+                Ok(data) => {
+                    bookmarks.extend(data.bookmarks);
+                    history.extend(data.history);
+                    favicons.extend(data.favicons);
+                }
+                Err(e) => warn!(
+                    "Could not read browser profile {}: {e}",
+                    profile.path.display()
+                ),
+            }
+        }
+
+        if let Err(e) = fs::remove_dir_all(&scratch)
+            && e.kind() != io::ErrorKind::NotFound
+        {
+            error!("Could not remove {}: {e}", scratch.display());
+        }
+
+        let icons = self.save_favicons(favicons);
+
+        // A bookmarked page is also in the history. Bookmarks come first so
+        // their titles win.
+        let mut seen = HashSet::new();
+        bookmarks
+            .into_iter()
+            .chain(history)
+            .filter(|page| page.is_openable() && seen.insert(page.url.clone()))
+            .map(|page| page.to_item(host(&page.url).and_then(|host| icons.get(host))))
+            .collect()
+    }
+
+    /// Write favicons into the cache, one per host, returning their paths.
+    /// Formats the UI can't draw are left out.
+    fn save_favicons(&self, favicons: HashMap<String, Vec<u8>>) -> HashMap<String, String> {
+        let dir = self.cache.join("favicons");
+        if let Err(e) = fs::create_dir_all(&dir) {
+            error!("Could not create {}: {e}", dir.display());
+            return HashMap::new();
+        }
+
+        let mut paths = HashMap::new();
+        for (host, data) in favicons {
+            let Some(extension) = image_extension(&data) else {
+                continue;
+            };
+            let path = dir.join(format!("{}.{extension}", host.replace(':', "_")));
+
+            match fs::write(&path, &data) {
+                Ok(()) => {
+                    paths.insert(host, path.to_string_lossy().into_owned());
+                }
+                Err(e) => error!("Could not save favicon for {host}: {e}"),
+            }
+        }
+
+        paths
+    }
+}
+
+impl WaycastScanner for BrowserScanner {
+    fn scan(&self) -> Vec<LauncherItem> {
+        if !self.cfg.enabled {
+            return Vec::new();
+        }
+
+        let profiles = self.profiles();
+        let fingerprint = fingerprint(&profiles);
+
+        let mut last = self.last.lock().expect("browser scan lock poisoned");
+        if let Some((seen, items)) = last.as_ref()
+            && *seen == fingerprint
+        {
+            return items.clone();
+        }
+
+        info!("Reading {} browser profiles", profiles.len());
+        let items = self.read_all(&profiles);
+        *last = Some((fingerprint, items.clone()));
+
+        items
+    }
+}
+
+fn fingerprint(profiles: &[Profile]) -> Fingerprint {
+    profiles
+        .iter()
+        .flat_map(Profile::sources)
+        .map(|path| {
+            let stamp = fs::metadata(&path)
+                .and_then(|metadata| Ok((metadata.modified()?, metadata.len())))
+                .ok();
+            (path, stamp)
+        })
+        .collect()
+}
+
+/// `path` with `suffix` tacked onto the file name, like SQLite names its
+/// write-ahead log.
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(suffix);
+    PathBuf::from(name)
+}
+
+/// Copy the database at `path` into `scratch` and open the copy. The
+/// write-ahead log comes along when there is one, since that's where the
+/// browser's latest writes are until it checkpoints.
+async fn open_copy(path: &Path, scratch: &Path) -> Result<SqliteConnection, BrowserError> {
+    let copy = scratch.join(path.file_name().expect("database file name"));
+    fs::copy(path, &copy)?;
+
+    match fs::copy(with_suffix(path, "-wal"), with_suffix(&copy, "-wal")) {
+        Ok(_) => {}
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+        Err(e) => return Err(e.into()),
+    }
+
+    Ok(SqliteConnection::connect_with(&SqliteConnectOptions::new().filename(&copy)).await?)
+}
+
+/// Pick a favicon for each host in `pages` and load it. `icons` lists
+/// `(page url, icon id, width)` for every icon the database has, `data`
+/// selects one icon's image by id.
+async fn read_favicons(
+    conn: &mut SqliteConnection,
+    icons: &'static str,
+    data: &'static str,
+    pages: &[&Page],
+) -> Result<HashMap<String, Vec<u8>>, BrowserError> {
+    let hosts: HashSet<&str> = pages.iter().filter_map(|page| host(&page.url)).collect();
+    let rows: Vec<(String, i64, i64)> = sqlx::query_as(icons).fetch_all(&mut *conn).await?;
+
+    // The closest to FAVICON_SIZE, preferring bigger ones on a tie.
+    let distance = |width: i64| {
+        let width = if width == VECTOR_WIDTH {
+            FAVICON_SIZE
+        } else {
+            width
+        };
+        ((width - FAVICON_SIZE).abs(), -width)
+    };
+
+    let mut best: HashMap<&str, (i64, i64)> = HashMap::new();
+    for (url, id, width) in &rows {
+        let Some(host) = host(url).and_then(|host| hosts.get(host).copied()) else {
+            continue;
+        };
+        match best.get(host) {
+            Some((_, current)) if distance(*current) <= distance(*width) => {}
+            _ => {
+                best.insert(host, (*id, *width));
+            }
+        }
+    }
+
+    let mut favicons = HashMap::with_capacity(best.len());
+    for (host, (id, _)) in best {
+        let (image,): (Vec<u8>,) = sqlx::query_as(data).bind(id).fetch_one(&mut *conn).await?;
+        favicons.insert(host.to_string(), image);
+    }
+
+    Ok(favicons)
+}
+
+/// The host (and port) of `url`, which favicons are shared across.
+fn host(url: &str) -> Option<&str> {
+    let (_, rest) = url.split_once("://")?;
+    let authority = rest.split(['/', '?', '#']).next()?;
+    let host = authority.rsplit('@').next()?;
+
+    (!host.is_empty()).then_some(host)
+}
+
+fn image_extension(data: &[u8]) -> Option<&'static str> {
+    if data.starts_with(b"\x89PNG") {
+        Some("png")
+    } else if data.starts_with(b"\xFF\xD8\xFF") {
+        Some("jpg")
+    } else if String::from_utf8_lossy(&data[..data.len().min(256)]).contains("<svg") {
+        Some("svg")
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn page(url: &str) -> Page {
+        Page {
+            url: url.to_string(),
+            title: None,
+        }
+    }
+
+    #[test]
+    fn favicons_are_shared_by_host_and_port() {
+        assert_eq!(host("https://example.com"), Some("example.com"));
+        assert_eq!(
+            host("https://user@example.com:8080/a/b?q=1#top"),
+            Some("example.com:8080")
+        );
+        assert_eq!(host("http://example.com?q=/x"), Some("example.com"));
+        assert_eq!(host("file:///home/user/notes.html"), None);
+        assert_eq!(host("example.com/no-scheme"), None);
+    }
+
+    #[test]
+    fn recognizes_the_favicon_formats_the_ui_draws() {
+        assert_eq!(image_extension(b"\x89PNG\r\n\x1a\n...."), Some("png"));
+        assert_eq!(image_extension(b"\xFF\xD8\xFF\xE0...."), Some("jpg"));
+        assert_eq!(
+            image_extension(br#"<?xml version="1.0"?><svg xmlns="http://www.w3.org/2000/svg"/>"#),
+            Some("svg")
+        );
+        // An ICO header
+        assert_eq!(image_extension(b"\x00\x00\x01\x00\x01\x00"), None);
+        assert_eq!(image_extension(b""), None);
+    }
+
+    #[test]
+    fn only_pages_that_open_somewhere_useful() {
+        assert!(page("https://example.com").is_openable());
+        assert!(page("http://example.com").is_openable());
+        assert!(page("file:///home/user/notes.html").is_openable());
+        assert!(!page("javascript:alert(1)").is_openable());
+        assert!(!page("about:config").is_openable());
+        assert!(!page("place:sort=8").is_openable());
+        assert!(!page("chrome://settings").is_openable());
+    }
+
+    #[test]
+    fn untitled_pages_are_shown_by_url() {
+        let item = Page {
+            url: String::from("https://example.com"),
+            title: Some(String::from("  ")),
+        }
+        .to_item(None);
+
+        assert_eq!(item.title, "https://example.com");
+        assert_eq!(item.icon, ICON);
+    }
+
+    #[test]
+    fn fingerprint_changes_when_the_browser_writes() {
+        let dir = tempfile::tempdir().unwrap();
+        let places = dir.path().join("places.sqlite");
+        fs::write(&places, "one").unwrap();
+        let profiles = [Profile {
+            browser: Browser::Firefox,
+            path: dir.path().to_path_buf(),
+        }];
+
+        let before = fingerprint(&profiles);
+        let stamp = |fingerprint: &Fingerprint, path: &Path| {
+            fingerprint
+                .iter()
+                .find(|(source, _)| source == path)
+                .map(|(_, stamp)| *stamp)
+                .unwrap()
+        };
+        assert!(stamp(&before, &places).is_some());
+        assert!(stamp(&before, &dir.path().join("favicons.sqlite")).is_none());
+        assert_eq!(before, fingerprint(&profiles));
+
+        fs::write(&places, "one more").unwrap();
+        assert_ne!(before, fingerprint(&profiles));
+    }
+
+    #[tokio::test]
+    async fn picks_the_favicon_closest_in_size() {
+        let mut conn = SqliteConnection::connect("sqlite::memory:").await.unwrap();
+        sqlx::raw_sql(
+            "create table icons (page_url text, id integer, width integer, data blob);
+            insert into icons values
+                ('https://example.com/a', 1, 16, x'01'),
+                ('https://example.com/b', 2, 32, x'02'),
+                ('https://example.com/', 3, 64, x'03'),
+                ('https://vector.org/', 4, 64, x'04'),
+                ('https://vector.org/', 5, 65535, x'05'),
+                ('https://tie.net/', 6, 28, x'06'),
+                ('https://tie.net/', 7, 36, x'07'),
+                ('https://unvisited.io/', 8, 32, x'08');",
+        )
+        .execute(&mut conn)
+        .await
+        .unwrap();
+        let pages = [
+            page("https://example.com/a"),
+            page("https://vector.org/"),
+            page("https://tie.net/x"),
+        ];
+        let pages: Vec<&Page> = pages.iter().collect();
+
+        let favicons = read_favicons(
+            &mut conn,
+            "select page_url, id, width from icons",
+            "select data from icons where id = ?1",
+            &pages,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(favicons.len(), 3);
+        assert_eq!(favicons["example.com"], [2]);
+        // SVGs fit any size.
+        assert_eq!(favicons["vector.org"], [5]);
+        // As close either way, so the bigger one.
+        assert_eq!(favicons["tie.net"], [7]);
+    }
+}
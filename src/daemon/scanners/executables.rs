@@ -0,0 +1,132 @@
+use std::collections::HashSet;
+use std::env;
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+use std::path::PathBuf;
+
+use crate::core::{ItemKind, LauncherItem, WaycastScanner, shell};
+
+/// Executables on `$PATH`, so programs without a desktop entry can be run.
+pub struct ExecutableScanner {
+    dirs: Vec<PathBuf>,
+}
+
+impl ExecutableScanner {
+    pub fn new(dirs: Vec<PathBuf>) -> Self {
+        Self { dirs }
+    }
+
+    /// The daemon's own `$PATH`, which is what commands get run with.
+    pub fn from_env() -> Self {
+        let dirs = env::var_os("PATH")
+            .map(|path| env::split_paths(&path).collect())
+            .unwrap_or_default();
+
+        Self::new(dirs)
+    }
+}
+
+impl WaycastScanner for ExecutableScanner {
+    fn scan(&self) -> Vec<LauncherItem> {
+        let mut seen = HashSet::new();
+        let mut executables = Vec::new();
+
+        for dir in &self.dirs {
+            let Ok(entries) = fs::read_dir(dir) else {
+                continue;
+            };
+
+            for entry in entries.flatten() {
+                let name = entry.file_name().to_string_lossy().into_owned();
+                // Same as the shell: the first one on the path wins.
+                if seen.contains(&name) {
+                    continue;
+                }
+
+                // fs::metadata follows symlinks, which is how most of /usr/bin
+                // and everything in a nix profile is installed.
+                let path = entry.path();
+                let Ok(metadata) = fs::metadata(&path) else {
+                    continue;
+                };
+                if !metadata.is_file() || metadata.permissions().mode() & 0o111 == 0 {
+                    continue;
+                }
+
+                let path = path.to_string_lossy().into_owned();
+                executables.push(LauncherItem {
+                    id: path.clone(),
+                    kind: ItemKind::Executable,
+                    title: name.clone(),
+                    description: Some(path),
+                    icon: shell::EXECUTABLE_ICON.into(),
+                });
+                seen.insert(name);
+            }
+        }
+
+        executables
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    fn file(dir: &Path, name: &str, mode: u32) {
+        let path = dir.join(name);
+        fs::write(&path, "#!/bin/sh\n").unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(mode)).unwrap();
+    }
+
+    fn scan(dirs: &[&Path]) -> Vec<(String, String)> {
+        let scanner = ExecutableScanner::new(dirs.iter().map(|dir| dir.to_path_buf()).collect());
+        let mut found: Vec<_> = scanner
+            .scan()
+            .into_iter()
+            .map(|item| (item.title, item.id))
+            .collect();
+        found.sort();
+        found
+    }
+
+    #[test]
+    fn the_first_executable_on_the_path_wins() {
+        let first = tempfile::tempdir().unwrap();
+        let second = tempfile::tempdir().unwrap();
+        file(first.path(), "tool", 0o755);
+        file(second.path(), "tool", 0o755);
+        file(second.path(), "other", 0o755);
+        let path = |dir: &tempfile::TempDir, name: &str| {
+            dir.path().join(name).to_string_lossy().into_owned()
+        };
+
+        assert_eq!(
+            scan(&[first.path(), second.path()]),
+            [
+                (String::from("other"), path(&second, "other")),
+                (String::from("tool"), path(&first, "tool")),
+            ]
+        );
+    }
+
+    #[test]
+    fn skips_what_cannot_be_run() {
+        let first = tempfile::tempdir().unwrap();
+        let second = tempfile::tempdir().unwrap();
+        file(first.path(), "README", 0o644);
+        // Not executable here, so the shell would run the later one.
+        file(first.path(), "tool", 0o644);
+        file(second.path(), "tool", 0o700);
+        fs::create_dir(first.path().join("lib")).unwrap();
+        std::os::unix::fs::symlink(second.path().join("tool"), first.path().join("linked"))
+            .unwrap();
+        let missing = first.path().join("missing");
+
+        let found = scan(&[first.path(), &missing, second.path()]);
+        let titles: Vec<&str> = found.iter().map(|(title, _)| title.as_str()).collect();
+        assert_eq!(titles, ["linked", "tool"]);
+        assert_eq!(found[1].1, second.path().join("tool").to_string_lossy());
+    }
+}
@@ -1,37 +1,92 @@
+use crate::core::apps::{self, AppDetails, AppDirectory};
+use crate::core::config::DuplicateApps;
 use crate::core::{ItemKind, LauncherItem, WaycastScanner};
+use appimages::AppImageExtractor;
 use freedesktop::ApplicationEntry;
+pub mod appimages;
+pub mod browsers;
+pub mod executables;
 pub mod projects;
+pub mod recent;
+pub mod ssh;
+
+/// Desktop entries from every app directory, including the ones extracted
+/// from AppImages.
+pub struct ApplicationScanner {
+    dirs: Vec<AppDirectory>,
+    appimages: AppImageExtractor,
+    duplicates: DuplicateApps,
+}
 
-pub struct ApplicationScanner;
-
-impl Default for ApplicationScanner {
-    fn default() -> Self {
-        Self
+impl ApplicationScanner {
+    pub fn new(
+        dirs: Vec<AppDirectory>,
+        appimages: AppImageExtractor,
+        duplicates: DuplicateApps,
+    ) -> Self {
+        Self {
+            dirs,
+            appimages,
+            duplicates,
+        }
     }
-}
 
-impl WaycastScanner for ApplicationScanner {
-    fn scan(&self) -> Vec<LauncherItem> {
-        let apps = ApplicationEntry::all();
-        let mut entries = Vec::with_capacity(apps.len());
+    /// Apps along with what their desktop entries say about them besides
+    /// the name and comment.
+    pub fn scan_with_details(&self) -> (Vec<LauncherItem>, Vec<AppDetails>) {
+        self.appimages.extract_all();
+
+        let mut seen = HashSet::new();
+        let mut apps = Vec::new();
+
+        for dir in &self.dirs {
+            for app in desktop_entries(&dir.path) {
+                // The first directory with an id wins, even if it hides the
+                // app, so a user's own entry can override the system's.
+                let id = app.id().unwrap_or_default();
+                if !seen.insert(id.clone()) || !app.should_show() {
+                    continue;
+                }
+
+                let de = LauncherItem {
+                    id,
+                    kind: ItemKind::DesktopEntry,
+                    title: app.name().unwrap_or("Name not found".into()),
+                    description: app.comment().map(|d| d.to_string()),
+                    icon: app.icon().unwrap_or("application-x-executable".to_string()),
+                };
 
-        for app in apps {
-            if !app.should_show() {
-                continue;
+                let mut details = AppDetails::from_desktop_file(&de.id, &de.title, app.path())
+                    .unwrap_or_else(|| AppDetails {
+                        id: de.id.clone(),
+                        ..AppDetails::default()
+                    });
+                details.source = dir.source;
+                apps.push((de, details));
             }
+        }
 
-            let de = LauncherItem {
-                id: app.id().unwrap_or_default().to_string(),
-                kind: ItemKind::DesktopEntry,
-                title: app.name().unwrap_or("Name not found".into()),
-                description: app.comment().map(|d| d.to_string()),
-                icon: app.icon().unwrap_or("application-x-executable".to_string()),
-            };
+        let merge = self.duplicates == DuplicateApps::Merge;
+        apps::resolve_duplicates(apps, merge).into_iter().unzip()
+    }
+}
 
-            entries.push(de);
-        }
+fn desktop_entries(dir: &Path) -> Vec<ApplicationEntry> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+
+    entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "desktop"))
+        .filter_map(|path| ApplicationEntry::from_path(path).ok())
+        .collect()
+}
 
-        entries
+impl WaycastScanner for ApplicationScanner {
+    fn scan(&self) -> Vec<LauncherItem> {
+        self.scan_with_details().0
     }
 }
 
@@ -40,7 +95,7 @@ use directories::UserDirs;
 use glib::object::Cast;
 use ignore::{WalkBuilder, WalkState};
 use std::collections::HashSet;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 pub struct FileScanner {
     paths: HashSet<PathBuf>,
@@ -151,21 +206,24 @@ impl From<FileEntry> for LauncherItem {
             title: String::from(val.path.file_name().unwrap().to_string_lossy()),
             kind: crate::core::ItemKind::File,
             description: Some(val.path.to_string_lossy().to_string()),
-            icon: {
-                let (content_type, _) = gio::content_type_guess(Some(&val.path), None);
-                let icon = gio::content_type_get_icon(&content_type);
-                if let Some(themed_icon) = icon.downcast_ref::<gio::ThemedIcon>()
-                    && let Some(icon_name) = themed_icon.names().first()
-                {
-                    icon_name.to_string()
-                } else {
-                    String::from("text-x-generic")
-                }
-            },
+            icon: file_icon(&val.path),
         }
     }
 }
 
+/// Themed icon for the type of file at `path`, guessed from its name.
+fn file_icon(path: &Path) -> String {
+    let (content_type, _) = gio::content_type_guess(Some(path), None);
+    let icon = gio::content_type_get_icon(&content_type);
+    if let Some(themed_icon) = icon.downcast_ref::<gio::ThemedIcon>()
+        && let Some(icon_name) = themed_icon.names().first()
+    {
+        icon_name.to_string()
+    } else {
+        String::from("text-x-generic")
+    }
+}
+
 pub fn default_search_list() -> HashSet<PathBuf> {
     if let Some(ud) = UserDirs::new() {
         let mut paths: HashSet<PathBuf> = HashSet::new();
@@ -0,0 +1,75 @@
+use std::path::{Path, PathBuf};
+
+use tracing::error;
+
+use crate::core::config::RecentFilesConfig;
+use crate::core::recent::{self, RecentFile};
+use crate::core::{ItemKind, LauncherItem, WaycastScanner};
+
+use super::file_icon;
+
+/// Files recently opened in any app, from the GTK recent files list.
+pub struct RecentFileScanner {
+    cfg: RecentFilesConfig,
+    path: Option<PathBuf>,
+}
+
+impl RecentFileScanner {
+    pub fn new(cfg: RecentFilesConfig) -> Self {
+        Self {
+            cfg,
+            path: recent::default_path(),
+        }
+    }
+
+    /// The list, when recent files are indexed.
+    pub fn list_path(&self) -> Option<&Path> {
+        self.path.as_deref().filter(|_| self.cfg.enabled)
+    }
+}
+
+impl WaycastScanner for RecentFileScanner {
+    fn scan(&self) -> Vec<LauncherItem> {
+        let Some(path) = self.path.as_ref().filter(|_| self.cfg.enabled) else {
+            return Vec::new();
+        };
+
+        let files = match recent::read(path) {
+            Ok(files) => files,
+            Err(recent::RecentError::Io(e)) if e.kind() == std::io::ErrorKind::NotFound => {
+                return Vec::new();
+            }
+            Err(e) => {
+                error!("{e}");
+                return Vec::new();
+            }
+        };
+
+        // The list keeps files that have since been moved or deleted.
+        files
+            .iter()
+            .filter(|file| file.path.exists())
+            .take(self.cfg.max_items)
+            .map(to_item)
+            .collect()
+    }
+}
+
+fn to_item(file: &RecentFile) -> LauncherItem {
+    let path = file.path.to_string_lossy().into_owned();
+    let description = match &file.application {
+        Some(application) => format!("{application} · {path}"),
+        None => path.clone(),
+    };
+
+    LauncherItem {
+        title: file
+            .path
+            .file_name()
+            .map_or_else(|| path.clone(), |name| name.to_string_lossy().into_owned()),
+        id: path,
+        kind: ItemKind::RecentFile,
+        description: Some(description),
+        icon: file_icon(&file.path),
+    }
+}
@@ -0,0 +1,58 @@
+use std::collections::HashSet;
+use std::fs;
+use std::path::PathBuf;
+
+use crate::core::config::SshConfig;
+use crate::core::ssh::{self, HostSource};
+use crate::core::{LauncherItem, WaycastScanner};
+
+/// Hosts from `~/.ssh/config` and, when enabled, `~/.ssh/known_hosts`.
+pub struct SshScanner {
+    cfg: SshConfig,
+    dir: Option<PathBuf>,
+}
+
+impl SshScanner {
+    pub fn new(cfg: SshConfig) -> Self {
+        Self {
+            cfg,
+            dir: directories::UserDirs::new().map(|dirs| dirs.home_dir().join(".ssh")),
+        }
+    }
+
+    /// The directory to watch for new hosts.
+    pub fn ssh_dir(&self) -> Option<PathBuf> {
+        self.cfg.enabled.then(|| self.dir.clone()).flatten()
+    }
+}
+
+impl WaycastScanner for SshScanner {
+    fn scan(&self) -> Vec<LauncherItem> {
+        let Some(dir) = self.ssh_dir() else {
+            return Vec::new();
+        };
+
+        let configured = ssh::config_hosts(&dir);
+        let known = if self.cfg.known_hosts {
+            fs::read_to_string(dir.join("known_hosts"))
+                .map(|contents| ssh::known_hosts(&contents))
+                .unwrap_or_default()
+        } else {
+            Vec::new()
+        };
+
+        // Hosts in the config are the ones with their user and port set up,
+        // so they win over the same name in known_hosts.
+        let mut seen = HashSet::new();
+        let configured = configured
+            .into_iter()
+            .map(|host| (host, HostSource::Config));
+        let known = known.into_iter().map(|host| (host, HostSource::KnownHosts));
+
+        configured
+            .chain(known)
+            .filter(|(host, _)| seen.insert(host.clone()))
+            .map(|(host, source)| ssh::host_item(&host, source))
+            .collect()
+    }
+}
@@ -1,6 +1,6 @@
 use notify::{Config, EventKind, RecommendedWatcher};
 use notify_debouncer_full::{DebouncedEvent, RecommendedCache, new_debouncer_opt, notify};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::time::Duration;
 use tokio::sync::mpsc::{Sender, error::TrySendError};
 use tracing::info;
@@ -17,6 +17,27 @@ pub fn watch_directories(
     directories: Vec<PathBuf>,
     comm_channel: Sender<FileEvent>,
     recursive_mode: notify::RecursiveMode,
+) {
+    watch(directories, comm_channel, recursive_mode, |_| true);
+}
+
+/// Watch `files` through the directory they're in, for files that are
+/// replaced rather than written in place. Changes to anything else in the
+/// directory are ignored.
+pub fn watch_files(directory: PathBuf, files: Vec<PathBuf>, comm_channel: Sender<FileEvent>) {
+    watch(
+        vec![directory],
+        comm_channel,
+        notify::RecursiveMode::NonRecursive,
+        move |path| files.iter().any(|file| file == path),
+    );
+}
+
+fn watch(
+    directories: Vec<PathBuf>,
+    comm_channel: Sender<FileEvent>,
+    recursive_mode: notify::RecursiveMode,
+    is_watched: impl Fn(&Path) -> bool,
 ) {
     let (tx, rx) = crossbeam_channel::unbounded();
     let notify_config = Config::default();
@@ -43,7 +64,7 @@ pub fn watch_directories(
                         matches!(
                             ev.kind,
                             EventKind::Create(_) | EventKind::Modify(_) | EventKind::Remove(_)
-                        )
+                        ) && ev.paths.iter().any(|path| is_watched(path))
                     })
                     .collect();
 
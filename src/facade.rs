@@ -1,13 +1,29 @@
+use std::collections::HashMap;
 use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
 
 use thiserror::Error;
+use tracing::{error, info};
 
 use crate::core::{
-    FuzzyMatcher, ItemKind, LauncherItem,
-    config::AppConfig,
-    data::{DataError, WaycastData},
+    FuzzyMatcher, ItemKind, KeyedItem, LauncherItem, SearchResult,
+    actions::{self, Action, ActionKind},
+    apps::{self, AppDetails},
+    calculator::{self, Calculation},
+    clipboard::{self, Clipboard, ClipboardError, Serve},
+    config::{AppConfig, EmojiAction},
+    custom,
+    data::{DataError, WaycastData, launches, overrides::ItemOverride},
+    emoji::{self, EmojiIndex},
     icon::IconResolver,
-    launcher::{self, LaunchError},
+    launcher::{self, LaunchError, SpawnOptions},
+    plugins::{Plugin, PluginHost},
+    preview::{Preview, PreviewCache},
+    ranking::{Ranker, ScoreBreakdown},
+    recent::{self, RecentFiles},
+    shell, ssh,
+    windows::{self, Window, WindowBackend, WindowError},
 };
 
 #[derive(Error, Debug)]
@@ -16,25 +32,85 @@ pub enum WaycastError {
     Data(#[from] DataError),
     #[error(transparent)]
     Launch(#[from] LaunchError),
+    #[error("Failed to copy to the clipboard: {0}")]
+    Clipboard(#[from] ClipboardError),
+    #[error("Failed to switch windows: {0}")]
+    Window(#[from] WindowError),
+    #[error("Window switching is not available on this compositor")]
+    NoWindowBackend,
+    #[error("Not a clipboard history entry: {0}")]
+    InvalidClipboardEntry(String),
+    #[error("No command named {0} in the config")]
+    UnknownCustomCommand(String),
     #[error("Unknown item kind for {0}")]
     UnknownKind(String),
 }
 
+/// What a search found without waiting for plugins.
+#[derive(Debug, Default)]
+pub struct QuickSearch {
+    pub results: Vec<SearchResult>,
+    /// Whether plugins take part in the search, so [`WaycastFacade::search`]
+    /// would add what they find
+    pub plugins_pending: bool,
+}
+
 pub struct WaycastFacade {
     config: AppConfig,
     db: WaycastData,
     icon_resolver: IconResolver,
+    clipboard: Clipboard,
+    emoji: EmojiIndex,
+    /// `None` when window switching is off or the compositor isn't one we
+    /// can talk to
+    windows: Option<Arc<dyn WindowBackend>>,
+    /// Visit times for ranking recent files
+    recent: RecentFiles,
+    plugins: Arc<PluginHost>,
+    previews: Arc<PreviewCache>,
+    /// Pins, hides and aliases as of the last search, so the action panel
+    /// knows whether to offer pinning or unpinning without a query
+    overrides: Mutex<HashMap<(String, ItemKind), ItemOverride>>,
+    /// Open windows as of when the launcher was last shown. They don't
+    /// change while it's up, so its searches and the launch that hides it
+    /// reuse them rather than asking the compositor on every keystroke.
+    session_windows: Mutex<Option<Vec<Window>>>,
+    rt: tokio::runtime::Handle,
 }
 
 impl WaycastFacade {
     pub fn new(config: AppConfig, rt: tokio::runtime::Handle) -> Result<Self, WaycastError> {
         let db = rt.block_on(WaycastData::writeable_connection(&config.database_file))?;
         let icon_resolver = IconResolver::new(db.clone());
+        let clipboard = Clipboard::new(&config.clipboard);
+        let emoji = EmojiIndex::new(&config.emoji);
+        let windows = if config.windows.enabled {
+            windows::detect()
+        } else {
+            None
+        };
+        let recent = RecentFiles::new(
+            config
+                .recent_files
+                .enabled
+                .then(recent::default_path)
+                .flatten(),
+        );
+        let plugins = Arc::new(PluginHost::new(&config.plugins));
 
         Ok(Self {
             config,
             db,
             icon_resolver,
+            clipboard,
+            emoji,
+            windows,
+            recent,
+            plugins,
+            previews: Arc::default(),
+            overrides: Mutex::default(),
+            session_windows: Mutex::default(),
+            rt,
         })
     }
 
@@ -46,23 +122,451 @@ impl WaycastFacade {
         &self.db
     }
 
-    pub fn launch(&self, item: &LauncherItem) -> Result<(), WaycastError> {
+    pub fn plugins(&self) -> &Arc<PluginHost> {
+        &self.plugins
+    }
+
+    /// Launch `item`. The activation token, from the compositor, lets
+    /// whatever gets launched take the focus.
+    pub fn launch(
+        &self,
+        item: &LauncherItem,
+        activation_token: Option<&str>,
+    ) -> Result<(), WaycastError> {
         match item.kind {
-            ItemKind::DesktopEntry => launcher::launch_desktop_entry(&item.id)?,
-            ItemKind::File => launcher::open_path(&item.id)?,
+            ItemKind::DesktopEntry => match self.running_window(&item.id) {
+                Some(window) => {
+                    info!("{} is already running, focusing it", item.id);
+                    self.focus_window(&window.id)?
+                }
+                None => launcher::launch_desktop_entry(
+                    &item.id,
+                    &self.config.scan_paths.apps,
+                    &self.config.apps.terminal,
+                    self.spawn_options(Some(&item.id), activation_token),
+                )?,
+            },
+            ItemKind::File | ItemKind::Bookmark | ItemKind::RecentFile => {
+                launcher::open_path(&item.id, self.spawn_options(None, activation_token))?
+            }
             ItemKind::Project => {
                 let command = self.config.project_open_command.replace("{path}", &item.id);
-                launcher::run_command(&command)?
+                launcher::run_command(&command, self.spawn_options(None, activation_token))?
+            }
+            ItemKind::Calculator => {
+                self.copy(&item.id)?;
+                self.record_calculation(Calculation::from_item(item));
+                // Calculations are not something to rank by launch history.
+                return Ok(());
+            }
+            ItemKind::Clipboard => {
+                self.recopy(item)?;
+                return Ok(());
+            }
+            ItemKind::Emoji => self.put_emoji(&item.id)?,
+            ItemKind::Command => launcher::run_shell_command(
+                &item.id,
+                None,
+                self.spawn_options(None, activation_token),
+            )?,
+            ItemKind::TerminalCommand => launcher::run_shell_command(
+                &item.id,
+                Some(&self.config.run.terminal),
+                self.spawn_options(None, activation_token),
+            )?,
+            ItemKind::Custom => self.run_custom(&item.id, activation_token)?,
+            ItemKind::Plugin => self.activate_plugin_item(&item.id),
+            ItemKind::SshHost => {
+                let command = ssh::ssh_command(&self.config.run.terminal, &item.id);
+                let args: Vec<&str> = command[1..].iter().map(String::as_str).collect();
+                let opts = SpawnOptions {
+                    scope_id: Some("ssh"),
+                    ..self.spawn_options(None, activation_token)
+                };
+                launcher::spawn_detached(&command[0], &args, opts).map_err(|e| {
+                    LaunchError::LaunchError(format!("Failed to connect to {}: {e}", item.id))
+                })?
+            }
+            ItemKind::Executable => {
+                let opts = self.spawn_options(None, activation_token);
+                launcher::spawn_detached(&item.id, &[], opts).map_err(|e| {
+                    LaunchError::LaunchError(format!("Failed to run {}: {e}", item.id))
+                })?
+            }
+            ItemKind::Window => {
+                self.focus_window(&item.id)?;
+                // Window ids don't outlive the window, so there's no
+                // history worth keeping.
+                return Ok(());
             }
             ItemKind::Unknown => return Err(WaycastError::UnknownKind(item.id.clone())),
         }
 
+        self.record_launch(item);
+
+        Ok(())
+    }
+
+    /// What can be done with `item`, starting with what `launch` does.
+    pub fn actions(&self, item: &LauncherItem) -> Vec<Action> {
+        let pinned = self
+            .overrides
+            .lock()
+            .expect("overrides lock poisoned")
+            .get(&(item.id.clone(), item.kind))
+            .is_some_and(|item_override| item_override.pinned_at.is_some());
+
+        actions::for_item(item, &self.config.project_commands, pinned)
+    }
+
+    /// Do one of the `actions` for `item`. Opening it some other way counts
+    /// as a launch for ranking, copying, trashing or pinning it doesn't.
+    pub fn run_action(
+        &self,
+        item: &LauncherItem,
+        action: &Action,
+        activation_token: Option<&str>,
+    ) -> Result<(), WaycastError> {
+        match &action.kind {
+            ActionKind::Default => return self.launch(item, activation_token),
+            ActionKind::NewInstance => launcher::launch_desktop_entry(
+                &item.id,
+                &self.config.scan_paths.apps,
+                &self.config.apps.terminal,
+                self.spawn_options(Some(&item.id), activation_token),
+            )?,
+            ActionKind::OpenWith(app_id) => {
+                let app = app_id.strip_suffix(".desktop").unwrap_or(app_id);
+                let opts = self.spawn_options(Some(app), activation_token);
+                launcher::open_with(app_id, &item.id, opts)?
+            }
+            ActionKind::Command(command) => launcher::run_command(
+                &command.replace("{path}", &item.id),
+                self.spawn_options(None, activation_token),
+            )?,
+            ActionKind::Reveal => {
+                launcher::reveal_path(&item.id, self.spawn_options(None, activation_token))?;
+                return Ok(());
+            }
+            ActionKind::CopyPath => {
+                self.copy(&item.id)?;
+                return Ok(());
+            }
+            ActionKind::MoveToTrash => {
+                launcher::trash_path(&item.id)?;
+                return Ok(());
+            }
+            ActionKind::Pin | ActionKind::Unpin | ActionKind::Hide => {
+                self.save_override(item, action.kind.clone());
+                return Ok(());
+            }
+        }
+
+        self.record_launch(item);
+
+        Ok(())
+    }
+
+    /// Launches feed search ranking, so they are recorded in the
+    /// background. Nothing about the launch itself should wait on it.
+    fn record_launch(&self, item: &LauncherItem) {
+        let db = self.db.clone();
+        let item = item.clone();
+
+        self.rt.spawn(async move {
+            if let Err(e) = db.launches().record(&item).await {
+                error!("Failed to record launch of {}: {e}", item.id);
+            }
+        });
+    }
+
+    /// Pin, unpin or hide `item` in the background, like launches are
+    /// recorded. It shows the next time the launcher opens.
+    fn save_override(&self, item: &LauncherItem, kind: ActionKind) {
+        let db = self.db.clone();
+        let item = item.clone();
+
+        self.rt.spawn(async move {
+            let overrides = db.overrides();
+            let result = match kind {
+                ActionKind::Hide => overrides.set_hidden(&item.id, item.kind, true).await,
+                kind => {
+                    let pinned = kind == ActionKind::Pin;
+                    overrides.set_pinned(&item.id, item.kind, pinned).await
+                }
+            };
+
+            if let Err(e) = result {
+                error!("Failed to save override for {}: {e}", item.id);
+            }
+        });
+    }
+
+    /// Put text on the clipboard, like a path, a name or a result. It stays
+    /// there until something else is copied.
+    pub fn copy(&self, text: &str) -> Result<(), WaycastError> {
+        Ok(self.clipboard.copy_text(text)?)
+    }
+
+    /// Copy what stands for `item` outside the launcher: a file's path, a
+    /// page's URL, a calculation's result or the emoji itself. Anything
+    /// else gets its title copied.
+    pub fn copy_item(&self, item: &LauncherItem) -> Result<(), WaycastError> {
+        match item.kind {
+            ItemKind::Clipboard => self.recopy(item),
+            ItemKind::File
+            | ItemKind::RecentFile
+            | ItemKind::Project
+            | ItemKind::Bookmark
+            | ItemKind::Calculator
+            | ItemKind::Emoji
+            | ItemKind::Command
+            | ItemKind::TerminalCommand
+            | ItemKind::Executable
+            | ItemKind::SshHost => self.copy(&item.id),
+            _ => self.copy(&item.title),
+        }
+    }
+
+    /// Put a clipboard history entry back on the clipboard. The contents
+    /// come out of the database, so this finishes in the background.
+    fn recopy(&self, item: &LauncherItem) -> Result<(), WaycastError> {
+        let id: i64 = item
+            .id
+            .parse()
+            .map_err(|_| WaycastError::InvalidClipboardEntry(item.id.clone()))?;
+        let db = self.db.clone();
+        let clipboard = self.clipboard.clone();
+
+        self.rt.spawn(async move {
+            let contents = match db.clipboard().contents(id).await {
+                Ok(Some(contents)) => contents,
+                Ok(None) => {
+                    error!("Clipboard history entry {id} no longer exists");
+                    return;
+                }
+                Err(e) => {
+                    error!("Failed to load clipboard history entry {id}: {e}");
+                    return;
+                }
+            };
+
+            match tokio::task::spawn_blocking(move || {
+                clipboard.copy(contents, Serve::UntilReplaced)
+            })
+            .await
+            {
+                Ok(Ok(())) => {}
+                Ok(Err(e)) => error!("Failed to copy clipboard history entry {id}: {e}"),
+                Err(e) => error!("Clipboard copy task failed: {e}"),
+            }
+        });
+
+        Ok(())
+    }
+
+    /// How to spawn a launch of the app `app_id`, or of anything that isn't
+    /// an app when it's `None`.
+    fn spawn_options<'a>(
+        &self,
+        app_id: Option<&str>,
+        activation_token: Option<&'a str>,
+    ) -> SpawnOptions<'a> {
+        SpawnOptions {
+            activation_token,
+            launch: self.config.launch.profile(app_id),
+            ..SpawnOptions::default()
+        }
+    }
+
+    /// Run or open a `[[commands]]` entry. Commands go through `sh`, since
+    /// anything worth a custom entry tends to need quoting or a pipe.
+    fn run_custom(&self, id: &str, activation_token: Option<&str>) -> Result<(), WaycastError> {
+        let custom = custom::find(&self.config.commands, id)
+            .ok_or_else(|| WaycastError::UnknownCustomCommand(id.to_string()))?;
+
+        match (&custom.command, &custom.url) {
+            (Some(command), _) => {
+                let terminal = custom
+                    .terminal
+                    .then_some(self.config.run.terminal.as_slice());
+                let opts = self.spawn_options(None, activation_token);
+                launcher::run_shell_command(command, terminal, opts)?
+            }
+            (None, Some(url)) => {
+                launcher::open_path(url, self.spawn_options(None, activation_token))?
+            }
+            (None, None) => return Err(WaycastError::UnknownCustomCommand(id.to_string())),
+        }
+
         Ok(())
     }
 
-    /// Every distinct icon name or path referenced by an indexed item.
+    /// Plugins get as long as their timeout to activate an item, which is
+    /// too long to hold up the UI for, so failures only make the log.
+    fn activate_plugin_item(&self, id: &str) {
+        let plugins = Arc::clone(&self.plugins);
+        let id = id.to_string();
+
+        self.rt.spawn_blocking(move || {
+            if let Err(e) = plugins.activate(&id) {
+                error!("Failed to activate {id}: {e}");
+            }
+        });
+    }
+
+    /// Copy or type an emoji, depending on the configured action. Emoji
+    /// launches are recorded like any other so recently used ones come
+    /// first.
+    fn put_emoji(&self, glyph: &str) -> Result<(), WaycastError> {
+        let cfg = &self.config.emoji;
+        match cfg.action {
+            EmojiAction::Copy => self.copy(glyph)?,
+            EmojiAction::Type => emoji::type_text(
+                &cfg.type_command,
+                glyph,
+                Duration::from_millis(cfg.type_delay_ms),
+            ),
+        }
+
+        Ok(())
+    }
+
+    fn focus_window(&self, id: &str) -> Result<(), WaycastError> {
+        let backend = self.windows.as_ref().ok_or(WaycastError::NoWindowBackend)?;
+        Ok(backend.focus(id)?)
+    }
+
+    /// A window of the desktop entry `id`, when focusing existing windows is
+    /// on. Prefers the most recently focused one if the compositor says
+    /// which that is.
+    fn running_window(&self, id: &str) -> Option<Window> {
+        if !self.config.windows.focus_existing {
+            return None;
+        }
+
+        let windows = match self.session_windows() {
+            Some(windows) => windows,
+            None => self
+                .windows
+                .as_ref()?
+                .windows()
+                .inspect_err(|e| error!("Could not list windows: {e}"))
+                .ok()?,
+        };
+        let mut candidates = windows.into_iter().filter(|w| w.is_instance_of(id));
+        let first = candidates.next()?;
+
+        Some(if first.focused {
+            first
+        } else {
+            candidates.find(|w| w.focused).unwrap_or(first)
+        })
+    }
+
+    /// List the open windows for the searches of a launcher session, which
+    /// starts when it's shown.
+    pub async fn start_session(&self) {
+        let windows = self.list_windows().await;
+        *self.session_windows.lock().expect("windows lock poisoned") = Some(windows);
+    }
+
+    /// Forget the last session's windows, which are out of date by the
+    /// time the launcher is shown again. They're listed anew until
+    /// [`Self::start_session`] is done.
+    pub fn forget_session(&self) {
+        *self.session_windows.lock().expect("windows lock poisoned") = None;
+    }
+
+    fn session_windows(&self) -> Option<Vec<Window>> {
+        self.session_windows
+            .lock()
+            .expect("windows lock poisoned")
+            .clone()
+    }
+
+    /// Every open window: the session's, or listed now outside of one.
+    async fn open_windows(&self) -> Vec<Window> {
+        match self.session_windows() {
+            Some(windows) => windows,
+            None => self.list_windows().await,
+        }
+    }
+
+    /// Every open window, or none when they can't be listed. Listing goes
+    /// over a socket, so it's kept off the async workers.
+    async fn list_windows(&self) -> Vec<Window> {
+        let Some(backend) = self.windows.clone() else {
+            return Vec::new();
+        };
+
+        match tokio::task::spawn_blocking(move || backend.windows()).await {
+            Ok(Ok(windows)) => windows,
+            Ok(Err(e)) => {
+                error!("Could not list windows: {e}");
+                Vec::new()
+            }
+            Err(e) => {
+                error!("Window listing task failed: {e}");
+                Vec::new()
+            }
+        }
+    }
+
+    /// Windows as items, with the icon of the app they belong to when one of
+    /// `apps` matches.
+    fn window_items(windows: &[Window], apps: &[LauncherItem]) -> Vec<LauncherItem> {
+        windows
+            .iter()
+            .map(|window| {
+                let app = apps.iter().find(|app| window.is_instance_of(&app.id));
+                window.to_item(app.map(|app| app.icon.as_str()))
+            })
+            .collect()
+    }
+
+    fn record_calculation(&self, calculation: Calculation) {
+        let db = self.db.clone();
+        let limit = self.config.calculator.history_size;
+
+        self.rt.spawn(async move {
+            if let Err(e) = db.calculations().record(&calculation, limit).await {
+                error!(
+                    "Failed to record calculation {}: {e}",
+                    calculation.expression
+                );
+            }
+        });
+    }
+
+    /// Preview of the file or directory at `path`, or `None` when it can't
+    /// be read. It reads the file, so it runs off the async workers.
+    pub async fn preview(&self, path: PathBuf) -> Option<Arc<Preview>> {
+        let previews = Arc::clone(&self.previews);
+
+        match tokio::task::spawn_blocking(move || previews.get(&path)).await {
+            Ok(Ok(preview)) => Some(preview),
+            Ok(Err(e)) => {
+                info!("No preview: {e}");
+                None
+            }
+            Err(e) => {
+                error!("Preview task failed: {e}");
+                None
+            }
+        }
+    }
+
+    /// Every distinct icon name or path referenced by an indexed item, plus
+    /// the icons of results that are never indexed.
     pub async fn icon_names(&self) -> Result<Vec<String>, WaycastError> {
-        Ok(self.db.items().get_icons().await?)
+        let mut names = self.db.items().get_icons().await?;
+        names.push(calculator::ICON.to_string());
+        names.push(clipboard::HISTORY_ICON.to_string());
+        names.push(emoji::ICON.to_string());
+        names.push(windows::ICON.to_string());
+        names.push(shell::ICON.to_string());
+        Ok(names)
     }
 
     /// Resolve an icon name or path to a file on disk, falling back to the
@@ -84,14 +588,106 @@ impl WaycastFacade {
     }
 
     /// Initial list of items that should be shown when no search query is
-    /// present. Currently just returns desktop entries, but in the
-    /// future this could account for frequently accessed items
-    /// being shown and such.
+    /// present: pinned items of any kind, then the rest of the desktop
+    /// entries.
     pub async fn initial_items(&self) -> Result<Vec<LauncherItem>, WaycastError> {
-        self.get_items(Some(ItemKind::DesktopEntry)).await
+        let overrides = self.load_overrides().await?;
+        let pinned = self.db.items().get_pinned().await?;
+        let apps: Vec<LauncherItem> = self
+            .get_items(Some(ItemKind::DesktopEntry))
+            .await?
+            .into_iter()
+            .filter(|app| !pinned.contains(app) && !is_hidden(&overrides, app))
+            .collect();
+
+        Ok(pinned.into_iter().chain(apps).collect())
+    }
+
+    /// Every override, which is also kept for `actions`.
+    async fn load_overrides(
+        &self,
+    ) -> Result<HashMap<(String, ItemKind), ItemOverride>, WaycastError> {
+        let overrides = self.db.overrides().all().await?;
+        *self.overrides.lock().expect("overrides lock poisoned") = overrides.clone();
+        Ok(overrides)
     }
 
-    pub async fn search(&self, query: String) -> Result<Vec<LauncherItem>, WaycastError> {
+    /// Search everything, or one kind of result when the query starts with
+    /// its prefix. Hidden items are left out either way.
+    pub async fn search(&self, query: String) -> Result<Vec<SearchResult>, WaycastError> {
+        let overrides = self.load_overrides().await?;
+        let results = match self.search_prefixed(&query, &overrides).await {
+            Some(results) => results?,
+            None => {
+                let plugin_results = self.search_plugins(&query).await;
+                self.search_all(query, &overrides, plugin_results).await?
+            }
+        };
+
+        Ok(without_hidden(results, &overrides))
+    }
+
+    /// [`Self::search`] without waiting for the plugins that take part in
+    /// every search, so a slow one doesn't hold up everything else.
+    pub async fn quick_search(&self, query: String) -> Result<QuickSearch, WaycastError> {
+        let overrides = self.load_overrides().await?;
+        let (results, plugins_pending) = match self.search_prefixed(&query, &overrides).await {
+            Some(results) => (results?, false),
+            None => (
+                self.search_all(query, &overrides, Vec::new()).await?,
+                self.plugins.searches_everything(),
+            ),
+        };
+
+        Ok(QuickSearch {
+            results: without_hidden(results, &overrides),
+            plugins_pending,
+        })
+    }
+
+    /// Results of one kind when the query starts with its prefix, or `None`
+    /// when it's a search of everything.
+    async fn search_prefixed(
+        &self,
+        query: &str,
+        overrides: &HashMap<(String, ItemKind), ItemOverride>,
+    ) -> Option<Result<Vec<SearchResult>, WaycastError>> {
+        if let Some(expression) = query.strip_prefix(calculator::PREFIX) {
+            return Some(self.calculate(expression).await);
+        }
+        if let Some(query) = query.strip_prefix(clipboard::SEARCH_PREFIX) {
+            return Some(self.search_clipboard(query.trim()).await);
+        }
+        if let Some(query) = query.strip_prefix(emoji::PREFIX) {
+            return Some(self.search_emoji(query.trim()).await);
+        }
+        if let Some(query) = query.strip_prefix(windows::PREFIX) {
+            return Some(self.search_windows(query.trim()).await);
+        }
+        if let Some(query) = query.strip_prefix(apps::CATEGORY_PREFIX) {
+            return Some(self.search_category(query, overrides).await);
+        }
+        let run_prefix = self.config.run.prefix.as_str();
+        if !run_prefix.is_empty()
+            && let Some(command) = query.strip_prefix(run_prefix)
+        {
+            return Some(self.search_commands(command.trim()).await);
+        }
+        if let Some((plugin, query)) = self.plugins.for_query(query) {
+            return Some(Ok(self.search_plugin(plugin, query.trim()).await));
+        }
+
+        None
+    }
+
+    /// Search every kind of item, along with what the plugins without a
+    /// prefix found for `query`.
+    async fn search_all(
+        &self,
+        query: String,
+        overrides: &HashMap<(String, ItemKind), ItemOverride>,
+        plugin_results: Vec<LauncherItem>,
+    ) -> Result<Vec<SearchResult>, WaycastError> {
         // Use sqlite fts to filter files first since there could be thousands
         let file_results: Vec<LauncherItem> = self
             .db
@@ -99,25 +695,403 @@ impl WaycastFacade {
             .search(query.clone(), Some(ItemKind::File), 20)
             .await?;
 
-        let mut fm = FuzzyMatcher::new();
+        // Same for bookmarks, with the whole browser history in there
+        let bookmark_results: Vec<LauncherItem> = self
+            .db
+            .items()
+            .search(query.clone(), Some(ItemKind::Bookmark), 20)
+            .await?;
+
+        let ranking = &self.config.ranking;
+        let mut fm = FuzzyMatcher::new()
+            .with_secondary_key_weight(ranking.secondary_key_weight)
+            .with_keyword_weight(ranking.keyword_weight);
         let mut rows = Vec::new();
 
-        let apps = self.get_items(Some(ItemKind::DesktopEntry)).await?;
+        let mut apps = self.get_items(Some(ItemKind::DesktopEntry)).await?;
+        let open_windows = self.open_windows().await;
 
         let projects = self.get_items(Some(ItemKind::Project)).await?;
+        let ssh_hosts = self.get_items(Some(ItemKind::SshHost)).await?;
+        let recent_files = self.get_items(Some(ItemKind::RecentFile)).await?;
+        let custom_commands = self.get_items(Some(ItemKind::Custom)).await?;
+        let plugin_items = self.get_items(Some(ItemKind::Plugin)).await?;
 
+        rows.extend(Self::window_items(&open_windows, &apps));
+        for app in &mut apps {
+            if open_windows.iter().any(|w| w.is_instance_of(&app.id)) {
+                mark_running(app);
+            }
+        }
         rows.extend(apps);
         rows.extend(projects);
+        rows.extend(ssh_hosts);
+        rows.extend(custom_commands);
+        // A plugin can return an item it also lists.
+        rows.extend(
+            plugin_results
+                .into_iter()
+                .filter(|item| !plugin_items.contains(item)),
+        );
+        rows.extend(plugin_items);
+
+        // A recently opened file may also be one of the indexed files.
+        let file_results: Vec<LauncherItem> = file_results
+            .into_iter()
+            .filter(|file| !recent_files.iter().any(|recent| recent.id == file.id))
+            .collect();
 
         let mut candidates = rows;
+        candidates.extend(recent_files);
         candidates.extend(file_results);
+        candidates.extend(bookmark_results);
+        // Hidden items go before ranking so they don't take up a kind's
+        // share of the results.
+        candidates.retain(|item| !is_hidden(overrides, item));
+        let app_details = self.db.apps().all().await?;
+        let keyed = keyed_items(&candidates, overrides, &app_details);
+
+        let mut stats = self.db.launches().stats().await?;
+        self.recent.add_visits(&mut stats);
+        let ranker = Ranker::new(ranking, &stats, launches::now_epoch_secs());
+        let ranked = ranker.rank(
+            fm.score_items(&query, &keyed)
+                .into_iter()
+                .map(|(score, candidate)| (score, candidate.item))
+                .collect(),
+        );
 
-        let results: Vec<LauncherItem> = fm
-            .match_items(&query, &candidates, 5)
+        // A calculation goes on top, but never in place of regular matches.
+        let calculation = Calculation::from_query(&query, &self.config.calculator);
+        let results: Vec<SearchResult> = calculation
+            .map(|calculation| SearchResult::from(calculation.to_item()))
             .into_iter()
-            .cloned()
+            .chain(ranked.into_iter().map(|(score, item)| SearchResult {
+                item: item.clone(),
+                indices: fm.match_indices(&query, item),
+                score,
+            }))
             .collect();
 
         Ok(results)
     }
+
+    /// Search the clipboard history, most recent first when there's no
+    /// query.
+    async fn search_clipboard(&self, query: &str) -> Result<Vec<SearchResult>, WaycastError> {
+        let max_entries = self.config.clipboard.history.max_entries;
+        let entries: Vec<LauncherItem> = self
+            .db
+            .clipboard()
+            .recent(max_entries)
+            .await?
+            .into_iter()
+            .map(LauncherItem::from)
+            .collect();
+
+        if query.is_empty() {
+            return Ok(entries.into_iter().map(SearchResult::from).collect());
+        }
+
+        let mut fm = FuzzyMatcher::new();
+        Ok(fm
+            .match_items(query, &entries, entries.len())
+            .into_iter()
+            .map(|m| SearchResult {
+                item: m.item.clone(),
+                indices: m.indices,
+                score: ScoreBreakdown {
+                    fuzzy: m.score as f32,
+                    ..ScoreBreakdown::default()
+                },
+            })
+            .collect())
+    }
+
+    /// Search emoji and symbols. Recently used ones get the same boost
+    /// launched apps do, and with no query they're listed first.
+    async fn search_emoji(&self, query: &str) -> Result<Vec<SearchResult>, WaycastError> {
+        let stats = self.db.launches().stats().await?;
+
+        if query.is_empty() {
+            let mut recent: Vec<_> = stats
+                .iter()
+                .filter(|((_, kind), _)| *kind == ItemKind::Emoji)
+                .collect();
+            recent.sort_by_key(|(_, stats)| std::cmp::Reverse(stats.last_launched_at));
+
+            let recent: Vec<&LauncherItem> = recent
+                .into_iter()
+                .filter_map(|((glyph, _), _)| self.emoji.get(glyph))
+                .collect();
+            let rest = self
+                .emoji
+                .candidates(query)
+                .into_iter()
+                .filter(|item| !recent.contains(item));
+
+            return Ok(recent
+                .iter()
+                .copied()
+                .chain(rest)
+                .take(emoji::MAX_RESULTS)
+                .cloned()
+                .map(SearchResult::from)
+                .collect());
+        }
+
+        let candidates = self.emoji.candidates(query);
+        let ranker = Ranker::new(&self.config.ranking, &stats, launches::now_epoch_secs());
+        let mut fm =
+            FuzzyMatcher::new().with_secondary_key_weight(self.config.ranking.secondary_key_weight);
+
+        let mut scored: Vec<(ScoreBreakdown, &LauncherItem)> = fm
+            .score_items(query, &candidates)
+            .into_iter()
+            .map(|(fuzzy, item)| (ranker.score(fuzzy, item), *item))
+            .collect();
+        scored.sort_by(|(a, _), (b, _)| b.total().total_cmp(&a.total()));
+
+        Ok(scored
+            .into_iter()
+            .take(emoji::MAX_RESULTS)
+            .map(|(score, item)| SearchResult {
+                item: item.clone(),
+                indices: fm.match_indices(query, item),
+                score,
+            })
+            .collect())
+    }
+
+    /// Search in run mode: the typed command ready to run either way, then
+    /// past commands and programs on `$PATH` that match it. With nothing
+    /// typed, the command history, most recent first.
+    async fn search_commands(&self, command: &str) -> Result<Vec<SearchResult>, WaycastError> {
+        let stats = self.db.launches().stats().await?;
+
+        let mut history: Vec<_> = stats
+            .iter()
+            .filter(|((_, kind), _)| matches!(kind, ItemKind::Command | ItemKind::TerminalCommand))
+            .collect();
+        history.sort_by_key(|(_, stats)| std::cmp::Reverse(stats.last_launched_at));
+        let history: Vec<LauncherItem> = history
+            .into_iter()
+            .take(self.config.run.history_size)
+            .map(|((past, kind), _)| shell::command_item(past, *kind))
+            .collect();
+
+        if command.is_empty() {
+            return Ok(history.into_iter().map(SearchResult::from).collect());
+        }
+
+        let typed = shell::command_items(command);
+        let mut candidates: Vec<LauncherItem> = history
+            .into_iter()
+            .filter(|item| !typed.contains(item))
+            .collect();
+        candidates.extend(self.get_items(Some(ItemKind::Executable)).await?);
+
+        let ranker = Ranker::new(&self.config.ranking, &stats, launches::now_epoch_secs());
+        let mut fm =
+            FuzzyMatcher::new().with_secondary_key_weight(self.config.ranking.secondary_key_weight);
+        let mut scored: Vec<(ScoreBreakdown, &LauncherItem)> = fm
+            .score_items(command, &candidates)
+            .into_iter()
+            .map(|(fuzzy, item)| (ranker.score(fuzzy, item), item))
+            .collect();
+        scored.sort_by(|(a, _), (b, _)| b.total().total_cmp(&a.total()));
+
+        Ok(typed
+            .into_iter()
+            .map(SearchResult::from)
+            .chain(
+                scored
+                    .into_iter()
+                    .take(shell::MAX_RESULTS)
+                    .map(|(score, item)| SearchResult {
+                        item: item.clone(),
+                        indices: fm.match_indices(command, item),
+                        score,
+                    }),
+            )
+            .collect())
+    }
+
+    /// Search open windows only, listing all of them when there's no query.
+    async fn search_windows(&self, query: &str) -> Result<Vec<SearchResult>, WaycastError> {
+        if self.windows.is_none() {
+            return Ok(Vec::new());
+        }
+
+        let apps = self.get_items(Some(ItemKind::DesktopEntry)).await?;
+        let items = Self::window_items(&self.open_windows().await, &apps);
+
+        if query.is_empty() {
+            return Ok(items.into_iter().map(SearchResult::from).collect());
+        }
+
+        let mut fm =
+            FuzzyMatcher::new().with_secondary_key_weight(self.config.ranking.secondary_key_weight);
+        Ok(fm
+            .match_items(query, &items, items.len())
+            .into_iter()
+            .map(|m| SearchResult {
+                item: m.item.clone(),
+                indices: m.indices,
+                score: ScoreBreakdown {
+                    fuzzy: m.score as f32,
+                    ..ScoreBreakdown::default()
+                },
+            })
+            .collect())
+    }
+
+    /// Search the apps in one category, or list them all by name when
+    /// nothing follows the category.
+    async fn search_category(
+        &self,
+        query: &str,
+        overrides: &HashMap<(String, ItemKind), ItemOverride>,
+    ) -> Result<Vec<SearchResult>, WaycastError> {
+        let (category, query) = apps::split_category_query(query);
+        let app_details = self.db.apps().all().await?;
+        let mut apps: Vec<LauncherItem> = self
+            .get_items(Some(ItemKind::DesktopEntry))
+            .await?
+            .into_iter()
+            .filter(|app| {
+                app_details
+                    .get(&app.id)
+                    .is_some_and(|details| details.in_category(category))
+            })
+            .collect();
+
+        if query.is_empty() {
+            apps.sort_by_key(|app| app.title.to_lowercase());
+            return Ok(apps.into_iter().map(SearchResult::from).collect());
+        }
+
+        let ranking = &self.config.ranking;
+        let stats = self.db.launches().stats().await?;
+        let ranker = Ranker::new(ranking, &stats, launches::now_epoch_secs());
+        let mut fm = FuzzyMatcher::new()
+            .with_secondary_key_weight(ranking.secondary_key_weight)
+            .with_keyword_weight(ranking.keyword_weight);
+        let keyed = keyed_items(&apps, overrides, &app_details);
+
+        let mut scored: Vec<(ScoreBreakdown, &LauncherItem)> = fm
+            .score_items(query, &keyed)
+            .into_iter()
+            .map(|(fuzzy, candidate)| (ranker.score(fuzzy, candidate.item), candidate.item))
+            .collect();
+        scored.sort_by(|(a, _), (b, _)| b.total().total_cmp(&a.total()));
+
+        Ok(scored
+            .into_iter()
+            .map(|(score, item)| SearchResult {
+                item: item.clone(),
+                indices: fm.match_indices(query, item),
+                score,
+            })
+            .collect())
+    }
+
+    /// Results from the plugins searched along with everything else.
+    async fn search_plugins(&self, query: &str) -> Vec<LauncherItem> {
+        let plugins = Arc::clone(&self.plugins);
+        let query = query.to_string();
+
+        tokio::task::spawn_blocking(move || plugins.search(&query))
+            .await
+            .unwrap_or_else(|e| {
+                error!("Plugin search task failed: {e}");
+                Vec::new()
+            })
+    }
+
+    /// Search a single plugin by its prefix. Its results are shown in the
+    /// order it returned them.
+    async fn search_plugin(&self, plugin: Arc<Plugin>, query: &str) -> Vec<SearchResult> {
+        let query = query.to_string();
+
+        match tokio::task::spawn_blocking(move || plugin.search(&query)).await {
+            Ok(Ok(items)) => items.into_iter().map(SearchResult::from).collect(),
+            Ok(Err(e)) => {
+                error!("{e}");
+                Vec::new()
+            }
+            Err(e) => {
+                error!("Plugin search task failed: {e}");
+                Vec::new()
+            }
+        }
+    }
+
+    /// Search in calculator mode: the expression evaluated no matter what it
+    /// looks like, or the history when there's no expression yet.
+    async fn calculate(&self, expression: &str) -> Result<Vec<SearchResult>, WaycastError> {
+        let cfg = &self.config.calculator;
+
+        if expression.trim().is_empty() {
+            let history = self.db.calculations().recent(cfg.history_size).await?;
+            return Ok(history
+                .iter()
+                .map(|calculation| SearchResult::from(calculation.to_item()))
+                .collect());
+        }
+
+        Ok(Calculation::evaluate(expression, cfg)
+            .map(|calculation| SearchResult::from(calculation.to_item()))
+            .into_iter()
+            .collect())
+    }
+}
+
+fn without_hidden(
+    results: Vec<SearchResult>,
+    overrides: &HashMap<(String, ItemKind), ItemOverride>,
+) -> Vec<SearchResult> {
+    results
+        .into_iter()
+        .filter(|result| !is_hidden(overrides, &result.item))
+        .collect()
+}
+
+/// `items` with their aliases and, for apps, what their desktop entries
+/// say about them, for searching.
+fn keyed_items<'a>(
+    items: &'a [LauncherItem],
+    overrides: &'a HashMap<(String, ItemKind), ItemOverride>,
+    app_details: &HashMap<String, AppDetails>,
+) -> Vec<KeyedItem<'a>> {
+    items
+        .iter()
+        .map(|item| KeyedItem {
+            item,
+            aliases: overrides
+                .get(&(item.id.clone(), item.kind))
+                .map_or(&[], |item_override| item_override.aliases.as_slice()),
+            keywords: match item.kind {
+                ItemKind::DesktopEntry => app_details
+                    .get(&item.id)
+                    .map(AppDetails::search_keys)
+                    .unwrap_or_default(),
+                _ => Vec::new(),
+            },
+        })
+        .collect()
+}
+
+fn is_hidden(overrides: &HashMap<(String, ItemKind), ItemOverride>, item: &LauncherItem) -> bool {
+    overrides
+        .get(&(item.id.clone(), item.kind))
+        .is_some_and(|item_override| item_override.hidden)
+}
+
+/// Note in an app's description that it already has a window open.
+fn mark_running(app: &mut LauncherItem) {
+    app.description = Some(match app.description.take() {
+        Some(description) if !description.is_empty() => format!("Running · {description}"),
+        _ => String::from("Running"),
+    });
 }
@@ -1,7 +1,8 @@
 use clap::{Parser, Subcommand};
 use waycast::app::AppError;
-use waycast::cmd::{self, StartupError};
-use waycast::core::config::{self, AppConfig};
+use waycast::cmd::{self, ItemChange, StartupError};
+use waycast::core::SearchMode;
+use waycast::core::config::{self, AppConfig, OutputTarget};
 
 use tracing::{error, warn};
 use tracing_subscriber::{EnvFilter, fmt};
@@ -12,22 +13,71 @@ enum Cache {
     Clear,
 }
 
+#[derive(Subcommand)]
+enum Item {
+    /// Show an item first when nothing has been typed yet
+    Pin { id: String },
+    /// Stop showing an item first
+    Unpin { id: String },
+    /// Leave an item out of every result
+    Hide { id: String },
+    /// Show a hidden item again
+    Unhide { id: String },
+    /// Set other names to find an item by, like "ff" for Firefox. Giving
+    /// none removes them.
+    Alias { id: String, aliases: Vec<String> },
+    /// List every pinned, hidden or aliased item
+    List,
+}
+
 #[derive(Subcommand)]
 enum Command {
     Version,
     /// Start the waycast daemon process
     Daemon,
     /// Signal the daemon to show the launcher UI
-    Show,
+    Show {
+        /// Output to show it on: "active", "focused", "cursor" or an output
+        /// name like "DP-1". Defaults to `ui.output` from the config.
+        #[arg(long)]
+        output: Option<OutputTarget>,
+        /// Start out searching only one kind of result: "emoji" or "windows"
+        #[arg(long)]
+        mode: Option<SearchMode>,
+    },
+    /// Signal the daemon to hide the launcher UI
+    Hide,
+    /// Signal the daemon to show the launcher UI, or hide it if it's showing
+    Toggle {
+        /// Output to show it on, same as for `show`
+        #[arg(long)]
+        output: Option<OutputTarget>,
+        /// Mode to show it in, same as for `show`
+        #[arg(long)]
+        mode: Option<SearchMode>,
+    },
     /// Show the current app configuration
     Config,
     /// Ping the daemon to check if it's up
     Status,
+    /// Search indexed items the same way the launcher does
+    Search {
+        query: String,
+        /// Show how each result's score was calculated
+        #[arg(long)]
+        explain: bool,
+    },
     /// Cache operations
     Cache {
         #[command(subcommand)]
         command: Cache,
     },
+    /// Pin, hide or alias items. Ids are the ones `search --explain`
+    /// shows.
+    Item {
+        #[command(subcommand)]
+        command: Item,
+    },
 }
 
 #[derive(Parser)]
@@ -66,10 +116,27 @@ pub fn main() -> Result<(), StartupError> {
             }
         },
         Command::Status => cmd::status_command(cfg.socket_file),
-        Command::Show => cmd::show_ui_command(cfg.socket_file),
+        Command::Show { output, mode } => cmd::show_ui_command(cfg.socket_file, output, mode),
+        Command::Hide => cmd::hide_ui_command(cfg.socket_file),
+        Command::Toggle { output, mode } => cmd::toggle_ui_command(cfg.socket_file, output, mode),
         Command::Cache { command } => match command {
             Cache::Clear => cmd::cache_clear_command(cfg.database_file),
         },
+        Command::Item { command } => match command {
+            Item::Pin { id } => cmd::item_command(cfg.database_file, id, ItemChange::Pinned(true)),
+            Item::Unpin { id } => {
+                cmd::item_command(cfg.database_file, id, ItemChange::Pinned(false))
+            }
+            Item::Hide { id } => cmd::item_command(cfg.database_file, id, ItemChange::Hidden(true)),
+            Item::Unhide { id } => {
+                cmd::item_command(cfg.database_file, id, ItemChange::Hidden(false))
+            }
+            Item::Alias { id, aliases } => {
+                cmd::item_command(cfg.database_file, id, ItemChange::Aliases(aliases))
+            }
+            Item::List => cmd::item_list_command(cfg.database_file),
+        },
         Command::Config => cmd::config_command(&cfg),
+        Command::Search { query, explain } => cmd::search_command(cfg, query, explain),
     }
 }
@@ -8,6 +8,8 @@ use thiserror::Error;
 use tracing::{error, info};
 
 use crate::app::AppMessage;
+use crate::core::SearchMode;
+use crate::core::config::OutputTarget;
 
 #[derive(Debug, Error)]
 pub enum SocketError {
@@ -61,11 +63,10 @@ impl WaycastSocketListener {
                 continue;
             }
 
-            let cmd = match msg.trim() {
-                "show" => AppMessage::Show,
-                "ping" => AppMessage::Ping,
-                _ => {
-                    error!("Invalid command {}", msg.trim());
+            let cmd = match parse_command(msg.trim()) {
+                Ok(cmd) => cmd,
+                Err(e) => {
+                    error!("{e}");
                     continue;
                 }
             };
@@ -95,8 +96,16 @@ impl WaycastSocketClient {
 
     fn send_command(&mut self, cmd: AppMessage) -> Result<(), SocketError> {
         match cmd {
-            AppMessage::Show => {
-                self.client.write_all(b"show\n")?;
+            AppMessage::Show(output, mode) => {
+                self.client
+                    .write_all(with_args("show", output, mode).as_bytes())?;
+            }
+            AppMessage::Hide => {
+                self.client.write_all(b"hide\n")?;
+            }
+            AppMessage::Toggle(output, mode) => {
+                self.client
+                    .write_all(with_args("toggle", output, mode).as_bytes())?;
             }
             AppMessage::Ping => {
                 self.client.write_all(b"ping\n")?;
@@ -111,11 +120,65 @@ impl WaycastSocketClient {
         let _ = self.client.shutdown(Shutdown::Write);
     }
 
-    pub fn send_show(&mut self) -> Result<(), SocketError> {
-        self.send_command(AppMessage::Show)
+    pub fn send_show(
+        &mut self,
+        output: Option<OutputTarget>,
+        mode: Option<SearchMode>,
+    ) -> Result<(), SocketError> {
+        self.send_command(AppMessage::Show(output, mode))
+    }
+
+    pub fn send_hide(&mut self) -> Result<(), SocketError> {
+        self.send_command(AppMessage::Hide)
+    }
+
+    pub fn send_toggle(
+        &mut self,
+        output: Option<OutputTarget>,
+        mode: Option<SearchMode>,
+    ) -> Result<(), SocketError> {
+        self.send_command(AppMessage::Toggle(output, mode))
     }
 
     pub fn send_ping(&mut self) -> Result<(), SocketError> {
         self.send_command(AppMessage::Ping)
     }
 }
+
+const MODE_ARG: &str = "mode=";
+
+/// Show and toggle take the output to show on and the mode to show in as
+/// optional arguments, e.g. "show DP-1 mode=emoji".
+fn parse_command(msg: &str) -> Result<AppMessage, SocketError> {
+    let mut words = msg.split_whitespace();
+    let name = words.next().unwrap_or_default();
+    let mut output = None;
+    let mut mode = None;
+
+    for word in words {
+        match word.strip_prefix(MODE_ARG) {
+            Some(value) => mode = Some(value.parse().map_err(SocketError::InvalidCommand)?),
+            None => output = Some(OutputTarget::from(word.to_string())),
+        }
+    }
+
+    match (name, output, mode) {
+        ("show", output, mode) => Ok(AppMessage::Show(output, mode)),
+        ("hide", None, None) => Ok(AppMessage::Hide),
+        ("toggle", output, mode) => Ok(AppMessage::Toggle(output, mode)),
+        ("ping", None, None) => Ok(AppMessage::Ping),
+        _ => Err(SocketError::InvalidCommand(msg.to_string())),
+    }
+}
+
+fn with_args(command: &str, output: Option<OutputTarget>, mode: Option<SearchMode>) -> String {
+    let mut line = command.to_string();
+    if let Some(output) = output {
+        line.push_str(&format!(" {output}"));
+    }
+    if let Some(mode) = mode {
+        line.push_str(&format!(" {MODE_ARG}{mode}"));
+    }
+    line.push('\n');
+    line
+}
@@ -0,0 +1,112 @@
+//! Activation tokens for launched apps, over xdg-activation. Apps hand the
+//! token back to the compositor when they map a window, which is how they
+//! get focused instead of opening behind whatever was focused before the
+//! launcher.
+
+use thiserror::Error;
+use tracing::{debug, info};
+use wayland_client::globals::{GlobalListContents, registry_queue_init};
+use wayland_client::protocol::wl_registry::WlRegistry;
+use wayland_client::{Connection, Dispatch, Proxy, QueueHandle};
+use wayland_protocols::xdg::activation::v1::client::{
+    xdg_activation_token_v1::{self, XdgActivationTokenV1},
+    xdg_activation_v1::XdgActivationV1,
+};
+
+#[derive(Debug, Error)]
+enum ActivationError {
+    #[error("Could not connect to the Wayland compositor: {0}")]
+    Connect(#[from] wayland_client::ConnectError),
+    #[error("Could not list Wayland globals: {0}")]
+    Globals(#[from] wayland_client::globals::GlobalError),
+    #[error("The compositor does not support xdg-activation")]
+    Unsupported(#[from] wayland_client::globals::BindError),
+    #[error("Wayland connection error: {0}")]
+    Dispatch(#[from] wayland_client::DispatchError),
+}
+
+/// A token for launching `app_id`, or `None` when the compositor doesn't
+/// hand one out.
+///
+/// This asks on a connection of its own, without the launcher's surface or
+/// the serial of the key press that launched the app. iced_layershell
+/// 0.13.7 exposes neither: its key events leave out the serial, and it
+/// ignores `window::run_with_handle`. Compositors strict about focus
+/// stealing, which only honour tokens tied to a recent input event, may
+/// therefore still only mark the app's window as urgent.
+pub fn request_token(app_id: Option<&str>) -> Option<String> {
+    match try_request_token(app_id) {
+        Ok(token) => token,
+        Err(e) => {
+            info!("No activation token for the launch: {e}");
+            None
+        }
+    }
+}
+
+fn try_request_token(app_id: Option<&str>) -> Result<Option<String>, ActivationError> {
+    let conn = Connection::connect_to_env()?;
+    let (globals, mut queue) = registry_queue_init::<State>(&conn)?;
+    let qh = queue.handle();
+
+    let activation: XdgActivationV1 = globals.bind(&qh, 1..=1, ())?;
+    let token = activation.get_activation_token(&qh, ());
+    if let Some(app_id) = app_id {
+        token.set_app_id(app_id.to_string());
+    }
+    token.commit();
+
+    // `done` is sent right after the commit, so it's in by the time the
+    // compositor answers the roundtrip.
+    let mut state = State::default();
+    queue.roundtrip(&mut state)?;
+    token.destroy();
+    activation.destroy();
+
+    debug!("Got activation token {:?}", state.token);
+    Ok(state.token)
+}
+
+#[derive(Default)]
+struct State {
+    token: Option<String>,
+}
+
+impl Dispatch<XdgActivationTokenV1, ()> for State {
+    fn event(
+        state: &mut Self,
+        _token: &XdgActivationTokenV1,
+        event: <XdgActivationTokenV1 as Proxy>::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+        if let xdg_activation_token_v1::Event::Done { token } = event {
+            state.token = Some(token);
+        }
+    }
+}
+
+impl Dispatch<XdgActivationV1, ()> for State {
+    fn event(
+        _state: &mut Self,
+        _activation: &XdgActivationV1,
+        _event: <XdgActivationV1 as Proxy>::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+    }
+}
+
+impl Dispatch<WlRegistry, GlobalListContents> for State {
+    fn event(
+        _state: &mut Self,
+        _registry: &WlRegistry,
+        _event: <WlRegistry as Proxy>::Event,
+        _data: &GlobalListContents,
+        _conn: &Connection,
+        _qh: &QueueHandle<Self>,
+    ) {
+    }
+}
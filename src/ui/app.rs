@@ -1,86 +1,148 @@
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
-
-use crate::core::LauncherItem;
-use crate::facade::{self, WaycastFacade};
+use std::time::Duration;
+
+use crate::core::actions::Action;
+use crate::core::data::launches;
+use crate::core::keymap::{self, KeyAction};
+use crate::core::preview::{self, Preview, PreviewContent};
+use crate::core::{ItemKind, LauncherItem, SearchMode, SearchResult};
+use crate::facade::{self, QuickSearch, WaycastFacade};
 use iced::keyboard::key;
 use iced::widget::scrollable::{self, Id as ScrollableId};
 use iced::widget::text_input::{self, Id as TextInputId};
 use iced::widget::{
-    button, column, container, image, row, scrollable as scrollable_widget, svg, text,
-    text_input as text_input_widget,
+    Row, button, column, container, image, row, scrollable as scrollable_widget, svg, text,
+    text_editor, text_input as text_input_widget,
 };
 use iced::{
-    Alignment, Color, Element, Length, Subscription, Task as Command, Theme, event, keyboard,
+    Alignment, Color, ContentFit, Element, Font, Length, Subscription, Task as Command, Theme,
+    event, highlighter, keyboard,
 };
-use iced_layershell::Application;
+use iced_layershell::reexport::{KeyboardInteractivity, Layer};
 use iced_layershell::to_layer_message;
+use iced_layershell::{Appearance, Application, DefaultStyle};
+use tokio::sync::Mutex;
+use tokio::sync::mpsc::UnboundedReceiver;
 use tracing::{error, info};
 
-use crate::ui::config;
+use crate::ui::activation;
+use crate::ui::output::Placement;
+use crate::ui::search::SearchSequence;
 use crate::ui::styles;
+use crate::ui::theme::WaycastTheme;
+use crate::ui::{UiCommand, UiFlags, config};
 
 #[to_layer_message]
 #[derive(Debug, Clone)]
 pub enum Message {
     Search(String),
     Execute(String),
-    EventOccurred(iced::Event),
-    // Data loading
-    Loaded(Vec<LauncherItem>),
+    /// Show/hide requests coming from the rest of the process
+    Ui(UiCommand),
+    /// A key press, with whether a widget already handled it
+    KeyPressed(keyboard::Key, keyboard::Modifiers, event::Status),
+    // Data loading. Tagged with the sequence number of the search that
+    // produced it.
+    Loaded(u64, Vec<SearchResult>),
+    /// Results shown while plugins are still searching
+    PartlyLoaded(u64, Vec<SearchResult>),
+    /// Preview of the file at a path, if it could be read
+    PreviewLoaded(String, Option<Arc<Preview>>),
     IconHandles(HashMap<String, IconHandle>),
     // UI Intents
     CloseWindow,
     SearchSubmit,
+    /// Run the action at this index in the action panel
+    RunAction(usize),
     // Window actions
     HideWindow(iced::window::Id),
 }
 
 pub struct Waycast {
     waycast: Arc<facade::WaycastFacade>,
-    /// Current items shown in the list
-    items: Vec<LauncherItem>,
+    /// Show/hide requests from the rest of the process
+    commands: Arc<Mutex<UnboundedReceiver<UiCommand>>>,
+    /// Whether the surface is currently shown. Hidden, it is shrunk and
+    /// pushed to the background layer instead of being torn down.
+    visible: bool,
+    /// Results of the latest search
+    results: Vec<SearchResult>,
+    /// The results shown in the list, which are all of them unless they're
+    /// filtered to one kind
+    items: Vec<SearchResult>,
+    /// The kind the results are filtered to
+    kind_filter: Option<ItemKind>,
     /// Icon handles to share between elements
     icon_handles: HashMap<String, IconHandle>,
     query: String,
+    /// The search in flight, and which results are stale
+    searches: SearchSequence,
     selected_index: usize,
+    /// Results that fit in the list at once, for paging
+    page_size: usize,
+    /// The action panel, shown in place of the results while it's open
+    actions: Option<ActionPanel>,
+    /// Preview of the selected file, and its path
+    preview: Option<(String, Arc<Preview>)>,
+    /// A text preview's lines, in the form the editor widget shows them
+    preview_text: text_editor::Content,
+    /// An image preview's thumbnail, made into a handle once rather than on
+    /// every draw
+    preview_image: Option<image::Handle>,
     search_input_id: TextInputId,
     scrollable_id: ScrollableId,
 }
 
+/// Actions for one result. It keeps the result itself since a search can
+/// replace the list while the panel is open.
+struct ActionPanel {
+    item: LauncherItem,
+    actions: Vec<Action>,
+    selected_index: usize,
+}
+
 impl Application for Waycast {
     type Message = Message;
-    type Flags = Arc<WaycastFacade>;
+    type Flags = UiFlags;
     type Theme = Theme;
     type Executor = iced::executor::Default;
 
-    fn new(waycast: Arc<facade::WaycastFacade>) -> (Self, Command<Message>) {
+    fn new(flags: UiFlags) -> (Self, Command<Message>) {
         let search_input_id = TextInputId::unique();
         let scrollable_id = ScrollableId::unique();
 
         let app = Self {
-            waycast,
+            waycast: flags.waycast,
+            commands: flags.commands,
+            visible: false,
             icon_handles: HashMap::new(),
+            results: Vec::new(),
             items: Vec::new(),
+            kind_filter: None,
             query: String::new(),
+            searches: SearchSequence::default(),
             selected_index: 0,
-            search_input_id: search_input_id.clone(),
+            page_size: 1,
+            actions: None,
+            preview: None,
+            preview_text: text_editor::Content::new(),
+            preview_image: None,
+            search_input_id,
             scrollable_id,
         };
 
-        let focus_task = text_input::focus(search_input_id);
         let load_task = Command::batch([
-            Command::perform(
-                Self::load_initial_data(app.waycast.clone()),
-                Message::Loaded,
-            ),
+            Command::perform(Self::load_initial_data(app.waycast.clone()), |results| {
+                Message::Loaded(0, results)
+            }),
             Command::perform(
                 Self::build_icon_handle_map(app.waycast.clone()),
                 Message::IconHandles,
             ),
         ]);
-        (app, Command::batch([focus_task, load_task]))
+        (app, load_task)
     }
 
     fn namespace(&self) -> String {
@@ -89,7 +151,15 @@ impl Application for Waycast {
 
     fn subscription(&self) -> Subscription<Message> {
         Subscription::batch([
-            event::listen().map(Message::EventOccurred),
+            Subscription::run_with_id("ui-commands", ui_commands(self.commands.clone())),
+            // Key presses the text input handled too, since it keeps
+            // plenty of Ctrl combinations to itself.
+            event::listen_with(|event, status, _window| match event {
+                iced::Event::Keyboard(keyboard::Event::KeyPressed { key, modifiers, .. }) => {
+                    Some(Message::KeyPressed(key, modifiers, status))
+                }
+                _ => None,
+            }),
             keyboard::on_key_release(|key, _modifiers| {
                 if matches!(key, keyboard::Key::Named(key::Named::Escape)) {
                     Some(Message::CloseWindow)
@@ -102,21 +172,39 @@ impl Application for Waycast {
 
     fn update(&mut self, message: Message) -> Command<Message> {
         match message {
-            Message::Search(query) => {
-                self.query = query.clone();
-                self.selected_index = 0;
-
-                if query.is_empty() {
-                    return Command::perform(
-                        Self::load_initial_data(self.waycast.clone()),
-                        Message::Loaded,
-                    );
+            Message::Search(query) => self.run_search(query),
+            Message::Ui(command) => match command {
+                UiCommand::Show(placement, mode) => self.show(placement, mode),
+                UiCommand::Hide => self.hide(),
+                UiCommand::Toggle(..) if self.visible => self.hide(),
+                UiCommand::Toggle(placement, mode) => self.show(placement, mode),
+                UiCommand::Exit => iced::exit(),
+            },
+            Message::Loaded(seq, results) => {
+                // An older search that finished late must not overwrite
+                // the results of a newer one.
+                if !self.searches.finish(seq) {
+                    return Command::none();
+                }
+
+                self.results = results;
+                self.filter_results();
+                self.load_preview()
+            }
+            Message::PartlyLoaded(seq, results) => {
+                if !self.searches.is_latest(seq) {
+                    return Command::none();
                 }
 
-                Command::perform(Self::search(self.waycast.clone(), query), Message::Loaded)
+                self.results = results;
+                self.filter_results();
+                self.load_preview()
             }
-            Message::Loaded(results) => {
-                self.items = results;
+            Message::PreviewLoaded(path, preview) => {
+                // The selection may have moved on while it loaded.
+                if self.selected_path().as_deref() == Some(path.as_str()) {
+                    self.show_preview(path, preview);
+                }
                 Command::none()
             }
             Message::IconHandles(handles) => {
@@ -124,50 +212,71 @@ impl Application for Waycast {
                 Command::none()
             }
             Message::Execute(_id) => self.execute_item(),
-            Message::EventOccurred(event) => {
-                if let iced::Event::Keyboard(keyboard::Event::KeyPressed { key, .. }) = event {
-                    self.handle_key_press(key)
-                } else {
-                    Command::none()
+            Message::KeyPressed(key, modifiers, status) => {
+                self.handle_key_press(key, modifiers, status)
+            }
+            Message::CloseWindow if self.actions.is_some() => self.close_actions(),
+            Message::CloseWindow => self.hide(),
+            Message::SearchSubmit => {
+                match self.actions.as_ref().map(|panel| panel.selected_index) {
+                    Some(index) => self.run_action(index),
+                    None => self.execute_item(),
                 }
             }
-            Message::CloseWindow => iced::exit(),
-            Message::SearchSubmit => self.execute_item(),
+            Message::RunAction(index) => self.run_action(index),
             _ => Command::none(),
         }
     }
 
     fn view(&self) -> Element<'_, Message> {
-        let results_list = self.build_results_list();
-        let scrollable_list = self.build_scrollable(results_list);
+        if !self.visible {
+            return column![].into();
+        }
+
         let search_input = self.build_search_input();
 
-        let mut col = column![container(search_input).padding(config::PADDING_LARGE),];
-
-        if let Ok(calc_result) = mathengine::evaluate_expression(&self.query) {
-            let disp = match calc_result {
-                mathengine::Value::Number(n) => format!("{:.2}", n.0),
-                mathengine::Value::UnitValue(uv) => format!("{:.2} {}", uv.value(), uv.unit()),
-            };
-            col = col.push(
-                container(
-                    text(format!("= {}", disp))
-                        .size(20)
-                        .font(styles::bold_font())
-                        .color(Color::from_rgba(1.0, 1.0, 1.0, 0.7)),
-                )
-                .padding(config::PADDING_LARGE),
-            );
-        } else {
-            col = col.push(container(scrollable_list).padding(config::PADDING_LARGE));
-        }
+        let body = match (&self.actions, &self.preview) {
+            (Some(panel), _) => self.build_action_panel(panel),
+            (None, Some((path, preview))) => row![
+                container(self.build_scrollable(self.build_results_list()))
+                    .width(Length::FillPortion(3)),
+                container(build_preview(
+                    path,
+                    preview,
+                    &self.preview_text,
+                    self.preview_image.as_ref(),
+                ))
+                .width(Length::FillPortion(2)),
+            ]
+            .spacing(config::PADDING_LARGE)
+            .into(),
+            (None, None) => self.build_scrollable(self.build_results_list()),
+        };
 
-        col.into()
+        column![
+            container(search_input).padding(config::PADDING_LARGE),
+            container(body).padding(config::PADDING_LARGE),
+        ]
+        .into()
     }
 
     fn theme(&self) -> Self::Theme {
         Theme::Dark
     }
+
+    /// Nothing is drawn while hidden, so the 1x1 surface left mapped then
+    /// doesn't show up as a stray pixel.
+    fn style(&self, theme: &Self::Theme) -> Appearance {
+        let appearance = theme.default_style();
+        if self.visible {
+            return appearance;
+        }
+
+        Appearance {
+            background_color: Color::TRANSPARENT,
+            ..appearance
+        }
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -200,48 +309,417 @@ impl Waycast {
         handles
     }
 
-    async fn load_initial_data(waycast: Arc<WaycastFacade>) -> Vec<LauncherItem> {
-        waycast.initial_items().await.unwrap_or_default()
+    async fn load_initial_data(waycast: Arc<WaycastFacade>) -> Vec<SearchResult> {
+        waycast
+            .initial_items()
+            .await
+            .unwrap_or_default()
+            .into_iter()
+            .map(SearchResult::from)
+            .collect()
     }
 
-    async fn search(waycast: Arc<WaycastFacade>, query: String) -> Vec<LauncherItem> {
-        waycast.search(query).await.unwrap_or_default()
+    async fn search(waycast: Arc<WaycastFacade>, query: String, debounce: Duration) -> QuickSearch {
+        // Keystrokes that arrive while this sleeps abort it, so only the
+        // search for the last one actually runs.
+        if !debounce.is_zero() {
+            tokio::time::sleep(debounce).await;
+        }
+
+        waycast.quick_search(query).await.unwrap_or_default()
     }
 
-    fn handle_key_press(&mut self, key: keyboard::Key) -> Command<Message> {
-        let results_len = self.items.len();
+    /// Show what the search found, then, when plugins take part in it, the
+    /// results again with theirs once they've answered.
+    fn load_search(
+        waycast: Arc<WaycastFacade>,
+        query: String,
+        seq: u64,
+        found: QuickSearch,
+    ) -> Command<Message> {
+        if !found.plugins_pending {
+            return Command::done(Message::Loaded(seq, found.results));
+        }
+
+        Command::done(Message::PartlyLoaded(seq, found.results)).chain(Command::perform(
+            async move { waycast.search(query).await.unwrap_or_default() },
+            move |results| Message::Loaded(seq, results),
+        ))
+    }
+
+    fn run_search(&mut self, query: String) -> Command<Message> {
+        self.query = query.clone();
+        self.selected_index = 0;
+        self.actions = None;
+        let seq = self.searches.start();
+
+        let search = if query.is_empty() {
+            Command::perform(
+                Self::load_initial_data(self.waycast.clone()),
+                move |results| Message::Loaded(seq, results),
+            )
+        } else {
+            let debounce = Duration::from_millis(self.waycast.config().ui.search_debounce_ms);
+            let waycast = self.waycast.clone();
+            Command::future(Self::search(waycast.clone(), query.clone(), debounce))
+                .then(move |found| Self::load_search(waycast.clone(), query.clone(), seq, found))
+        };
+
+        let (search, handle) = search.abortable();
+        self.searches.track(handle);
+
+        search
+    }
+
+    fn show(&mut self, placement: Placement, mode: Option<SearchMode>) -> Command<Message> {
+        // A mode starts the query out with its prefix. Focusing the input
+        // puts the cursor after it.
+        let enter_mode = match mode {
+            Some(mode) => self.run_search(mode.prefix()),
+            None => Command::none(),
+        };
 
+        if self.visible {
+            return Command::batch([enter_mode, text_input::focus(self.search_input_id.clone())]);
+        }
+
+        info!("Showing UI");
+        self.visible = true;
+        self.waycast.forget_session();
+        let waycast = self.waycast.clone();
+        let start_session = Command::future(async move { waycast.start_session().await });
+        let list_height = placement.size.1 as f32 - config::LIST_CHROME_HEIGHT;
+        self.page_size = ((list_height / config::ITEM_HEIGHT) as usize).max(1);
+
+        Command::batch([
+            start_session.discard(),
+            enter_mode,
+            Command::done(Message::AnchorChange(placement.anchor)),
+            Command::done(Message::MarginChange(placement.margin)),
+            Command::done(Message::SizeChange(placement.size)),
+            Command::done(Message::LayerChange(Layer::Overlay)),
+            Command::done(Message::KeyboardInteractivityChange(
+                KeyboardInteractivity::Exclusive,
+            )),
+            text_input::focus(self.search_input_id.clone()),
+            self.scroll_to_selected(),
+        ])
+    }
+
+    /// Hide the surface without tearing it down, and get it ready for the
+    /// next show while nobody is looking: clear the query, reload the
+    /// initial items and refresh icons in case a rescan changed them.
+    ///
+    /// The surface stays mapped, shrunk to 1x1 on the background layer with
+    /// nothing drawn. iced_layershell 0.13's single-window `Application`
+    /// can't unmap it: only its multi-window runtime handles creating and
+    /// removing layer surfaces, and closing the main window exits.
+    fn hide(&mut self) -> Command<Message> {
+        info!("Hiding UI");
+        self.visible = false;
+        self.actions = None;
+        self.kind_filter = None;
+
+        Command::batch([
+            Command::done(Message::KeyboardInteractivityChange(
+                KeyboardInteractivity::None,
+            )),
+            Command::done(Message::LayerChange(Layer::Background)),
+            Command::done(Message::SizeChange(config::HIDDEN_SIZE)),
+            self.run_search(String::new()),
+            Command::perform(
+                Self::build_icon_handle_map(self.waycast.clone()),
+                Message::IconHandles,
+            ),
+        ])
+    }
+
+    fn handle_key_press(
+        &mut self,
+        key: keyboard::Key,
+        modifiers: keyboard::Modifiers,
+        status: event::Status,
+    ) -> Command<Message> {
+        let name = match key.as_ref() {
+            keyboard::Key::Named(named) => format!("{named:?}"),
+            keyboard::Key::Character(character) => character.to_string(),
+            keyboard::Key::Unidentified => return Command::none(),
+        };
+        let modifiers = keymap::Modifiers {
+            ctrl: modifiers.control(),
+            alt: modifiers.alt(),
+            shift: modifiers.shift(),
+            logo: modifiers.logo(),
+        };
+
+        if let Some(action) = self.waycast.config().ui.keymap.action(&name, modifiers) {
+            return self.run_key_action(action);
+        }
+
+        // A focused input submits on its own.
         match key {
-            keyboard::Key::Named(key::Named::ArrowDown) => {
-                self.selected_index = (self.selected_index + 1) % results_len;
-                self.scroll_to_selected()
+            keyboard::Key::Named(key::Named::Enter) if status == event::Status::Ignored => {
+                self.update(Message::SearchSubmit)
             }
-            keyboard::Key::Named(key::Named::ArrowUp) => {
-                if self.selected_index == 0 {
-                    self.selected_index = results_len - 1;
-                } else {
-                    self.selected_index -= 1;
-                }
-                self.scroll_to_selected()
-            }
-            keyboard::Key::Named(key::Named::Enter) => self.execute_item(),
             _ => Command::none(),
         }
     }
 
-    fn execute_item(&self) -> Command<Message> {
-        info!("Executing");
-        if let Some(item) = self.items.get(self.selected_index)
-            && let Err(e) = self.waycast.launch(item)
+    fn run_key_action(&mut self, action: KeyAction) -> Command<Message> {
+        match action {
+            KeyAction::Next
+            | KeyAction::Previous
+            | KeyAction::PageDown
+            | KeyAction::PageUp
+            | KeyAction::First
+            | KeyAction::Last => self.move_selection(action),
+            KeyAction::Actions => self.toggle_actions(),
+            KeyAction::Copy => self.copy_selected(),
+            KeyAction::DeleteWord => {
+                let query = self
+                    .query
+                    .trim_end()
+                    .trim_end_matches(|c: char| !c.is_whitespace())
+                    .to_string();
+                Command::batch([
+                    self.run_search(query),
+                    text_input::move_cursor_to_end(self.search_input_id.clone()),
+                ])
+            }
+            KeyAction::Clear => self.run_search(String::new()),
+            KeyAction::CycleKind => self.cycle_kind(),
+        }
+    }
+
+    /// Move the selection in the action panel when it's open, and in the
+    /// results otherwise. Next and previous wrap around, paging stops at
+    /// either end.
+    fn move_selection(&mut self, action: KeyAction) -> Command<Message> {
+        let page_size = self.page_size;
+        let (selected, len) = match &mut self.actions {
+            Some(panel) => (&mut panel.selected_index, panel.actions.len()),
+            None => (&mut self.selected_index, self.items.len()),
+        };
+        if len == 0 {
+            return Command::none();
+        }
+
+        let last = len - 1;
+        *selected = match action {
+            KeyAction::Next if *selected >= last => 0,
+            KeyAction::Next => *selected + 1,
+            KeyAction::Previous => selected.checked_sub(1).unwrap_or(last),
+            KeyAction::PageDown => (*selected + page_size).min(last),
+            KeyAction::PageUp => selected.saturating_sub(page_size),
+            KeyAction::First => 0,
+            KeyAction::Last => last,
+            _ => *selected,
+        };
+
+        self.selection_changed()
+    }
+
+    /// Copy the selected result, or the one the action panel is open for.
+    fn copy_selected(&mut self) -> Command<Message> {
+        let item = match &self.actions {
+            Some(panel) => Some(&panel.item),
+            None => self
+                .items
+                .get(self.selected_index)
+                .map(|result| &result.item),
+        };
+        let Some(item) = item else {
+            return Command::none();
+        };
+
+        if let Err(e) = self.waycast.copy_item(item) {
+            error!("Failed to copy {}: {e}", item.title);
+        }
+
+        self.hide()
+    }
+
+    /// Filter the results to the next kind among them, or back to all of
+    /// them after the last one.
+    fn cycle_kind(&mut self) -> Command<Message> {
+        let mut kinds: Vec<ItemKind> = Vec::new();
+        for result in &self.results {
+            if !kinds.contains(&result.item.kind) {
+                kinds.push(result.item.kind);
+            }
+        }
+
+        self.kind_filter = match self.kind_filter {
+            None => kinds.first().copied(),
+            Some(current) => kinds
+                .iter()
+                .skip_while(|kind| **kind != current)
+                .nth(1)
+                .copied(),
+        };
+        self.actions = None;
+        self.selected_index = 0;
+        self.filter_results();
+
+        self.selection_changed()
+    }
+
+    fn selection_changed(&mut self) -> Command<Message> {
+        Command::batch([self.scroll_to_selected(), self.load_preview()])
+    }
+
+    /// Path of the selected result, if it's something on disk to preview.
+    fn selected_path(&self) -> Option<String> {
+        let item = &self.items.get(self.selected_index)?.item;
+        matches!(
+            item.kind,
+            ItemKind::File | ItemKind::RecentFile | ItemKind::Project
+        )
+        .then(|| item.id.clone())
+    }
+
+    fn load_preview(&mut self) -> Command<Message> {
+        if !self.waycast.config().ui.preview {
+            return Command::none();
+        }
+        let Some(path) = self.selected_path() else {
+            self.preview = None;
+            return Command::none();
+        };
+        if self
+            .preview
+            .as_ref()
+            .is_some_and(|(shown, _)| *shown == path)
         {
-            error!("Failed to launch: {e}");
+            return Command::none();
+        }
+
+        let waycast = self.waycast.clone();
+        let load = PathBuf::from(&path);
+        Command::perform(async move { waycast.preview(load).await }, move |preview| {
+            Message::PreviewLoaded(path.clone(), preview)
+        })
+    }
+
+    fn show_preview(&mut self, path: String, preview: Option<Arc<Preview>>) {
+        let Some(preview) = preview else {
+            self.preview = None;
+            return;
+        };
+
+        self.preview_image = None;
+        match &preview.content {
+            PreviewContent::Text { text, .. } => {
+                self.preview_text = text_editor::Content::with_text(text);
+            }
+            PreviewContent::Image(thumbnail) => {
+                self.preview_image = Some(image::Handle::from_rgba(
+                    thumbnail.width,
+                    thumbnail.height,
+                    thumbnail.pixels.clone(),
+                ));
+            }
+            _ => {}
         }
+        self.preview = Some((path, preview));
+    }
+
+    fn filter_results(&mut self) {
+        self.items = match self.kind_filter {
+            Some(kind) => self
+                .results
+                .iter()
+                .filter(|result| result.item.kind == kind)
+                .cloned()
+                .collect(),
+            None => self.results.clone(),
+        };
+    }
 
-        iced::exit()
+    fn execute_item(&mut self) -> Command<Message> {
+        info!("Executing");
+        let Some(result) = self.items.get(self.selected_index) else {
+            return self.hide();
+        };
+
+        let item = result.item.clone();
+        let token = activation::request_token(app_id(&item));
+        let waycast = self.waycast.clone();
+        let launch = Self::in_background(move || {
+            if let Err(e) = waycast.launch(&item, token.as_deref()) {
+                error!("Failed to launch: {e}");
+            }
+        });
+
+        Command::batch([self.hide(), launch])
+    }
+
+    /// Run a launch off the UI thread, once the launcher is hiding. Apps
+    /// activated over D-Bus are waited for while they start, and the
+    /// launcher shouldn't sit on screen holding the keyboard meanwhile. The
+    /// activation token has to be asked for before that, while the launcher
+    /// still has the focus.
+    fn in_background(launch: impl FnOnce() + Send + 'static) -> Command<Message> {
+        Command::future(async move {
+            if let Err(e) = tokio::task::spawn_blocking(launch).await {
+                error!("Launch task failed: {e}");
+            }
+        })
+        .discard()
+    }
+
+    fn toggle_actions(&mut self) -> Command<Message> {
+        if self.actions.is_some() {
+            return self.close_actions();
+        }
+        let Some(result) = self.items.get(self.selected_index) else {
+            return Command::none();
+        };
+
+        self.actions = Some(ActionPanel {
+            item: result.item.clone(),
+            actions: self.waycast.actions(&result.item),
+            selected_index: 0,
+        });
+        self.scroll_to_selected()
+    }
+
+    /// Back to the results, with the input focused again since Escape
+    /// takes the focus away from it.
+    fn close_actions(&mut self) -> Command<Message> {
+        self.actions = None;
+        Command::batch([
+            text_input::focus(self.search_input_id.clone()),
+            self.scroll_to_selected(),
+        ])
+    }
+
+    fn run_action(&mut self, index: usize) -> Command<Message> {
+        let Some(panel) = self.actions.take() else {
+            return Command::none();
+        };
+        let Some(action) = panel.actions.get(index) else {
+            return Command::none();
+        };
+
+        info!("Running {} on {}", action.title, panel.item.id);
+        let token = activation::request_token(app_id(&panel.item));
+        let waycast = self.waycast.clone();
+        let (item, action) = (panel.item.clone(), action.clone());
+        let run = Self::in_background(move || {
+            if let Err(e) = waycast.run_action(&item, &action, token.as_deref()) {
+                error!("Failed to {}: {e}", action.title.to_lowercase());
+            }
+        });
+
+        Command::batch([self.hide(), run])
     }
 
     fn scroll_to_selected(&self) -> Command<Message> {
-        let scroll_offset = self.selected_index as f32 * config::ITEM_HEIGHT;
+        let scroll_offset = match &self.actions {
+            Some(panel) => panel.selected_index as f32 * config::ACTION_HEIGHT,
+            None => self.selected_index as f32 * config::ITEM_HEIGHT,
+        };
         scrollable::scroll_to(
             self.scrollable_id.clone(),
             scrollable::AbsoluteOffset {
@@ -252,7 +730,7 @@ impl Waycast {
     }
 
     fn build_search_input(&self) -> Element<'_, Message> {
-        row![
+        let mut input_row = row![
             text_input_widget(config::SEARCH_PLACEHOLDER, &self.query)
                 .id(self.search_input_id.clone())
                 .size(config::SEARCH_INPUT_SIZE)
@@ -262,30 +740,45 @@ impl Waycast {
                 .width(Length::Fill)
                 .on_submit(Message::SearchSubmit),
         ]
-        .into()
+        .align_y(Alignment::Center);
+
+        if let Some(kind) = self.kind_filter {
+            input_row = input_row.push(
+                text(kind.label())
+                    .size(config::DESCRIPTION_FONT_SIZE)
+                    .font(styles::bold_font())
+                    .color(WaycastTheme::placeholder_color()),
+            );
+        }
+
+        if self.searches.is_loading() {
+            input_row = input_row.push(
+                text(config::LOADING_TEXT)
+                    .size(config::DESCRIPTION_FONT_SIZE)
+                    .font(styles::italic_font())
+                    .color(WaycastTheme::placeholder_color()),
+            );
+        }
+
+        input_row.into()
     }
 
     fn build_results_list(&self) -> Element<'_, Message> {
-        let results = self.items.clone();
-
-        if results.is_empty() {
+        if self.items.is_empty() {
             return column![text("No results")].into();
         }
 
         let mut col = column![];
-        for (index, item) in results.iter().enumerate() {
-            let result_item = self.build_result_item(item.clone(), index == self.selected_index);
+        for (index, result) in self.items.iter().enumerate() {
+            let result_item = self.build_result_item(result, index == self.selected_index);
             col = col.push(result_item);
         }
 
         col.into()
     }
 
-    fn build_result_item(
-        &self,
-        item: crate::core::LauncherItem,
-        is_selected: bool,
-    ) -> Element<'_, Message> {
+    fn build_result_item(&self, result: &SearchResult, is_selected: bool) -> Element<'_, Message> {
+        let item = &result.item;
         let icon_handle: IconHandle = self
             .icon_handles
             .get(&item.icon)
@@ -297,24 +790,55 @@ impl Waycast {
         let content = row![
             column![icon_view].padding(config::PADDING_SMALL),
             column![
-                text(item.title)
-                    .size(config::TITLE_FONT_SIZE)
-                    .font(styles::bold_font()),
-                text(item.description.unwrap_or_default())
-                    .size(config::DESCRIPTION_FONT_SIZE)
-                    .font(styles::italic_font())
+                highlighted_text(
+                    &item.title,
+                    result.title_indices(),
+                    config::TITLE_FONT_SIZE,
+                    styles::bold_font(),
+                ),
+                highlighted_text(
+                    item.description.as_deref().unwrap_or_default(),
+                    result.description_indices(),
+                    config::DESCRIPTION_FONT_SIZE,
+                    styles::italic_font(),
+                ),
             ]
             .padding(config::PADDING_SMALL),
         ]
         .align_y(Alignment::Center);
 
         button(content)
-            .on_press(Message::Execute(item.id))
+            .on_press(Message::Execute(item.id.clone()))
             .width(Length::Fill)
             .style(styles::result_button_style(is_selected))
             .into()
     }
 
+    fn build_action_panel<'a>(&'a self, panel: &'a ActionPanel) -> Element<'a, Message> {
+        let mut actions = column![];
+        for (index, action) in panel.actions.iter().enumerate() {
+            let is_selected = index == panel.selected_index;
+            actions = actions.push(
+                button(text(action.title.as_str()).size(config::TITLE_FONT_SIZE))
+                    .on_press(Message::RunAction(index))
+                    .width(Length::Fill)
+                    .height(config::ACTION_HEIGHT)
+                    .padding(config::PADDING_SMALL)
+                    .style(styles::result_button_style(is_selected)),
+            );
+        }
+
+        column![
+            text(panel.item.title.as_str())
+                .size(config::DESCRIPTION_FONT_SIZE)
+                .font(styles::italic_font())
+                .color(WaycastTheme::placeholder_color()),
+            self.build_scrollable(actions.into()),
+        ]
+        .spacing(config::PADDING_SMALL)
+        .into()
+    }
+
     fn build_scrollable<'a>(&self, content: Element<'a, Message>) -> Element<'a, Message> {
         scrollable_widget(content)
             .id(self.scrollable_id.clone())
@@ -325,6 +849,105 @@ impl Waycast {
     }
 }
 
+/// The preview pane: what's in the file, then its type, size and age.
+fn build_preview<'a>(
+    path: &'a str,
+    preview: &'a Preview,
+    text: &'a text_editor::Content,
+    thumbnail: Option<&'a image::Handle>,
+) -> Element<'a, Message> {
+    let mut details = vec![preview.mime.clone()];
+    let contents: Element<'a, Message> = match &preview.content {
+        PreviewContent::Text { extension, .. } => {
+            details.push(preview::format_size(preview.size));
+            text_editor(text)
+                .highlight(extension, highlighter::Theme::Base16Ocean)
+                .size(config::DESCRIPTION_FONT_SIZE)
+                .height(Length::Fill)
+                .into()
+        }
+        PreviewContent::Svg => {
+            details.push(preview::format_size(preview.size));
+            svg(svg::Handle::from_path(path))
+                .height(Length::Fill)
+                .into()
+        }
+        PreviewContent::Image(_) => {
+            details.push(preview::format_size(preview.size));
+            match thumbnail {
+                Some(handle) => image(handle.clone())
+                    .content_fit(ContentFit::Contain)
+                    .height(Length::Fill)
+                    .into(),
+                None => column![].height(Length::Fill).into(),
+            }
+        }
+        PreviewContent::Directory { entries, total } => {
+            details.push(match total {
+                1 => String::from("1 item"),
+                _ => format!("{total} items"),
+            });
+            let mut listing = column![];
+            for entry in entries {
+                let name = if entry.is_dir {
+                    format!("{}/", entry.name)
+                } else {
+                    entry.name.clone()
+                };
+                listing = listing.push(text(name).size(config::DESCRIPTION_FONT_SIZE));
+            }
+            if *total > entries.len() {
+                listing = listing.push(
+                    text(format!("and {} more", total - entries.len()))
+                        .size(config::DESCRIPTION_FONT_SIZE)
+                        .font(styles::italic_font())
+                        .color(WaycastTheme::placeholder_color()),
+                );
+            }
+            scrollable_widget(listing)
+                .height(Length::Fill)
+                .style(styles::scrollable_style)
+                .into()
+        }
+        PreviewContent::Other => {
+            details.push(preview::format_size(preview.size));
+            column![].height(Length::Fill).into()
+        }
+    };
+    if let Some(modified) = preview.modified {
+        details.push(format!(
+            "Modified {}",
+            preview::format_age(modified, launches::now_epoch_secs())
+        ));
+    }
+
+    column![
+        contents,
+        text(details.join(" · "))
+            .size(config::DESCRIPTION_FONT_SIZE)
+            .font(styles::italic_font())
+            .color(WaycastTheme::placeholder_color()),
+    ]
+    .spacing(config::PADDING_SMALL)
+    .into()
+}
+
+/// Forward commands from the rest of the process into the UI's update loop.
+fn ui_commands(
+    commands: Arc<Mutex<UnboundedReceiver<UiCommand>>>,
+) -> impl iced::futures::Stream<Item = Message> {
+    iced::futures::stream::unfold(commands, |commands| async move {
+        let command = commands.lock().await.recv().await?;
+        Some((Message::Ui(command), commands))
+    })
+}
+
+/// The app a launch starts, for the compositor to tie the activation token
+/// to.
+fn app_id(item: &LauncherItem) -> Option<&str> {
+    (item.kind == ItemKind::DesktopEntry).then_some(item.id.as_str())
+}
+
 fn build_icon_view(icon_handle: IconHandle) -> Element<'static, Message> {
     match icon_handle {
         IconHandle::Svg(handle) => svg::Svg::new(handle)
@@ -337,3 +960,50 @@ fn build_icon_view(icon_handle: IconHandle) -> Element<'static, Message> {
             .into(),
     }
 }
+
+/// Render `content` with the characters at `indices` picked out in the match
+/// highlight color.
+///
+/// Built from a row of plain text segments rather than `rich_text`: rich text
+/// captures clicks on its spans, which would swallow the result button's press.
+fn highlighted_text(
+    content: &str,
+    indices: &[u32],
+    size: u16,
+    font: Font,
+) -> Element<'static, Message> {
+    let mut segments = Row::new();
+    let mut current = String::new();
+    let mut current_matched = false;
+    let mut indices = indices.iter().peekable();
+
+    for (position, ch) in content.chars().enumerate() {
+        let matched = indices.next_if_eq(&&(position as u32)).is_some();
+        if matched != current_matched && !current.is_empty() {
+            segments = segments.push(segment(
+                std::mem::take(&mut current),
+                current_matched,
+                size,
+                font,
+            ));
+        }
+        current_matched = matched;
+        current.push(ch);
+    }
+
+    if !current.is_empty() {
+        segments = segments.push(segment(current, current_matched, size, font));
+    }
+
+    segments.into()
+}
+
+fn segment(content: String, matched: bool, size: u16, font: Font) -> Element<'static, Message> {
+    let segment = text(content).size(size).font(font);
+
+    if matched {
+        segment.color(WaycastTheme::match_highlight_color()).into()
+    } else {
+        segment.into()
+    }
+}
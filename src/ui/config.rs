@@ -1,16 +1,23 @@
 pub const APP_NAME: &str = "Waycast";
 pub const WINDOW_WIDTH: u32 = 800;
 pub const WINDOW_HEIGHT: u32 = 500;
+/// Size of the surface while hidden. Layer surfaces can't be zero sized.
+pub const HIDDEN_SIZE: (u32, u32) = (1, 1);
 
 pub const ICON_SIZE: u16 = 48;
 
 pub const ITEM_HEIGHT: f32 = 60.0;
+pub const ACTION_HEIGHT: f32 = 40.0;
+/// Height of everything around the results list: the search input and the
+/// padding.
+pub const LIST_CHROME_HEIGHT: f32 = 120.0;
 
 pub const TITLE_FONT_SIZE: u16 = 18;
 pub const DESCRIPTION_FONT_SIZE: u16 = 14;
 pub const SEARCH_INPUT_SIZE: u16 = 25;
 
 pub const SEARCH_PLACEHOLDER: &str = "Search...";
+pub const LOADING_TEXT: &str = "Searching...";
 
 pub const PADDING_SMALL: u16 = 5;
 pub const PADDING_LARGE: u16 = 20;
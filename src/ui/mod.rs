@@ -1,21 +1,58 @@
+mod activation;
 mod app;
 mod config;
+mod output;
+mod search;
 mod styles;
 mod theme;
 
 use std::sync::Arc;
 
 use iced_layershell::Application;
-use iced_layershell::reexport::Anchor;
+use iced_layershell::reexport::{Anchor, KeyboardInteractivity, Layer};
 use iced_layershell::settings::{LayerShellSettings, Settings, StartMode};
+use tokio::sync::Mutex;
+use tokio::sync::mpsc::{self, UnboundedReceiver, UnboundedSender};
+use tracing::{error, info};
 
+use crate::core::SearchMode;
+use crate::core::config::OutputTarget;
 use crate::facade::WaycastFacade;
 use app::Waycast;
+use output::Placement;
+
+/// Commands the resident UI accepts from the rest of the process.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum UiCommand {
+    /// Show the UI, searching in the given mode if there is one
+    Show(Placement, Option<SearchMode>),
+    Hide,
+    Toggle(Placement, Option<SearchMode>),
+    /// Tear the UI down for good
+    Exit,
+}
 
 pub struct WaycastUi;
 
+/// What the UI is started with. The receiver is shared behind a mutex since
+/// the subscription that drains it gets rebuilt on every update.
+pub struct UiFlags {
+    waycast: Arc<WaycastFacade>,
+    commands: Arc<Mutex<UnboundedReceiver<UiCommand>>>,
+}
+
 impl WaycastUi {
-    pub fn run(waycast: Arc<WaycastFacade>) -> Result<(), iced_layershell::Error> {
+    /// BLOCKS for as long as the UI is alive. The surface starts out hidden
+    /// and is shown and hidden through `commands`; it only exits when sent
+    /// `UiCommand::Exit`.
+    ///
+    /// `output` is the name of the output to create the surface on. `None`
+    /// lets the compositor pick.
+    pub fn run(
+        waycast: Arc<WaycastFacade>,
+        commands: UnboundedReceiver<UiCommand>,
+        output: Option<String>,
+    ) -> Result<(), iced_layershell::Error> {
         // `Settings` only derives `Default` when `Flags: Default`, and the
         // facade has no meaningful default, so borrow the defaults for
         // every other field from a unit-flagged `Settings`.
@@ -23,12 +60,20 @@ impl WaycastUi {
 
         Waycast::run(Settings {
             id: Some(config::APP_NAME.into()),
-            flags: waycast,
+            flags: UiFlags {
+                waycast,
+                commands: Arc::new(Mutex::new(commands)),
+            },
             layer_settings: LayerShellSettings {
-                size: Some((config::WINDOW_WIDTH, config::WINDOW_HEIGHT)),
+                size: Some(config::HIDDEN_SIZE),
                 exclusive_zone: 0,
                 anchor: Anchor::Bottom | Anchor::Left | Anchor::Right | Anchor::Top,
-                start_mode: StartMode::Active,
+                layer: Layer::Background,
+                keyboard_interactivity: KeyboardInteractivity::None,
+                start_mode: match output {
+                    Some(name) => StartMode::TargetScreen(name),
+                    None => StartMode::Active,
+                },
                 ..Default::default()
             },
             antialiasing: defaults.antialiasing,
@@ -39,3 +84,118 @@ impl WaycastUi {
         })
     }
 }
+
+/// Handle to the resident UI thread.
+///
+/// The UI is started once and then only shown and hidden, so icon handles
+/// and results stay loaded between uses. If the UI thread ever goes away
+/// the next command starts a new one.
+pub struct UiHandle {
+    waycast: Arc<WaycastFacade>,
+    commands: Option<UnboundedSender<UiCommand>>,
+    /// Output the running UI's surface was created on
+    output: Option<String>,
+}
+
+impl UiHandle {
+    pub fn new(waycast: Arc<WaycastFacade>) -> Self {
+        Self {
+            waycast,
+            commands: None,
+            output: None,
+        }
+    }
+
+    /// Start the UI hidden so the first show doesn't pay for startup.
+    pub fn start(&mut self) {
+        if self.commands.is_none() {
+            self.output = output::resolve(&self.waycast.config().ui.output);
+            self.commands = Some(self.spawn());
+        }
+    }
+
+    /// Show the UI on `target`, or on the configured output when `None`.
+    pub fn show(&mut self, target: Option<OutputTarget>, mode: Option<SearchMode>) {
+        self.place(target, mode, false);
+    }
+
+    /// Like `show`, except an already visible UI gets hidden.
+    pub fn toggle(&mut self, target: Option<OutputTarget>, mode: Option<SearchMode>) {
+        self.place(target, mode, true);
+    }
+
+    pub fn hide(&mut self) {
+        self.send(UiCommand::Hide);
+    }
+
+    fn place(&mut self, target: Option<OutputTarget>, mode: Option<SearchMode>, toggle: bool) {
+        let cfg = &self.waycast.config().ui;
+        let target = target.as_ref().unwrap_or(&cfg.output);
+        let output = output::resolve(target);
+        // A surface left for the compositor to place still lands on some
+        // output, and that one's override applies.
+        let placed_on = match target {
+            OutputTarget::Active => output::unbound_output(cfg),
+            _ => output.clone(),
+        };
+        let placement = Placement::for_output(cfg, placed_on.as_deref());
+        let mut command = if toggle {
+            UiCommand::Toggle(placement, mode)
+        } else {
+            UiCommand::Show(placement, mode)
+        };
+
+        // A layer surface is bound to the output it was created on, so
+        // moving to another one means replacing the UI. The new one starts
+        // hidden, which makes a toggle a show.
+        if output != self.output {
+            info!(
+                "Moving UI to output {}",
+                output.as_deref().unwrap_or("(any)")
+            );
+            self.stop();
+            self.output = output;
+            command = UiCommand::Show(placement, mode);
+        }
+
+        self.send(command);
+    }
+
+    fn stop(&mut self) {
+        if let Some(commands) = self.commands.take() {
+            let _ = commands.send(UiCommand::Exit);
+        }
+    }
+
+    fn send(&mut self, command: UiCommand) {
+        if let Some(commands) = &self.commands
+            && commands.send(command).is_ok()
+        {
+            return;
+        }
+
+        // The receiver only goes away when the UI thread has exited.
+        info!("UI is not running, starting it");
+        let commands = self.spawn();
+        if commands.send(command).is_err() {
+            error!("UI exited before it could receive {command:?}");
+        }
+        self.commands = Some(commands);
+    }
+
+    fn spawn(&self) -> UnboundedSender<UiCommand> {
+        let (tx, rx) = mpsc::unbounded_channel();
+        let waycast = self.waycast.clone();
+        let output = self.output.clone();
+
+        std::thread::spawn(move || {
+            info!("Launching UI");
+            if let Err(e) = WaycastUi::run(waycast, rx, output) {
+                error!("UI failed: {e}");
+            }
+            info!("UI exited");
+        });
+
+        tx
+    }
+}
@@ -0,0 +1,305 @@
+//! Picking which output (monitor) the launcher shows up on, and where on it.
+//!
+//! Wayland gives clients no way to ask which output is focused or where the
+//! cursor is, so those are answered by asking the compositor through its own
+//! CLI. Compositors we don't know about fall back to letting the compositor
+//! pick.
+
+use std::process::{Command, Stdio};
+
+use iced_layershell::reexport::Anchor;
+use serde::Deserialize;
+use tracing::{info, warn};
+
+use crate::core::config::{OutputTarget, UiConfig};
+use crate::ui::config;
+
+/// Where the launcher surface goes on its output.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Placement {
+    pub size: (u32, u32),
+    pub anchor: Anchor,
+    /// Top, right, bottom, left
+    pub margin: (i32, i32, i32, i32),
+}
+
+impl Placement {
+    /// Placement on `output`, applying any override configured for it.
+    pub fn for_output(cfg: &UiConfig, output: Option<&str>) -> Self {
+        let overrides = output
+            .and_then(|name| cfg.outputs.get(name))
+            .copied()
+            .unwrap_or_default();
+
+        let size = (
+            overrides.width.unwrap_or(config::WINDOW_WIDTH),
+            overrides.height.unwrap_or(config::WINDOW_HEIGHT),
+        );
+
+        // Anchoring to both opposite edges with a fixed size is what centers
+        // the surface on that axis. An offset anchors to the one edge instead.
+        let mut anchor = Anchor::empty();
+        let mut margin = (0, 0, 0, 0);
+        match overrides.x {
+            Some(x) => {
+                anchor |= Anchor::Left;
+                margin.3 = x;
+            }
+            None => anchor |= Anchor::Left | Anchor::Right,
+        }
+        match overrides.y {
+            Some(y) => {
+                anchor |= Anchor::Top;
+                margin.0 = y;
+            }
+            None => anchor |= Anchor::Top | Anchor::Bottom,
+        }
+
+        Self {
+            size,
+            anchor,
+            margin,
+        }
+    }
+}
+
+/// Resolve a target to an output name. `None` means the compositor picks.
+pub fn resolve(target: &OutputTarget) -> Option<String> {
+    let resolved = match target {
+        OutputTarget::Active => return None,
+        OutputTarget::Named(name) => return Some(name.clone()),
+        OutputTarget::Focused => focused_output(),
+        OutputTarget::Cursor => cursor_output().or_else(focused_output),
+    };
+
+    match &resolved {
+        Some(name) => info!("Resolved output {target} to {name}"),
+        None => warn!("Could not resolve output {target}, letting the compositor pick"),
+    }
+
+    resolved
+}
+
+/// The output a surface that isn't bound to one shows up on, which
+/// compositors make the focused one. It's only asked for when there are
+/// `[ui.outputs]` overrides that could apply to it.
+pub fn unbound_output(cfg: &UiConfig) -> Option<String> {
+    if cfg.outputs.is_empty() {
+        return None;
+    }
+
+    focused_output()
+}
+
+fn focused_output() -> Option<String> {
+    match Compositor::detect()? {
+        Compositor::Hyprland => hyprland_monitors()?
+            .into_iter()
+            .find(|monitor| monitor.focused)
+            .map(|monitor| monitor.name),
+        Compositor::Sway => {
+            #[derive(Deserialize)]
+            struct Output {
+                name: String,
+                focused: bool,
+            }
+
+            command_json::<Vec<Output>>("swaymsg", &["-t", "get_outputs", "-r"])?
+                .into_iter()
+                .find(|output| output.focused)
+                .map(|output| output.name)
+        }
+        Compositor::Niri => {
+            #[derive(Deserialize)]
+            struct Output {
+                name: String,
+            }
+
+            command_json::<Option<Output>>("niri", &["msg", "--json", "focused-output"])?
+                .map(|output| output.name)
+        }
+    }
+}
+
+/// Only Hyprland exposes the cursor position. Everywhere else this is `None`
+/// and the caller falls back to the focused output.
+fn cursor_output() -> Option<String> {
+    #[derive(Deserialize)]
+    struct Position {
+        x: f64,
+        y: f64,
+    }
+
+    if Compositor::detect()? != Compositor::Hyprland {
+        return None;
+    }
+
+    let cursor = command_json::<Position>("hyprctl", &["cursorpos", "-j"])?;
+
+    hyprland_monitors()?
+        .into_iter()
+        .find(|monitor| {
+            // Positions are in layout coordinates, sizes in physical pixels.
+            let width = monitor.width as f64 / monitor.scale;
+            let height = monitor.height as f64 / monitor.scale;
+            let x = monitor.x as f64;
+            let y = monitor.y as f64;
+
+            (x..x + width).contains(&cursor.x) && (y..y + height).contains(&cursor.y)
+        })
+        .map(|monitor| monitor.name)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Compositor {
+    Hyprland,
+    Sway,
+    Niri,
+}
+
+impl Compositor {
+    /// Each compositor exports its IPC socket to the session, which is a
+    /// more reliable tell than `XDG_CURRENT_DESKTOP`.
+    fn detect() -> Option<Self> {
+        let set = |var: &str| std::env::var_os(var).is_some();
+
+        if set("HYPRLAND_INSTANCE_SIGNATURE") {
+            Some(Self::Hyprland)
+        } else if set("SWAYSOCK") {
+            Some(Self::Sway)
+        } else if set("NIRI_SOCKET") {
+            Some(Self::Niri)
+        } else {
+            None
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct HyprlandMonitor {
+    name: String,
+    focused: bool,
+    x: i32,
+    y: i32,
+    width: u32,
+    height: u32,
+    scale: f64,
+}
+
+fn hyprland_monitors() -> Option<Vec<HyprlandMonitor>> {
+    command_json("hyprctl", &["monitors", "-j"])
+}
+
+fn command_json<T: serde::de::DeserializeOwned>(program: &str, args: &[&str]) -> Option<T> {
+    let output = Command::new(program)
+        .args(args)
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output()
+        .inspect_err(|e| warn!("Could not run {program}: {e}"))
+        .ok()?;
+
+    if !output.status.success() {
+        warn!("{program} exited with {}", output.status);
+        return None;
+    }
+
+    serde_json::from_slice(&output.stdout)
+        .inspect_err(|e| warn!("Unexpected output from {program}: {e}"))
+        .ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::config::OutputOverride;
+    use crate::core::keymap::Keymap;
+    use std::collections::HashMap;
+
+    fn ui_config(outputs: &[(&str, OutputOverride)]) -> UiConfig {
+        UiConfig {
+            search_debounce_ms: 0,
+            output: OutputTarget::Active,
+            outputs: outputs
+                .iter()
+                .map(|(name, output)| (name.to_string(), *output))
+                .collect::<HashMap<_, _>>(),
+            preview: false,
+            keymap: Keymap::default(),
+        }
+    }
+
+    #[test]
+    fn centers_without_an_override() {
+        let cfg = ui_config(&[(
+            "DP-1",
+            OutputOverride {
+                x: Some(10),
+                ..OutputOverride::default()
+            },
+        )]);
+        let centered = Placement {
+            size: (config::WINDOW_WIDTH, config::WINDOW_HEIGHT),
+            anchor: Anchor::Top | Anchor::Bottom | Anchor::Left | Anchor::Right,
+            margin: (0, 0, 0, 0),
+        };
+
+        assert_eq!(Placement::for_output(&cfg, None), centered);
+        assert_eq!(Placement::for_output(&cfg, Some("HDMI-A-1")), centered);
+    }
+
+    #[test]
+    fn offsets_anchor_to_the_top_left() {
+        let cfg = ui_config(&[
+            (
+                "DP-1",
+                OutputOverride {
+                    width: Some(1000),
+                    x: Some(40),
+                    ..OutputOverride::default()
+                },
+            ),
+            (
+                "DP-2",
+                OutputOverride {
+                    height: Some(300),
+                    y: Some(120),
+                    ..OutputOverride::default()
+                },
+            ),
+            (
+                "DP-3",
+                OutputOverride {
+                    x: Some(40),
+                    y: Some(120),
+                    ..OutputOverride::default()
+                },
+            ),
+        ]);
+
+        assert_eq!(
+            Placement::for_output(&cfg, Some("DP-1")),
+            Placement {
+                size: (1000, config::WINDOW_HEIGHT),
+                anchor: Anchor::Left | Anchor::Top | Anchor::Bottom,
+                margin: (0, 0, 0, 40),
+            }
+        );
+        assert_eq!(
+            Placement::for_output(&cfg, Some("DP-2")),
+            Placement {
+                size: (config::WINDOW_WIDTH, 300),
+                anchor: Anchor::Left | Anchor::Right | Anchor::Top,
+                margin: (120, 0, 0, 0),
+            }
+        );
+        assert_eq!(
+            Placement::for_output(&cfg, Some("DP-3")),
+            Placement {
+                size: (config::WINDOW_WIDTH, config::WINDOW_HEIGHT),
+                anchor: Anchor::Left | Anchor::Top,
+                margin: (120, 0, 0, 40),
+            }
+        );
+    }
+}
@@ -0,0 +1,104 @@
+//! Keeping track of the search in flight, so results from one that was
+//! overtaken by a newer query never replace the newer results.
+
+use iced::task;
+
+/// Numbers searches in the order they're sent. Only results tagged with the
+/// latest number are shown, and sending a search aborts the one before it.
+#[derive(Debug, Default)]
+pub struct SearchSequence {
+    latest: u64,
+    /// Handle to the search in flight. Dropping it aborts the search.
+    in_flight: Option<task::Handle>,
+}
+
+impl SearchSequence {
+    /// Start a search, returning the number to tag its results with.
+    pub fn start(&mut self) -> u64 {
+        self.latest += 1;
+        self.latest
+    }
+
+    /// Keep the handle of the search just sent. Replacing the previous one
+    /// drops it, which aborts that search if it's still running.
+    pub fn track(&mut self, handle: task::Handle) {
+        self.in_flight = Some(handle.abort_on_drop());
+    }
+
+    /// Whether results tagged `seq` are the latest ones and should be
+    /// shown. Stale results are dropped and leave the search in flight be.
+    pub fn finish(&mut self, seq: u64) -> bool {
+        if seq != self.latest {
+            return false;
+        }
+
+        self.in_flight = None;
+        true
+    }
+
+    /// Whether results tagged `seq` are the latest ones, for showing some of
+    /// them while the rest are still coming. The search stays in flight.
+    pub fn is_latest(&self, seq: u64) -> bool {
+        seq == self.latest
+    }
+
+    /// Whether a search is in flight
+    pub fn is_loading(&self) -> bool {
+        self.in_flight.is_some()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use iced::Task;
+
+    fn search() -> task::Handle {
+        let (_task, handle) = Task::future(async {}).abortable();
+        handle
+    }
+
+    #[test]
+    fn drops_results_overtaken_by_a_newer_search() {
+        let mut searches = SearchSequence::default();
+
+        let first = searches.start();
+        let first_handle = search();
+        searches.track(first_handle.clone());
+        let second = searches.start();
+        searches.track(search());
+
+        assert!(first_handle.is_aborted());
+        assert!(!searches.finish(first));
+        assert!(searches.is_loading());
+
+        assert!(searches.finish(second));
+        assert!(!searches.is_loading());
+    }
+
+    #[test]
+    fn keeps_searching_after_showing_partial_results() {
+        let mut searches = SearchSequence::default();
+
+        let first = searches.start();
+        let second = searches.start();
+        let handle = search();
+        searches.track(handle.clone());
+
+        assert!(!searches.is_latest(first));
+        assert!(searches.is_latest(second));
+        assert!(searches.is_loading());
+        assert!(!handle.is_aborted());
+
+        assert!(searches.finish(second));
+        assert!(!searches.is_loading());
+    }
+
+    #[test]
+    fn takes_the_initial_results_before_any_search() {
+        let mut searches = SearchSequence::default();
+
+        assert!(searches.finish(0));
+        assert!(!searches.is_loading());
+    }
+}
@@ -30,4 +30,10 @@ impl WaycastTheme {
     pub fn icon_color() -> Color {
         Color::WHITE
     }
+
+    /// Characters that matched the search query. Has to stay readable on
+    /// both the selected (light) and unselected (dark) row backgrounds.
+    pub fn match_highlight_color() -> Color {
+        Color::from_rgb8(0xF5, 0x8A, 0x07)
+    }
 }